@@ -0,0 +1,367 @@
+use std::{sync::Arc, time::Instant};
+
+use super::profiles;
+use crate::{
+    drivers::{active_driver, active_profile, bind_placeholders, driver_for, ResultSink},
+    helpers::{errors::ApiResult, session::Session},
+};
+use log::debug;
+use rustql_types::{
+    alter_index_ddl, split_statements, transaction_control, used_database, AlterIndexRequest,
+    ApiError, ApiResponse, CancelResult, ClientHello, DefinitionRequest, DescribeTableRequest,
+    ErrorCategory, ExportGraphRequest, GraphExport, LoadReferencesRequest, LoadTableRequest,
+    QueryChunk, QuerySummary, Request, Response, RowReferences, RunQueryRequest,
+    SchemaGraphRequest, ServerHello, TableData, TableFilter, TransactionControl, PROTOCOL_VERSION,
+};
+use tokio::{sync::mpsc::UnboundedSender, task};
+
+pub type ActionResult = ApiResult<Response>;
+
+// Referencing rows sent per foreign key, their total is counted in full
+static REFERENCE_ROWS: usize = 5;
+
+// Sends a response ahead of the final one, e.g. a batch of query rows
+pub type Progress<'a> = &'a dyn Fn(Response);
+
+// Handlers block on the database, so each request runs on the blocking pool
+// letting a slow query run alongside the rest of the session's requests.
+// Responses go out through `responses`, some requests are answered more than once
+pub async fn run_action(
+    id: u64,
+    request: Request,
+    session: Arc<Session>,
+    responses: UnboundedSender<String>,
+) {
+    let action = request.action();
+    let progress = responses.clone();
+    let progress = move |response| {
+        progress.send(send_response(Some(id), response)).ok();
+    };
+
+    let response = match task::spawn_blocking(move || dispatch(request, &session, &progress)).await
+    {
+        Ok(response) => response,
+        Err(err) => Err(ApiError::new(ErrorCategory::Internal, err.to_string())),
+    };
+
+    let response = match response {
+        Ok(response) => send_response(Some(id), response),
+        Err(err) => {
+            debug!("{} failed: {}", action, err);
+            send_error(err.with_action(action), Some(id))
+        }
+    };
+    responses.send(response).ok();
+}
+
+fn dispatch(request: Request, session: &Session, progress: Progress) -> ActionResult {
+    match request {
+        Request::Init(hello) => init(hello, session),
+        _ if !session.is_initialised() => Err(ApiError::new(
+            ErrorCategory::Protocol,
+            "Init must be sent before any other request",
+        )),
+        Request::LoadTables => load_tables(session),
+        Request::RunQuery(query) => run_query(query, session, progress),
+        Request::LoadTable(table) => load_table(table, session),
+        Request::DescribeTable(table) => describe_table(table, session),
+        Request::AlterIndex(index) => alter_index(index, session),
+        Request::LoadReferences(row) => load_references(row, session),
+        Request::LoadSchemaGraph(schema) => load_schema_graph(schema, session),
+        Request::ExportSchemaGraph(export) => export_schema_graph(export, session),
+        Request::LoadDefinition(object) => load_definition(object, session),
+        Request::Cancel => cancel(session),
+        Request::SetAutocommit(autocommit) => set_autocommit(autocommit, session),
+        Request::Commit => end_transaction(true, session),
+        Request::Rollback => end_transaction(false, session),
+        Request::ListProfiles => profiles::list_profiles(),
+        Request::CreateProfile(profile) => profiles::create_profile(profile),
+        Request::UpdateProfile(update) => profiles::update_profile(update, session),
+        Request::DeleteProfile(profile) => profiles::delete_profile(profile, session),
+        Request::TestProfile(profile) => profiles::test_profile(profile, session),
+        Request::Connect(profile) => profiles::connect(profile, session),
+    }
+}
+
+pub fn server_hello() -> ServerHello {
+    ServerHello {
+        protocol_version: PROTOCOL_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+fn init(hello: ClientHello, session: &Session) -> ActionResult {
+    if hello.protocol_version != PROTOCOL_VERSION {
+        return Err(ApiError::new(
+            ErrorCategory::Protocol,
+            format!(
+                "Incompatible client: api speaks protocol version {} but the client sent version {}",
+                PROTOCOL_VERSION, hello.protocol_version
+            ),
+        ));
+    }
+
+    session.set_initialised();
+    Ok(Response::Init(server_hello()))
+}
+
+pub fn send_error(err: ApiError, id: Option<u64>) -> String {
+    send_response(id, Response::Error(err))
+}
+
+pub fn send_response(id: Option<u64>, response: Response) -> String {
+    match serde_json::to_string(&ApiResponse { id, response }) {
+        Ok(res) => res,
+        Err(err) => send_error(ApiError::new(ErrorCategory::Internal, err.to_string()), id),
+    }
+}
+
+pub fn load_tables(session: &Session) -> ActionResult {
+    let databases = active_driver(session)?.list_databases()?;
+    Ok(Response::Databases(databases))
+}
+
+pub fn load_table(request: LoadTableRequest, session: &Session) -> ActionResult {
+    let table = active_driver(session)?.load_table(request)?;
+    Ok(Response::TableLoaded(table))
+}
+
+// Runs the statements of the script in order, each answered with its own rows and result.
+// With autocommit off a transaction is begun before the first statement when none is open
+pub fn run_query(request: RunQueryRequest, session: &Session, progress: Progress) -> ActionResult {
+    let profile = active_profile(session)?;
+    let statements = split_statements(profile.driver, &request.query);
+    if statements.is_empty() {
+        return Err(ApiError::request("The query has no statements to run"));
+    }
+
+    // every statement is bound before any runs, a missing value fails the script up front
+    let mut positional = 0;
+    let statements = statements
+        .into_iter()
+        .map(|statement| {
+            let (bound, params) =
+                bind_placeholders(profile.driver, &statement, &request.params, &mut positional)?;
+            Ok((statement, bound, params))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    let driver = driver_for(profile.clone(), session);
+    let started = Instant::now();
+    let mut transaction = session.transaction();
+    let mut summary = QuerySummary {
+        statements: 0,
+        failed: 0,
+        elapsed_ms: 0,
+        transaction: transaction.clone(),
+        db: request.db.clone(),
+    };
+
+    for (index, (query, bound, params)) in statements.into_iter().enumerate() {
+        let control = transaction_control(profile.driver, &query);
+        let used_db = used_database(profile.driver, &query);
+        // a `USE` earlier in the script changed the database the rest runs in
+        let request = RunQueryRequest {
+            db: summary.db.clone(),
+            ..request.clone()
+        };
+        if !transaction.autocommit && !transaction.open && control.is_none() {
+            driver.run_query(
+                RunQueryRequest {
+                    query: String::from("BEGIN"),
+                    ..request.clone()
+                },
+                vec![],
+                &mut ResultSink::collect(),
+            )?;
+            transaction.open = true;
+            session.set_transaction(transaction.clone());
+        }
+
+        let table = TableData {
+            db_name: summary.db.clone(),
+            table_name: request.table.clone(),
+            ..Default::default()
+        };
+        let mut sink =
+            ResultSink::stream(table, session.max_rows, profile.limits.clone(), |chunk| {
+                progress(Response::QueryRows(QueryChunk {
+                    statement: index,
+                    ..chunk
+                }))
+            });
+
+        let statement_started = Instant::now();
+        let outcome = driver.run_query(
+            RunQueryRequest {
+                query: bound,
+                ..request.clone()
+            },
+            params,
+            &mut sink,
+        );
+
+        // a limit stopping the result is the cause of whatever the driver reports after
+        let mut result = sink.finish();
+        result.error = result.error.or_else(|| outcome.err());
+        result.statement = index;
+        result.query = query;
+        result.elapsed_ms = statement_started.elapsed().as_millis() as u64;
+
+        // the pinned connection is only dropped when it was lost, the server then
+        // rolled back the transaction open on it
+        let connection_error =
+            matches!(&result.error, Some(err) if err.category == ErrorCategory::Connection);
+        if connection_error && transaction.open && !session.is_pinned() {
+            transaction.open = false;
+            if let Some(err) = result.error.as_mut() {
+                err.message = format!(
+                    "{}. The connection was lost, its open transaction was rolled back",
+                    err.message.trim_end_matches('.')
+                );
+            }
+        }
+        match (&result.error, control) {
+            (None, Some(TransactionControl::Begin)) => transaction.open = true,
+            (None, Some(TransactionControl::End)) => transaction.open = false,
+            _ => {}
+        }
+        session.set_transaction(transaction.clone());
+        if let (None, Some(db)) = (&result.error, used_db) {
+            summary.db = db;
+        }
+
+        let cancelled =
+            matches!(&result.error, Some(err) if err.category == ErrorCategory::Cancelled);
+        let failed = result.error.is_some();
+        progress(Response::StatementFinished(result));
+
+        summary.statements += 1;
+        summary.elapsed_ms = started.elapsed().as_millis() as u64;
+        summary.transaction = transaction.clone();
+        if failed {
+            summary.failed += 1;
+        }
+        if cancelled {
+            return Ok(Response::QueryCancelled(summary));
+        }
+        if failed && !request.continue_on_error {
+            break;
+        }
+    }
+
+    Ok(Response::QueryFinished(summary))
+}
+
+pub fn describe_table(request: DescribeTableRequest, session: &Session) -> ActionResult {
+    let description = active_driver(session)?.describe_table(request)?;
+    Ok(Response::TableDescribed(description))
+}
+
+// For each key pointing at the table, the rows whose columns hold the row's values
+pub fn load_references(request: LoadReferencesRequest, session: &Session) -> ActionResult {
+    let driver = active_driver(session)?;
+    let values = request.values;
+    let description = driver.describe_table(DescribeTableRequest {
+        db: request.db,
+        schema: request.schema,
+        table: request.table,
+    })?;
+
+    let mut references = vec![];
+    for foreign_key in description.referenced_by {
+        let filters = foreign_key
+            .referenced_columns
+            .iter()
+            .zip(&foreign_key.columns)
+            .map(|(referenced, column)| {
+                Some(TableFilter::equal(column.clone(), values.get(referenced)?))
+            })
+            .collect::<Option<Vec<TableFilter>>>();
+        let filters = match filters {
+            Some(filters) => filters,
+            None => continue,
+        };
+
+        let data = driver.load_table(LoadTableRequest {
+            db: foreign_key.table.db.clone(),
+            schema: foreign_key.table.schema.clone(),
+            table: foreign_key.table.table.clone(),
+            offset: 0,
+            page_size: REFERENCE_ROWS,
+            sort: None,
+            filters,
+        })?;
+        references.push(RowReferences { foreign_key, data });
+    }
+
+    Ok(Response::References(references))
+}
+
+// Runs the statement the client previewed, built from the same request
+pub fn alter_index(request: AlterIndexRequest, session: &Session) -> ActionResult {
+    let profile = active_profile(session)?;
+    let statement = alter_index_ddl(profile.driver, &request);
+    let driver = driver_for(profile, session);
+    driver.execute_ddl(&request.db, &statement)?;
+
+    let description = driver.describe_table(DescribeTableRequest {
+        db: request.db,
+        schema: request.schema,
+        table: request.table,
+    })?;
+    Ok(Response::TableDescribed(description))
+}
+
+pub fn load_schema_graph(request: SchemaGraphRequest, session: &Session) -> ActionResult {
+    let graph = active_driver(session)?.schema_graph(request)?;
+    Ok(Response::SchemaGraph(graph))
+}
+
+// Renders the graph the api reads now, so an export never lags behind the schema
+pub fn export_schema_graph(request: ExportGraphRequest, session: &Session) -> ActionResult {
+    let graph = active_driver(session)?.schema_graph(SchemaGraphRequest {
+        db: request.db,
+        schema: request.schema,
+    })?;
+    let text = graph
+        .focused(request.focus.as_ref())
+        .render(request.format, &request.layout);
+
+    Ok(Response::GraphExported(GraphExport {
+        format: request.format,
+        text,
+    }))
+}
+
+pub fn load_definition(request: DefinitionRequest, session: &Session) -> ActionResult {
+    let definition = active_driver(session)?.object_definition(request)?;
+    Ok(Response::Definition(definition))
+}
+
+pub fn set_autocommit(autocommit: bool, session: &Session) -> ActionResult {
+    let mut transaction = session.transaction();
+    if autocommit && transaction.open {
+        return Err(ApiError::request(
+            "Commit or roll back the open transaction before turning autocommit on",
+        ));
+    }
+
+    transaction.autocommit = autocommit;
+    session.set_transaction(transaction.clone());
+    Ok(Response::Transaction(transaction))
+}
+
+pub fn end_transaction(commit: bool, session: &Session) -> ActionResult {
+    active_driver(session)?.end_transaction(commit)?;
+
+    let mut transaction = session.transaction();
+    transaction.open = false;
+    session.set_transaction(transaction.clone());
+    Ok(Response::Transaction(transaction))
+}
+
+pub fn cancel(session: &Session) -> ActionResult {
+    let cancelled = active_driver(session)?.cancel()?;
+    Ok(Response::Cancelled(CancelResult { cancelled }))
+}
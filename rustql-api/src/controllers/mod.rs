@@ -1 +1,2 @@
-pub mod mysql;
+pub mod actions;
+pub mod profiles;
@@ -0,0 +1,146 @@
+use super::actions::ActionResult;
+use crate::{
+    drivers::driver_for,
+    helpers::{
+        errors::IntoApiResult,
+        profiles::{find_profile, load_profiles, lock_profiles, save_profiles},
+        session::Session,
+    },
+};
+use rustql_types::{
+    ApiError, ConnectionProfile, ProfileName, ProfileTestResult, Response, UpdateProfileRequest,
+};
+
+pub fn list_profiles() -> ActionResult {
+    Ok(listing(load_profiles().into_api()?))
+}
+
+pub fn create_profile(profile: ConnectionProfile) -> ActionResult {
+    let _lock = lock_profiles();
+    let mut profiles = load_profiles().into_api()?;
+
+    if profile.name.trim().is_empty() {
+        return Err(ApiError::request("Profile name cannot be empty"));
+    }
+    if profiles.iter().any(|saved| saved.name == profile.name) {
+        return Err(ApiError::request(format!(
+            "Profile already exists: {}",
+            profile.name
+        )));
+    }
+
+    profiles.push(profile);
+    save_profiles(&profiles).into_api()?;
+
+    Ok(listing(profiles))
+}
+
+pub fn update_profile(update: UpdateProfileRequest, session: &Session) -> ActionResult {
+    let UpdateProfileRequest {
+        original_name,
+        mut profile,
+    } = update;
+    let _lock = lock_profiles();
+    let mut profiles = load_profiles().into_api()?;
+
+    if profile.name.trim().is_empty() {
+        return Err(ApiError::request("Profile name cannot be empty"));
+    }
+    if profile.name != original_name && profiles.iter().any(|saved| saved.name == profile.name) {
+        return Err(ApiError::request(format!(
+            "Profile already exists: {}",
+            profile.name
+        )));
+    }
+
+    let saved = profiles
+        .iter_mut()
+        .find(|saved| saved.name == original_name)
+        .ok_or_else(|| ApiError::request(format!("Profile not found: {}", original_name)))?;
+    // the listing never sends passwords, an empty one keeps the saved password
+    if profile.password.is_empty() {
+        profile.password = saved.password.clone();
+    }
+    *saved = profile.clone();
+    save_profiles(&profiles).into_api()?;
+
+    session.evict_pools(&original_name);
+
+    // keep the open connection in sync with its edited profile, the editor's connection
+    // reopens with the new settings and a transaction left open on it is rolled back
+    if session.profile().map(|active| active.name) == Some(original_name) {
+        session.unpin();
+        session.set_profile(Some(profile));
+    }
+
+    Ok(listing(profiles))
+}
+
+pub fn delete_profile(ProfileName { name }: ProfileName, session: &Session) -> ActionResult {
+    let _lock = lock_profiles();
+    let mut profiles = load_profiles().into_api()?;
+
+    profiles.retain(|saved| saved.name != name);
+    save_profiles(&profiles).into_api()?;
+    session.evict_pools(&name);
+
+    if session.profile().map(|active| active.name) == Some(name) {
+        session.unpin();
+        session.set_profile(None);
+    }
+
+    Ok(listing(profiles))
+}
+
+pub fn test_profile(mut profile: ConnectionProfile, session: &Session) -> ActionResult {
+    let name = profile.name.clone();
+
+    // testing an edited profile without retyping its password
+    if profile.password.is_empty() {
+        if let Some(saved) = find_profile(&name).into_api()? {
+            profile.password = saved.password;
+        }
+    }
+
+    // a failed test is a valid answer, not an api error
+    let result = match driver_for(profile, session).test_connection() {
+        Ok(server) => ProfileTestResult {
+            name,
+            success: true,
+            message: format!("Connected to {}", server),
+        },
+        Err(err) => ProfileTestResult {
+            name,
+            success: false,
+            message: err.message,
+        },
+    };
+
+    Ok(Response::ProfileTested(result))
+}
+
+pub fn connect(ProfileName { name }: ProfileName, session: &Session) -> ActionResult {
+    let profile = find_profile(&name)
+        .into_api()?
+        .ok_or_else(|| ApiError::request(format!("Profile not found: {}", name)))?;
+
+    // fail here rather than on the first table load, this also warms up the pool
+    driver_for(profile.clone(), session).connect()?;
+    // the client connects again on every socket Init, the editor's connection and its
+    // transaction are kept then. Switching profiles rolls back one left open on the previous
+    if session.profile().map(|active| active.name) != Some(name.clone()) {
+        session.unpin();
+    }
+    session.set_profile(Some(profile));
+
+    Ok(Response::Connected(ProfileName { name }))
+}
+
+// Passwords stay on the server, the browser only ever sees them blank
+fn listing(mut profiles: Vec<ConnectionProfile>) -> Response {
+    for profile in &mut profiles {
+        profile.password.clear();
+    }
+
+    Response::Profiles(profiles)
+}
@@ -0,0 +1,463 @@
+use crate::helpers::{errors::ApiResult, session::Session};
+use rustql_types::{
+    find_placeholders, ApiError, ConnectionProfile, Database, DefinitionRequest,
+    DescribeTableRequest, DriverKind, ForeignKey, GraphColumn, GraphTable, LoadTableRequest,
+    ObjectDefinition, ObjectKind, QueryParams, RunQueryRequest, SchemaGraph, SchemaGraphRequest,
+    TableData, TableDescription, TablePage, TableRef, Value,
+};
+
+mod mysql;
+mod postgres;
+mod results;
+mod sqlite;
+
+pub use self::results::ResultSink;
+use self::{mysql::MySqlDriver, postgres::PostgresDriver, sqlite::SqliteDriver};
+
+// Most rows a single LoadTable page may hold
+static MAX_PAGE_SIZE: usize = 1000;
+
+// A database backend. Drivers are built per request from the profile and the
+// session's shared state, their methods block so they run on the blocking pool
+pub trait DatabaseDriver {
+    // opens (or reuses) the session's connections, failing early on bad settings
+    fn connect(&self) -> ApiResult<()>;
+
+    // connects without touching the session and returns a description of the server
+    fn test_connection(&self) -> ApiResult<String>;
+
+    fn list_databases(&self) -> ApiResult<Vec<Database>>;
+
+    fn load_table(&self, request: LoadTableRequest) -> ApiResult<TableData>;
+
+    // passes the rows to `sink` as they arrive, stopping the statement when it is full.
+    // `params` are bound to the driver's own placeholders in `request.query`, None as NULL
+    fn run_query(
+        &self,
+        request: RunQueryRequest,
+        params: Vec<Option<String>>,
+        sink: &mut ResultSink,
+    ) -> ApiResult<()>;
+
+    fn describe_table(&self, request: DescribeTableRequest) -> ApiResult<TableDescription>;
+
+    // every table of the database (or schema) with its columns, and the keys between them
+    fn schema_graph(&self, request: SchemaGraphRequest) -> ApiResult<SchemaGraph>;
+
+    // the statement creating a view, routine, trigger or event
+    fn object_definition(&self, request: DefinitionRequest) -> ApiResult<ObjectDefinition>;
+
+    // runs a schema change in `db` on a pooled connection, outside the editor's transaction
+    fn execute_ddl(&self, db: &str, statement: &str) -> ApiResult<()>;
+
+    // commits or rolls back the transaction open on the session's pinned connection
+    fn end_transaction(&self, commit: bool) -> ApiResult<()>;
+
+    // stops the statements this session is running, returns how many were stopped
+    fn cancel(&self) -> ApiResult<usize>;
+}
+
+pub fn driver_for(profile: ConnectionProfile, session: &Session) -> Box<dyn DatabaseDriver + '_> {
+    match profile.driver {
+        DriverKind::MySql => Box::new(MySqlDriver::new(profile, session)),
+        DriverKind::Postgres => Box::new(PostgresDriver::new(profile, session)),
+        DriverKind::Sqlite => Box::new(SqliteDriver::new(profile, session)),
+    }
+}
+
+pub fn active_profile(session: &Session) -> ApiResult<ConnectionProfile> {
+    session
+        .profile()
+        .ok_or_else(|| ApiError::request("No connection profile selected"))
+}
+
+pub fn active_driver(session: &Session) -> ApiResult<Box<dyn DatabaseDriver + '_>> {
+    Ok(driver_for(active_profile(session)?, session))
+}
+
+// json has no NaN or infinities, serde_json writes them as null which the client can't read
+// back as a number. They go as the text postgres prints them as instead
+fn float_value(value: f32) -> Value {
+    match value.is_finite() {
+        true => Value::Float(value),
+        false => Value::Text(non_finite_text(value.into())),
+    }
+}
+
+fn double_value(value: f64) -> Value {
+    match value.is_finite() {
+        true => Value::Double(value),
+        false => Value::Text(non_finite_text(value)),
+    }
+}
+
+fn non_finite_text(value: f64) -> String {
+    let text = match value {
+        value if value.is_nan() => "NaN",
+        value if value > 0.0 => "Infinity",
+        _ => "-Infinity",
+    };
+    text.to_string()
+}
+
+// The page a LoadTable request asks for, its total still to be counted
+fn requested_page(request: &LoadTableRequest) -> TablePage {
+    TablePage {
+        offset: request.offset,
+        page_size: request.page_size.clamp(1, MAX_PAGE_SIZE),
+        total: 0,
+    }
+}
+
+// Drivers read foreign keys one key part per row, a constraint's parts together and in order
+fn merge_foreign_keys(parts: Vec<ForeignKey>) -> Vec<ForeignKey> {
+    let mut keys: Vec<ForeignKey> = vec![];
+    for part in parts {
+        match keys.last_mut() {
+            Some(key) if key.name == part.name && key.table == part.table => {
+                key.columns.extend(part.columns);
+                key.referenced_columns.extend(part.referenced_columns);
+            }
+            _ => keys.push(part),
+        }
+    }
+    keys
+}
+
+// Splits the keys into those of `table` and those referencing it,
+// a self-referencing key ends up in both
+fn split_foreign_keys(
+    parts: Vec<ForeignKey>,
+    table: &TableRef,
+) -> (Vec<ForeignKey>, Vec<ForeignKey>) {
+    let mut keys = merge_foreign_keys(parts);
+
+    let referenced_by = keys
+        .iter()
+        .filter(|key| key.referenced_table == *table)
+        .cloned()
+        .collect();
+    keys.retain(|key| key.table == *table);
+
+    (keys, referenced_by)
+}
+
+// Drivers read columns one per row by table name, a table's columns together and in order
+fn graph_tables(columns: Vec<(String, GraphColumn)>) -> Vec<GraphTable> {
+    let mut tables: Vec<GraphTable> = vec![];
+    for (name, column) in columns {
+        match tables.last_mut() {
+            Some(table) if table.name == name => table.columns.push(column),
+            _ => tables.push(GraphTable {
+                name,
+                columns: vec![column],
+            }),
+        }
+    }
+    tables
+}
+
+// The definition read for the request, None when the object is gone or its body is hidden
+fn found_definition(
+    request: DefinitionRequest,
+    definition: Option<String>,
+) -> ApiResult<ObjectDefinition> {
+    let definition = definition.ok_or_else(|| {
+        ApiError::request(format!(
+            "No definition of {} {} is visible in {}",
+            request.kind.to_string().to_lowercase(),
+            request.name,
+            request.db
+        ))
+    })?;
+
+    Ok(ObjectDefinition {
+        db: request.db,
+        schema: request.schema,
+        name: request.name,
+        kind: request.kind,
+        definition,
+    })
+}
+
+fn unsupported_kind(driver: DriverKind, kind: ObjectKind) -> ApiError {
+    ApiError::request(format!(
+        "{} has no {}",
+        driver,
+        kind.plural().to_lowercase()
+    ))
+}
+
+// Statements for one page of a table, sharing the filter's bound values
+struct TableQuery {
+    select: String,
+    count: String,
+    params: Vec<String>,
+}
+
+// `table` is already quoted and qualified for the driver, columns are quoted here and
+// filter values become placeholders so nothing from the request is spliced into the sql
+fn table_query(
+    driver: DriverKind,
+    table: &str,
+    request: &LoadTableRequest,
+    page: &TablePage,
+) -> ApiResult<TableQuery> {
+    let mut params = vec![];
+    let conditions = request
+        .filters
+        .iter()
+        .map(|filter| {
+            let column = match (driver, filter.operator.is_pattern()) {
+                (DriverKind::Postgres, true) => {
+                    format!("{}::text", driver.quote_identifier(&filter.column))
+                }
+                _ => driver.quote_identifier(&filter.column),
+            };
+
+            Ok(match (filter.operator.takes_value(), filter.binary) {
+                (true, true) => format!(
+                    "{} {} {}",
+                    column,
+                    filter.operator,
+                    hex_literal(driver, &filter.value)?
+                ),
+                (true, false) => {
+                    params.push(filter.value.clone());
+                    format!(
+                        "{} {} {}",
+                        column,
+                        filter.operator,
+                        placeholder(driver, params.len())
+                    )
+                }
+                (false, _) => format!("{} {}", column, filter.operator),
+            })
+        })
+        .collect::<ApiResult<Vec<String>>>()?;
+
+    let filter = match conditions.is_empty() {
+        true => String::new(),
+        false => format!(" WHERE {}", conditions.join(" AND ")),
+    };
+    let order = match &request.sort {
+        Some(sort) => format!(
+            " ORDER BY {} {}",
+            driver.quote_identifier(&sort.column),
+            if sort.descending { "DESC" } else { "ASC" }
+        ),
+        None => String::new(),
+    };
+
+    Ok(TableQuery {
+        select: format!(
+            "SELECT * FROM {}{}{} LIMIT {} OFFSET {}",
+            table, filter, order, page.page_size, page.offset
+        ),
+        count: format!("SELECT COUNT(*) FROM {}{}", table, filter),
+        params,
+    })
+}
+
+// Binary values are written into the query rather than bound, the drivers bind text.
+// Checked to be hex digits first, the server rejects an odd count of them
+fn hex_literal(driver: DriverKind, hex: &str) -> ApiResult<String> {
+    if !hex.chars().all(|next| next.is_ascii_hexdigit()) {
+        return Err(ApiError::request(format!("Not a hex value: {}", hex)));
+    }
+
+    Ok(match driver {
+        DriverKind::Postgres => format!("decode('{}', 'hex')", hex),
+        DriverKind::MySql | DriverKind::Sqlite => format!("X'{}'", hex),
+    })
+}
+
+// Reads the same on every backend
+fn end_statement(commit: bool) -> &'static str {
+    match commit {
+        true => "COMMIT",
+        false => "ROLLBACK",
+    }
+}
+
+// 1-based position of the bound value
+fn placeholder(driver: DriverKind, position: usize) -> String {
+    match driver {
+        DriverKind::Postgres => format!("${}", position),
+        DriverKind::MySql | DriverKind::Sqlite => String::from("?"),
+    }
+}
+
+// Rewrites the placeholders of a statement to the driver's own and returns the values to bind
+// in order. `positional` is the last positional value taken, by earlier statements too; a numbered
+// placeholder takes its own value and a plain `?` the one after the last taken
+pub fn bind_placeholders(
+    driver: DriverKind,
+    statement: &str,
+    params: &QueryParams,
+    positional: &mut usize,
+) -> ApiResult<(String, Vec<Option<String>>)> {
+    let mut query = String::with_capacity(statement.len());
+    let mut values = vec![];
+    let mut copied = 0;
+
+    for found in find_placeholders(driver, statement) {
+        let value = match &found.name {
+            Some(name) => params
+                .named
+                .get(name)
+                .ok_or_else(|| ApiError::request(format!("No value for placeholder :{}", name)))?,
+            None => {
+                let number = match found.number {
+                    Some(number) => number,
+                    None => *positional + 1,
+                };
+                *positional = (*positional).max(number);
+                number
+                    .checked_sub(1)
+                    .and_then(|index| params.positional.get(index))
+                    .ok_or_else(|| {
+                        // `?` or `$`
+                        let sigil = &statement[found.start..found.start + 1];
+                        ApiError::request(format!("No value for placeholder {}{}", sigil, number))
+                    })?
+            }
+        };
+        values.push(value.clone());
+
+        query.push_str(&statement[copied..found.start]);
+        query.push_str(&placeholder(driver, values.len()));
+        copied = found.end;
+    }
+    query.push_str(&statement[copied..]);
+
+    Ok((query, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustql_types::ErrorCategory;
+
+    fn params(named: &[(&str, &str)], positional: &[Option<&str>]) -> QueryParams {
+        QueryParams {
+            named: named
+                .iter()
+                .map(|(name, value)| (name.to_string(), Some(value.to_string())))
+                .collect(),
+            positional: positional
+                .iter()
+                .map(|value| value.map(String::from))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn binds_named_placeholders() {
+        let params = params(&[("a", "1"), ("b", "2")], &[]);
+        let mut positional = 0;
+        let (query, values) = bind_placeholders(
+            DriverKind::Postgres,
+            "SELECT :a, :b::int, ':a', :a",
+            &params,
+            &mut positional,
+        )
+        .unwrap();
+
+        assert_eq!(query, "SELECT $1, $2::int, ':a', $3");
+        assert_eq!(
+            values,
+            vec![
+                Some(String::from("1")),
+                Some(String::from("2")),
+                Some(String::from("1"))
+            ]
+        );
+        assert_eq!(positional, 0);
+    }
+
+    #[test]
+    fn binds_positional_placeholders_across_statements() {
+        let params = params(&[("name", "x")], &[Some("1"), None, Some("3")]);
+        let mut positional = 0;
+
+        let (query, values) = bind_placeholders(
+            DriverKind::MySql,
+            "SELECT ?, :name",
+            &params,
+            &mut positional,
+        )
+        .unwrap();
+        assert_eq!(query, "SELECT ?, ?");
+        assert_eq!(
+            values,
+            vec![Some(String::from("1")), Some(String::from("x"))]
+        );
+
+        let (query, values) = bind_placeholders(
+            DriverKind::Sqlite,
+            "SELECT ?, '?', ?",
+            &params,
+            &mut positional,
+        )
+        .unwrap();
+        assert_eq!(query, "SELECT ?, '?', ?");
+        assert_eq!(values, vec![None, Some(String::from("3"))]);
+        assert_eq!(positional, 3);
+    }
+
+    #[test]
+    fn fails_on_missing_values() {
+        let params = params(&[], &[Some("1")]);
+        let mut positional = 0;
+
+        let err = bind_placeholders(
+            DriverKind::MySql,
+            "SELECT :missing",
+            &params,
+            &mut positional,
+        )
+        .unwrap_err();
+        assert_eq!(err.category, ErrorCategory::Request);
+
+        let err = bind_placeholders(DriverKind::MySql, "SELECT ?, ?", &params, &mut positional)
+            .unwrap_err();
+        assert_eq!(err.message, "No value for placeholder ?2");
+
+        let err = bind_placeholders(DriverKind::Postgres, "SELECT $0", &params, &mut positional)
+            .unwrap_err();
+        assert_eq!(err.message, "No value for placeholder $0");
+    }
+
+    #[test]
+    fn binds_numbered_placeholders_by_position() {
+        let params = params(&[("id", "7")], &[Some("1"), Some("2"), Some("3")]);
+        let mut positional = 0;
+
+        // a plain `?` takes the value after the highest one used so far, as sqlite does
+        let (query, values) = bind_placeholders(
+            DriverKind::Sqlite,
+            "SELECT ?2, ?, ?2, $id",
+            &params,
+            &mut positional,
+        )
+        .unwrap();
+        assert_eq!(query, "SELECT ?, ?, ?, ?");
+        let texts = |values: &[&str]| -> Vec<Option<String>> {
+            values.iter().map(|value| Some(value.to_string())).collect()
+        };
+        assert_eq!(values, texts(&["2", "3", "2", "7"]));
+        assert_eq!(positional, 3);
+
+        let mut positional = 0;
+        let (query, values) = bind_placeholders(
+            DriverKind::Postgres,
+            "SELECT $2::int + $1, $2",
+            &params,
+            &mut positional,
+        )
+        .unwrap();
+        assert_eq!(query, "SELECT $1::int + $2, $3");
+        assert_eq!(values, texts(&["2", "1", "2"]));
+    }
+}
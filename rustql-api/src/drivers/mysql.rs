@@ -0,0 +1,701 @@
+use super::{
+    double_value, end_statement, float_value, found_definition, graph_tables, merge_foreign_keys,
+    requested_page, split_foreign_keys, table_query, DatabaseDriver, ResultSink,
+};
+use crate::helpers::{
+    errors::{ApiResult, IntoApiResult},
+    pools::opts_from,
+    session::Session,
+};
+use mysql::{
+    consts::{ColumnFlags, ColumnType::*},
+    prelude::{FromValue, Protocol, Queryable},
+    Column, Conn, PooledConn, QueryResult, Row,
+};
+use rustql_types::{
+    ColumnDescription, ColumnMeta, ConnectionProfile, Database, DefinitionRequest,
+    DescribeTableRequest, DriverKind, ForeignKey, GraphColumn, IndexColumn, IndexDescription,
+    LoadTableRequest, ObjectDefinition, ObjectKind, RunQueryRequest, SchemaGraph,
+    SchemaGraphRequest, SchemaObject, StatementWarning, TableData, TableDescription, TableField,
+    TableRef, Value,
+};
+use std::collections::BTreeMap;
+
+static SHOW_WARNINGS: &str = "SHOW WARNINGS";
+
+// Routines, triggers and events of every database, views are listed along with the tables
+static LIST_OBJECTS: [&str; 3] = [
+    "SELECT ROUTINE_SCHEMA, ROUTINE_NAME, ROUTINE_TYPE FROM information_schema.ROUTINES
+    ORDER BY ROUTINE_NAME",
+    "SELECT TRIGGER_SCHEMA, TRIGGER_NAME, 'TRIGGER' FROM information_schema.TRIGGERS
+    ORDER BY TRIGGER_NAME",
+    "SELECT EVENT_SCHEMA, EVENT_NAME, 'EVENT' FROM information_schema.EVENTS
+    ORDER BY EVENT_NAME",
+];
+
+static DESCRIBE_COLUMNS: &str =
+    "SELECT COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY, COLUMN_DEFAULT, EXTRA,
+        GENERATION_EXPRESSION, COLLATION_NAME, COLUMN_COMMENT
+    FROM information_schema.COLUMNS
+    WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+    ORDER BY ORDINAL_POSITION";
+
+// Keys of the table and keys pointing at it, one row per key part
+static DESCRIBE_FOREIGN_KEYS: &str =
+    "SELECT CONSTRAINT_NAME, TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME,
+        REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME
+    FROM information_schema.KEY_COLUMN_USAGE
+    WHERE REFERENCED_TABLE_NAME IS NOT NULL
+        AND ((TABLE_SCHEMA = ? AND TABLE_NAME = ?)
+            OR (REFERENCED_TABLE_SCHEMA = ? AND REFERENCED_TABLE_NAME = ?))
+    ORDER BY TABLE_SCHEMA, TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION";
+
+static GRAPH_COLUMNS: &str = "SELECT TABLE_NAME, COLUMN_NAME, COLUMN_TYPE, COLUMN_KEY
+    FROM information_schema.COLUMNS
+    WHERE TABLE_SCHEMA = ?
+    ORDER BY TABLE_NAME, ORDINAL_POSITION";
+
+// Keys between the database's own tables
+static GRAPH_FOREIGN_KEYS: &str = "SELECT CONSTRAINT_NAME, TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME,
+        REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME
+    FROM information_schema.KEY_COLUMN_USAGE
+    WHERE TABLE_SCHEMA = ? AND REFERENCED_TABLE_SCHEMA = ?
+    ORDER BY TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION";
+
+// a row of DESCRIBE_FOREIGN_KEYS or GRAPH_FOREIGN_KEYS
+type ForeignKeyRow = (String, String, String, String, String, String, String);
+
+// a row of DESCRIBE_COLUMNS
+type ColumnRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    String,
+);
+
+pub struct MySqlDriver<'a> {
+    profile: ConnectionProfile,
+    session: &'a Session,
+}
+
+impl<'a> MySqlDriver<'a> {
+    pub fn new(profile: ConnectionProfile, session: &'a Session) -> Self {
+        MySqlDriver { profile, session }
+    }
+
+    fn conn(&self) -> ApiResult<PooledConn> {
+        self.session.mysql_pools.get_conn(&self.profile).into_api()
+    }
+
+    // Runs the statement on the connection with id `id`, registered as running so cancel can find it.
+    // Statements with bound values run as prepared statements, the rest go through the text
+    // protocol.
+    // Warnings are read back on the same connection once the result is dropped
+    fn query_into(
+        &self,
+        conn: &mut impl Queryable,
+        id: u64,
+        query: String,
+        params: Vec<Option<String>>,
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let running = self.session.mysql_running.track(&self.profile.name, id);
+
+        let stop = || self.kill_queries(&[id]).map(|_| ());
+        running.run(
+            self.profile.limits.max_execution_time(),
+            || self.kill_queries(&[id]),
+            || {
+                let warnings = match params.is_empty() {
+                    true => read_rows(conn.query_iter(query).into_api()?, sink, stop)?,
+                    false => read_rows(conn.exec_iter(query, params).into_api()?, sink, stop)?,
+                };
+
+                if warnings > 0 {
+                    let warnings = conn
+                        .query_map(SHOW_WARNINGS, |(level, code, message)| StatementWarning {
+                            level,
+                            code,
+                            message,
+                        })
+                        .into_api()?;
+                    sink.set_warnings(warnings);
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // The connections are busy running the statements, kill them from a fresh one
+    fn kill_queries(&self, ids: &[u64]) -> ApiResult<usize> {
+        let mut conn = Conn::new(opts_from(&self.profile)).into_api()?;
+        let killed = ids
+            .iter()
+            .filter(|id| conn.query_drop(format!("KILL QUERY {}", id)).is_ok())
+            .count();
+
+        Ok(killed)
+    }
+}
+
+impl DatabaseDriver for MySqlDriver<'_> {
+    fn connect(&self) -> ApiResult<()> {
+        self.conn().map(|_| ())
+    }
+
+    fn test_connection(&self) -> ApiResult<String> {
+        let mut conn = Conn::new(opts_from(&self.profile)).into_api()?;
+        conn.query_drop("SELECT 1").into_api()?;
+
+        let (major, minor, patch) = conn.server_version();
+        Ok(format!("MySQL {}.{}.{}", major, minor, patch))
+    }
+
+    fn list_databases(&self) -> ApiResult<Vec<Database>> {
+        let mut conn = self.conn()?;
+
+        let db_names: Vec<String> = conn.query("SHOW DATABASES").into_api()?;
+        let mut objects = list_objects(&mut conn);
+        let databases = db_names
+            .into_iter()
+            .map(|name| {
+                let relations: Vec<(String, String)> = conn
+                    .query(format!(
+                        "SHOW FULL TABLES FROM {}",
+                        DriverKind::MySql.quote_identifier(&name)
+                    ))
+                    .unwrap_or_default();
+                let (views, tables): (Vec<_>, Vec<_>) = relations
+                    .into_iter()
+                    .partition(|(_, table_type)| table_type == "VIEW");
+
+                let views = views.into_iter().map(|(name, _)| SchemaObject {
+                    name,
+                    kind: ObjectKind::View,
+                });
+                Database {
+                    tables: tables.into_iter().map(|(name, _)| name).collect(),
+                    objects: views
+                        .chain(objects.remove(&name).unwrap_or_default())
+                        .collect(),
+                    name,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(databases)
+    }
+
+    fn load_table(&self, request: LoadTableRequest) -> ApiResult<TableData> {
+        let table = format!(
+            "{}.{}",
+            DriverKind::MySql.quote_identifier(&request.db),
+            DriverKind::MySql.quote_identifier(&request.table)
+        );
+        let mut page = requested_page(&request);
+        let query = table_query(DriverKind::MySql, &table, &request, &page)?;
+        page.total = self
+            .conn()?
+            .exec_first::<u64, _, _>(query.count, query.params.clone())
+            .into_api()?
+            .unwrap_or_default() as usize;
+
+        let mut sink = ResultSink::collect();
+        let params = query.params.into_iter().map(Some).collect();
+        let mut conn = self.conn()?;
+        let id = conn.connection_id() as u64;
+        self.query_into(&mut conn, id, query.select, params, &mut sink)?;
+        let (count, table_fields) = sink.into_fields();
+
+        Ok(TableData {
+            db_name: request.db,
+            table_name: request.table,
+            count,
+            table_fields,
+            page: Some(page),
+        })
+    }
+
+    fn run_query(
+        &self,
+        request: RunQueryRequest,
+        params: Vec<Option<String>>,
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let key = (self.profile.name.clone(), None);
+        let in_transaction = self.session.transaction().open;
+
+        self.session.mysql_pinned.with(
+            key,
+            in_transaction,
+            || Conn::new(opts_from(&self.profile)).into_api(),
+            |conn| {
+                // unqualified names resolve against the selected database, set on every query
+                // as the script may have switched to another with `USE`
+                if !request.db.is_empty() {
+                    conn.query_drop(format!(
+                        "USE {}",
+                        DriverKind::MySql.quote_identifier(&request.db)
+                    ))
+                    .into_api()?;
+                }
+
+                let id = conn.connection_id() as u64;
+                self.query_into(conn, id, request.query, params, sink)
+            },
+        )
+    }
+
+    fn describe_table(&self, request: DescribeTableRequest) -> ApiResult<TableDescription> {
+        let mut conn = self.conn()?;
+        let rows: Vec<ColumnRow> = conn
+            .exec(DESCRIBE_COLUMNS, (&request.db, &request.table))
+            .into_api()?;
+        let indexes = describe_indexes(&mut conn, &request)?;
+        let parts = conn
+            .exec_map(
+                DESCRIBE_FOREIGN_KEYS,
+                (&request.db, &request.table, &request.db, &request.table),
+                foreign_key_from,
+            )
+            .into_api()?;
+        let (foreign_keys, referenced_by) = split_foreign_keys(
+            parts,
+            &TableRef {
+                db: request.db.clone(),
+                schema: None,
+                table: request.table.clone(),
+            },
+        );
+
+        let columns = rows
+            .into_iter()
+            .map(
+                |(
+                    name,
+                    sql_type,
+                    nullable,
+                    key,
+                    default,
+                    extra,
+                    generation,
+                    collation,
+                    comment,
+                )| {
+                    ColumnDescription {
+                        name,
+                        sql_type,
+                        nullable: nullable == "YES",
+                        primary_key: key == "PRI",
+                        default,
+                        extra: match generation.filter(|generation| !generation.is_empty()) {
+                            Some(generation) => format!("{} AS ({})", extra, generation),
+                            None => extra,
+                        },
+                        key,
+                        collation,
+                        comment,
+                    }
+                },
+            )
+            .collect();
+
+        Ok(TableDescription {
+            db_name: request.db,
+            table_name: request.table,
+            columns,
+            indexes,
+            foreign_keys,
+            referenced_by,
+        })
+    }
+
+    fn object_definition(&self, request: DefinitionRequest) -> ApiResult<ObjectDefinition> {
+        // the statement's keyword and the column of its output holding the definition
+        let (keyword, column) = match request.kind {
+            ObjectKind::View => ("VIEW", "Create View"),
+            ObjectKind::Procedure => ("PROCEDURE", "Create Procedure"),
+            ObjectKind::Function => ("FUNCTION", "Create Function"),
+            ObjectKind::Trigger => ("TRIGGER", "SQL Original Statement"),
+            ObjectKind::Event => ("EVENT", "Create Event"),
+        };
+        let row: Option<Row> = self
+            .conn()?
+            .query_first(format!(
+                "SHOW CREATE {} {}.{}",
+                keyword,
+                DriverKind::MySql.quote_identifier(&request.db),
+                DriverKind::MySql.quote_identifier(&request.name)
+            ))
+            .into_api()?;
+
+        // a routine's body is NULL for users who did not define it and may not read others'
+        let definition = row.and_then(|row| show_field(&row, column));
+        found_definition(request, definition)
+    }
+
+    fn schema_graph(&self, request: SchemaGraphRequest) -> ApiResult<SchemaGraph> {
+        let mut conn = self.conn()?;
+        let columns = conn
+            .exec_map(
+                GRAPH_COLUMNS,
+                (&request.db,),
+                |(table, name, sql_type, key): (String, String, String, String)| {
+                    let column = GraphColumn {
+                        name,
+                        sql_type,
+                        primary_key: key == "PRI",
+                    };
+                    (table, column)
+                },
+            )
+            .into_api()?;
+        let parts = conn
+            .exec_map(
+                GRAPH_FOREIGN_KEYS,
+                (&request.db, &request.db),
+                foreign_key_from,
+            )
+            .into_api()?;
+
+        Ok(SchemaGraph {
+            db: request.db,
+            schema: None,
+            tables: graph_tables(columns),
+            foreign_keys: merge_foreign_keys(parts),
+        })
+    }
+
+    fn execute_ddl(&self, _db: &str, statement: &str) -> ApiResult<()> {
+        self.conn()?.query_drop(statement).into_api()
+    }
+
+    fn end_transaction(&self, commit: bool) -> ApiResult<()> {
+        self.session
+            .mysql_pinned
+            .with_current(|conn| conn.query_drop(end_statement(commit)).into_api())
+            .map(|_| ())
+    }
+
+    fn cancel(&self) -> ApiResult<usize> {
+        let ids = self.session.mysql_running.cancel(&self.profile.name);
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.kill_queries(&ids)
+    }
+}
+
+// charset id reported for binary strings and blobs
+static BINARY_CHARSET: u16 = 63;
+
+static FLAG_NAMES: &[(ColumnFlags, &str)] = &[
+    (ColumnFlags::NOT_NULL_FLAG, "NOT_NULL"),
+    (ColumnFlags::PRI_KEY_FLAG, "PRIMARY_KEY"),
+    (ColumnFlags::UNIQUE_KEY_FLAG, "UNIQUE_KEY"),
+    (ColumnFlags::MULTIPLE_KEY_FLAG, "MULTIPLE_KEY"),
+    (ColumnFlags::UNSIGNED_FLAG, "UNSIGNED"),
+    (ColumnFlags::ZEROFILL_FLAG, "ZEROFILL"),
+    (ColumnFlags::BINARY_FLAG, "BINARY"),
+    (ColumnFlags::AUTO_INCREMENT_FLAG, "AUTO_INCREMENT"),
+    (ColumnFlags::ENUM_FLAG, "ENUM"),
+    (ColumnFlags::SET_FLAG, "SET"),
+    (ColumnFlags::TIMESTAMP_FLAG, "TIMESTAMP"),
+    (ColumnFlags::NO_DEFAULT_VALUE_FLAG, "NO_DEFAULT_VALUE"),
+    (ColumnFlags::ON_UPDATE_NOW_FLAG, "ON_UPDATE_NOW"),
+];
+
+// SHOW INDEX rather than information_schema.STATISTICS, its columns are read by name since
+// Visible and Expression only exist from MySQL 8 and MariaDB reports Ignored instead
+fn describe_indexes(
+    conn: &mut PooledConn,
+    request: &DescribeTableRequest,
+) -> ApiResult<Vec<IndexDescription>> {
+    let rows: Vec<Row> = conn
+        .query(format!(
+            "SHOW INDEX FROM {} FROM {}",
+            DriverKind::MySql.quote_identifier(&request.table),
+            DriverKind::MySql.quote_identifier(&request.db)
+        ))
+        .into_api()?;
+
+    let mut indexes: Vec<IndexDescription> = vec![];
+    for row in rows {
+        let name: String = show_field(&row, "Key_name").unwrap_or_default();
+        let expression: Option<String> = show_field(&row, "Expression");
+        let column = IndexColumn {
+            expression: expression.is_some(),
+            name: expression
+                .or_else(|| show_field(&row, "Column_name"))
+                .unwrap_or_default(),
+            sub_part: show_field(&row, "Sub_part"),
+            descending: show_field::<String>(&row, "Collation").as_deref() == Some("D"),
+        };
+
+        // the key parts of an index come one per row, in order
+        match indexes.last_mut() {
+            Some(index) if index.name == name => index.columns.push(column),
+            _ => indexes.push(IndexDescription {
+                unique: show_field::<i64>(&row, "Non_unique") == Some(0),
+                primary: name == "PRIMARY",
+                index_type: show_field(&row, "Index_type").unwrap_or_default(),
+                cardinality: show_field(&row, "Cardinality"),
+                visible: show_field::<String>(&row, "Visible").as_deref() != Some("NO")
+                    && show_field::<String>(&row, "Ignored").as_deref() != Some("YES"),
+                columns: vec![column],
+                name,
+            }),
+        }
+    }
+
+    Ok(indexes)
+}
+
+// One key part of a foreign key
+fn foreign_key_from(
+    (name, db, table, column, referenced_db, referenced_table, referenced_column): ForeignKeyRow,
+) -> ForeignKey {
+    ForeignKey {
+        name,
+        table: TableRef {
+            db,
+            schema: None,
+            table,
+        },
+        columns: vec![column],
+        referenced_table: TableRef {
+            db: referenced_db,
+            schema: None,
+            table: referenced_table,
+        },
+        referenced_columns: vec![referenced_column],
+    }
+}
+
+// Objects other than views by database, a list the user may not read is left out
+fn list_objects(conn: &mut PooledConn) -> BTreeMap<String, Vec<SchemaObject>> {
+    let mut objects: BTreeMap<String, Vec<SchemaObject>> = BTreeMap::new();
+    for query in LIST_OBJECTS.iter() {
+        let rows: Vec<(String, String, String)> = conn.query(*query).unwrap_or_default();
+        for (db, name, kind) in rows {
+            let kind = match kind.as_str() {
+                "PROCEDURE" => ObjectKind::Procedure,
+                "FUNCTION" => ObjectKind::Function,
+                "TRIGGER" => ObjectKind::Trigger,
+                _ => ObjectKind::Event,
+            };
+            objects
+                .entry(db)
+                .or_default()
+                .push(SchemaObject { name, kind });
+        }
+    }
+    objects
+}
+
+// A column of a SHOW statement by name, None for a NULL or a column this server does not report
+fn show_field<T: FromValue>(row: &Row, name: &str) -> Option<T> {
+    row.get_opt::<Option<T>, _>(name)
+        .and_then(Result::ok)
+        .flatten()
+}
+
+// Dropping a result reads whatever the server still sends,
+// so a statement stopped early is killed before the result goes
+// Returns the number of warnings the statement raised
+fn read_rows<P: Protocol>(
+    mut result: QueryResult<'_, '_, '_, P>,
+    sink: &mut ResultSink,
+    stop: impl FnOnce() -> ApiResult<()>,
+) -> ApiResult<u16> {
+    let columns = result.columns().as_ref().to_vec();
+    sink.set_fields(columns.iter().map(field_from).collect());
+
+    for row in result.by_ref() {
+        let values = row.into_api()?.unwrap();
+        let values = values
+            .into_iter()
+            .zip(&columns)
+            .map(|(value, column)| value_from(value, column));
+
+        if !sink.push_row(values) {
+            return stop().map(|_| 0);
+        }
+    }
+
+    // only known once the rows are read
+    sink.set_status(
+        columns.is_empty().then(|| result.affected_rows()),
+        result.last_insert_id(),
+        Some(result.info_str().into_owned()),
+    );
+    Ok(result.warnings())
+}
+
+fn field_from(column: &Column) -> TableField {
+    let flags = column.flags();
+
+    TableField {
+        name: column.name_str().to_string(),
+        sql_type: sql_type_name(column),
+        meta: ColumnMeta {
+            flags: FLAG_NAMES
+                .iter()
+                .filter(|(flag, _)| flags.contains(*flag))
+                .map(|(_, name)| name.to_string())
+                .collect(),
+            nullable: Some(!flags.contains(ColumnFlags::NOT_NULL_FLAG)),
+            primary_key: flags.contains(ColumnFlags::PRI_KEY_FLAG),
+            unsigned: flags.contains(ColumnFlags::UNSIGNED_FLAG),
+            charset: Some(charset_name(column.character_set())),
+        },
+        values: vec![],
+    }
+}
+
+fn sql_type_name(column: &Column) -> String {
+    let binary = column.character_set() == BINARY_CHARSET;
+    let flags = column.flags();
+
+    let name = match column.column_type() {
+        MYSQL_TYPE_TINY => "tinyint",
+        MYSQL_TYPE_SHORT => "smallint",
+        MYSQL_TYPE_INT24 => "mediumint",
+        MYSQL_TYPE_LONG => "int",
+        MYSQL_TYPE_LONGLONG => "bigint",
+        MYSQL_TYPE_FLOAT => "float",
+        MYSQL_TYPE_DOUBLE => "double",
+        MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => "decimal",
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => "date",
+        MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => "time",
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_DATETIME2 => "datetime",
+        MYSQL_TYPE_TIMESTAMP | MYSQL_TYPE_TIMESTAMP2 => "timestamp",
+        MYSQL_TYPE_YEAR => "year",
+        MYSQL_TYPE_JSON => "json",
+        MYSQL_TYPE_BIT => "bit",
+        MYSQL_TYPE_GEOMETRY => "geometry",
+        MYSQL_TYPE_NULL => "null",
+        MYSQL_TYPE_ENUM => "enum",
+        MYSQL_TYPE_SET => "set",
+        // enums and sets are sent as strings, flagged
+        MYSQL_TYPE_STRING if flags.contains(ColumnFlags::ENUM_FLAG) => "enum",
+        MYSQL_TYPE_STRING if flags.contains(ColumnFlags::SET_FLAG) => "set",
+        MYSQL_TYPE_STRING if binary => "binary",
+        MYSQL_TYPE_STRING => "char",
+        MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING if binary => "varbinary",
+        MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING => "varchar",
+        MYSQL_TYPE_TINY_BLOB | MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB | MYSQL_TYPE_BLOB
+            if binary =>
+        {
+            "blob"
+        }
+        MYSQL_TYPE_TINY_BLOB | MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB | MYSQL_TYPE_BLOB => {
+            "text"
+        }
+        other => {
+            return format!("{:?}", other)
+                .trim_start_matches("MYSQL_TYPE_")
+                .to_lowercase()
+        }
+    };
+
+    name.to_string()
+}
+
+// Names for the common charset ids, see SHOW COLLATION
+fn charset_name(id: u16) -> String {
+    match id {
+        8 | 47 | 48 | 49 => String::from("latin1"),
+        11 | 65 => String::from("ascii"),
+        33 | 83 | 192..=215 => String::from("utf8"),
+        45 | 46 | 224..=247 | 255..=323 => String::from("utf8mb4"),
+        63 => String::from("binary"),
+        id => id.to_string(),
+    }
+}
+
+fn value_from(value: mysql::Value, column: &Column) -> Value {
+    match value {
+        mysql::Value::NULL => Value::Null,
+        mysql::Value::Int(value) => Value::Int(value),
+        mysql::Value::UInt(value) => Value::UInt(value),
+        mysql::Value::Float(value) => float_value(value),
+        mysql::Value::Double(value) => double_value(value),
+        mysql::Value::Date(year, month, day, 0, 0, 0, 0)
+            if matches!(column.column_type(), MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE) =>
+        {
+            Value::Date(format!("{:04}-{:02}-{:02}", year, month, day))
+        }
+        mysql::Value::Date(year, month, day, hour, minute, second, micros) => {
+            Value::DateTime(format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}",
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                fraction(micros)
+            ))
+        }
+        mysql::Value::Time(negative, days, hours, minutes, seconds, micros) => {
+            Value::Time(format!(
+                "{}{:02}:{:02}:{:02}{}",
+                if negative { "-" } else { "" },
+                days * 24 + u32::from(hours),
+                minutes,
+                seconds,
+                fraction(micros)
+            ))
+        }
+        mysql::Value::Bytes(bytes) => value_from_text(bytes, column),
+    }
+}
+
+fn fraction(micros: u32) -> String {
+    match micros {
+        0 => String::new(),
+        micros => format!(".{:06}", micros),
+    }
+}
+
+// The text protocol sends every value as bytes, the column type says what they hold
+fn value_from_text(bytes: Vec<u8>, column: &Column) -> Value {
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => return Value::Bytes(err.into_bytes()),
+    };
+    let unsigned = column.flags().contains(ColumnFlags::UNSIGNED_FLAG);
+
+    let parsed = match column.column_type() {
+        MYSQL_TYPE_TINY | MYSQL_TYPE_SHORT | MYSQL_TYPE_INT24 | MYSQL_TYPE_LONG
+        | MYSQL_TYPE_LONGLONG | MYSQL_TYPE_YEAR => match unsigned {
+            true => text.parse().ok().map(Value::UInt),
+            false => text.parse().ok().map(Value::Int),
+        },
+        MYSQL_TYPE_FLOAT => text.parse().ok().map(float_value),
+        MYSQL_TYPE_DOUBLE => text.parse().ok().map(double_value),
+        MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => Some(Value::Decimal(text.clone())),
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => Some(Value::Date(text.clone())),
+        MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => Some(Value::Time(text.clone())),
+        MYSQL_TYPE_DATETIME
+        | MYSQL_TYPE_DATETIME2
+        | MYSQL_TYPE_TIMESTAMP
+        | MYSQL_TYPE_TIMESTAMP2 => Some(Value::DateTime(text.clone())),
+        MYSQL_TYPE_JSON => Some(Value::Json(text.clone())),
+        MYSQL_TYPE_BIT | MYSQL_TYPE_GEOMETRY => Some(Value::Bytes(text.clone().into_bytes())),
+        _ if column.character_set() == BINARY_CHARSET => {
+            Some(Value::Bytes(text.clone().into_bytes()))
+        }
+        _ => None,
+    };
+
+    parsed.unwrap_or(Value::Text(text))
+}
@@ -0,0 +1,1272 @@
+use super::{
+    double_value, end_statement, float_value, found_definition, graph_tables, merge_foreign_keys,
+    non_finite_text, requested_page, split_foreign_keys, table_query, unsupported_kind,
+    DatabaseDriver, ResultSink,
+};
+use crate::helpers::{
+    errors::{ApiResult, IntoApiResult},
+    pools::{pg_config_from, PgConn},
+    session::{PgPinned, Session},
+};
+use bytes::BytesMut;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use postgres::{
+    fallible_iterator::FallibleIterator,
+    types::{to_sql_checked, Format, FromSql, IsNull, Kind, ToSql, Type},
+    Client, Column, NoTls, Row,
+};
+use rust_decimal::Decimal;
+use rustql_types::{
+    first_keyword, ApiError, ColumnDescription, ColumnMeta, ConnectionProfile, Database,
+    DefinitionRequest, DescribeTableRequest, DriverKind, ErrorCategory, ForeignKey, GraphColumn,
+    IndexColumn, IndexDescription, LoadTableRequest, ObjectDefinition, ObjectKind, RunQueryRequest,
+    Schema, SchemaGraph, SchemaGraphRequest, SchemaObject, TableData, TableDescription, TableField,
+    TableRef, Value,
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    error::Error,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+use uuid::Uuid;
+
+static DEFAULT_DB: &str = "postgres";
+static DEFAULT_SCHEMA: &str = "public";
+
+static LIST_DATABASES: &str = "SELECT datname::text FROM pg_database
+    WHERE datallowconn AND NOT datistemplate
+    ORDER BY datname";
+
+static LIST_TABLES: &str =
+    "SELECT table_schema::text, table_name::text FROM information_schema.tables
+    WHERE table_schema NOT IN ('pg_catalog', 'information_schema') AND table_type <> 'VIEW'
+    ORDER BY table_schema, table_name";
+
+// Views (materialized ones too), routines and triggers, one row per name. Routines added by
+// extensions are left out, overloads and triggers of the same name on several tables are
+// shown once and defined together
+static LIST_OBJECTS: &str = "SELECT * FROM (
+        SELECT n.nspname::text, c.relname::text, 'View' AS kind
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind IN ('v', 'm')
+        UNION
+        SELECT n.nspname::text, p.proname::text,
+            CASE p.prokind WHEN 'p' THEN 'Procedure' ELSE 'Function' END
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE p.prokind IN ('f', 'p')
+            AND NOT EXISTS (
+                SELECT 1 FROM pg_depend d WHERE d.objid = p.oid AND d.deptype = 'e'
+            )
+        UNION
+        SELECT n.nspname::text, t.tgname::text, 'Trigger'
+        FROM pg_trigger t
+        JOIN pg_class c ON c.oid = t.tgrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE NOT t.tgisinternal
+    ) objects (schema, name, kind)
+    WHERE schema NOT IN ('pg_catalog', 'information_schema') AND schema NOT LIKE 'pg\\_%'
+    ORDER BY schema, kind, name";
+
+static DEFINE_VIEW: &str = "SELECT CASE c.relkind WHEN 'm' THEN 'CREATE MATERIALIZED VIEW '
+        ELSE 'CREATE VIEW ' END
+        || quote_ident(n.nspname) || '.' || quote_ident(c.relname) || ' AS' || E'\\n'
+        || pg_get_viewdef(c.oid, true)
+    FROM pg_class c
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = $1 AND c.relname = $2 AND c.relkind IN ('v', 'm')";
+
+static DEFINE_ROUTINE: &str =
+    "SELECT string_agg(pg_get_functiondef(p.oid) || ';', E'\\n\\n' ORDER BY p.oid)
+    FROM pg_proc p
+    JOIN pg_namespace n ON n.oid = p.pronamespace
+    WHERE n.nspname = $1 AND p.proname = $2 AND p.prokind = $3";
+
+static DEFINE_TRIGGER: &str =
+    "SELECT string_agg(pg_get_triggerdef(t.oid, true) || ';', E'\\n' ORDER BY c.relname)
+    FROM pg_trigger t
+    JOIN pg_class c ON c.oid = t.tgrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE n.nspname = $1 AND t.tgname = $2 AND NOT t.tgisinternal";
+
+// information_schema uses domain types the client can't decode, so everything is cast to text
+static DESCRIBE_COLUMNS: &str =
+    "SELECT c.column_name::text, c.data_type::text, c.is_nullable::text,
+        c.column_default::text, c.is_identity::text,
+        COALESCE((
+            SELECT MIN(CASE tc.constraint_type WHEN 'PRIMARY KEY' THEN 'PRI' ELSE 'UNI' END)
+            FROM information_schema.table_constraints tc
+            JOIN information_schema.key_column_usage k
+                ON k.constraint_name = tc.constraint_name
+                AND k.table_schema = tc.table_schema
+                AND k.table_name = tc.table_name
+            WHERE tc.constraint_type IN ('PRIMARY KEY', 'UNIQUE')
+                AND tc.table_schema = c.table_schema
+                AND tc.table_name = c.table_name
+                AND k.column_name = c.column_name
+        ), ''),
+        c.is_generated::text, c.generation_expression::text, c.collation_name::text,
+        COALESCE(col_description(
+            (quote_ident(c.table_schema) || '.' || quote_ident(c.table_name))::regclass,
+            c.ordinal_position::int
+        ), '')
+    FROM information_schema.columns c
+    WHERE c.table_schema = $1 AND c.table_name = $2
+    ORDER BY c.ordinal_position";
+
+// One row per key part. Expressions have no attribute and come back from pg_get_indexdef,
+// reltuples is -1 for a table never analyzed
+static DESCRIBE_INDEXES: &str = "SELECT i.relname::text, ix.indisunique, ix.indisprimary,
+        am.amname::text, ix.indisvalid,
+        CASE WHEN i.reltuples < 0 THEN NULL ELSE i.reltuples::bigint END,
+        a.attname::text, pg_get_indexdef(ix.indexrelid, k.n, true),
+        (ix.indoption[k.n - 1] & 1) = 1
+    FROM pg_index ix
+    JOIN pg_class t ON t.oid = ix.indrelid
+    JOIN pg_namespace ns ON ns.oid = t.relnamespace
+    JOIN pg_class i ON i.oid = ix.indexrelid
+    JOIN pg_am am ON am.oid = i.relam
+    CROSS JOIN LATERAL generate_series(1, ix.indnkeyatts) AS k(n)
+    LEFT JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = ix.indkey[k.n - 1]
+    WHERE ns.nspname = $1 AND t.relname = $2
+    ORDER BY ix.indisprimary DESC, i.relname, k.n";
+
+// Foreign keys one row per key part, conkey and confkey hold the paired columns' numbers.
+// Completed with the keys' tables and an order by the queries below
+static FOREIGN_KEY_PARTS: &str = "SELECT c.conname::text, cn.nspname::text, cl.relname::text,
+        a.attname::text, rn.nspname::text, rl.relname::text, ra.attname::text
+    FROM pg_constraint c
+    JOIN pg_class cl ON cl.oid = c.conrelid
+    JOIN pg_namespace cn ON cn.oid = cl.relnamespace
+    JOIN pg_class rl ON rl.oid = c.confrelid
+    JOIN pg_namespace rn ON rn.oid = rl.relnamespace
+    CROSS JOIN LATERAL unnest(c.conkey, c.confkey) WITH ORDINALITY AS k(attnum, refnum, n)
+    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+    JOIN pg_attribute ra ON ra.attrelid = c.confrelid AND ra.attnum = k.refnum
+    WHERE c.contype = 'f'";
+
+// Keys of the table and keys pointing at it
+static DESCRIBE_FOREIGN_KEYS: &str =
+    "AND ((cn.nspname = $1 AND cl.relname = $2) OR (rn.nspname = $1 AND rl.relname = $2))
+    ORDER BY cn.nspname, cl.relname, c.conname, k.n";
+
+// Keys between the schema's own tables
+static GRAPH_FOREIGN_KEYS: &str = "AND cn.nspname = $1 AND rn.nspname = $1
+    ORDER BY cl.relname, c.conname, k.n";
+
+// Tables, views and their kin with their columns, format_type gives types as they were declared
+static GRAPH_COLUMNS: &str = "SELECT t.relname::text, a.attname::text,
+        format_type(a.atttypid, a.atttypmod), COALESCE(a.attnum = ANY(pk.conkey), false)
+    FROM pg_class t
+    JOIN pg_namespace ns ON ns.oid = t.relnamespace
+    JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum > 0 AND NOT a.attisdropped
+    LEFT JOIN pg_constraint pk ON pk.conrelid = t.oid AND pk.contype = 'p'
+    WHERE ns.nspname = $1 AND t.relkind IN ('r', 'p', 'v', 'm', 'f')
+    ORDER BY t.relname, a.attnum";
+
+pub struct PostgresDriver<'a> {
+    profile: ConnectionProfile,
+    session: &'a Session,
+}
+
+impl<'a> PostgresDriver<'a> {
+    pub fn new(profile: ConnectionProfile, session: &'a Session) -> Self {
+        PostgresDriver { profile, session }
+    }
+
+    // Postgres connections are bound to a database, fall back to the profile's one
+    fn client(&self, db: &str) -> ApiResult<PgConn> {
+        self.session
+            .pg_pools
+            .get_client(&self.profile, self.db_or_default(db))
+            .into_api()
+    }
+
+    fn db_or_default<'b>(&'b self, db: &'b str) -> &'b str {
+        match db {
+            "" => self.default_db(),
+            db => db,
+        }
+    }
+
+    fn default_db(&self) -> &str {
+        self.profile.database.as_deref().unwrap_or(DEFAULT_DB)
+    }
+
+    // Runs the statement registered under the connection's cancel token, so cancel can find it
+    fn query_into(
+        &self,
+        client: &mut Client,
+        query: &str,
+        params: &[TextParam],
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let cancel_token = client.cancel_token();
+        let running = self
+            .session
+            .pg_running
+            .track(&self.profile.name, cancel_token.clone());
+
+        running.run(
+            self.profile.limits.max_execution_time(),
+            move || cancel_token.cancel_query(NoTls).map(|_| 1).into_api(),
+            || read_rows(client, query, params, sink),
+        )
+    }
+
+    fn list_schemas(&self, client: &mut Client) -> ApiResult<Vec<Schema>> {
+        let tables = client.query(LIST_TABLES, &[]).into_api()?;
+        // objects are extra, a server too old to list them still lists its tables
+        let objects = client.query(LIST_OBJECTS, &[]).unwrap_or_default();
+
+        let mut schemas: BTreeMap<String, Schema> = BTreeMap::new();
+        for row in tables {
+            schemas
+                .entry(row.get(0))
+                .or_default()
+                .tables
+                .push(row.get(1));
+        }
+        for row in objects {
+            let kind = match row.get(2) {
+                "Procedure" => ObjectKind::Procedure,
+                "Function" => ObjectKind::Function,
+                "Trigger" => ObjectKind::Trigger,
+                _ => ObjectKind::View,
+            };
+            schemas
+                .entry(row.get(0))
+                .or_default()
+                .objects
+                .push(SchemaObject {
+                    name: row.get(1),
+                    kind,
+                });
+        }
+
+        Ok(schemas
+            .into_iter()
+            .map(|(name, schema)| Schema { name, ..schema })
+            .collect())
+    }
+}
+
+impl DatabaseDriver for PostgresDriver<'_> {
+    fn connect(&self) -> ApiResult<()> {
+        self.client(self.default_db()).map(|_| ())
+    }
+
+    fn test_connection(&self) -> ApiResult<String> {
+        let mut client = pg_config_from(&self.profile, self.default_db())
+            .connect(NoTls)
+            .into_api()?;
+        let version: String = client
+            .query_one("SHOW server_version", &[])
+            .into_api()?
+            .get(0);
+
+        Ok(format!("PostgreSQL {}", version))
+    }
+
+    fn list_databases(&self) -> ApiResult<Vec<Database>> {
+        let mut client = self.client(self.default_db())?;
+        let db_names: Vec<String> = client
+            .query(LIST_DATABASES, &[])
+            .into_api()?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let databases = db_names
+            .into_iter()
+            .map(|name| {
+                // the other databases are listed once, over a connection of their own
+                // rather than a pool kept open for each
+                let schemas = match name == self.default_db() {
+                    true => self.list_schemas(&mut client),
+                    false => pg_config_from(&self.profile, &name)
+                        .connect(NoTls)
+                        .into_api()
+                        .and_then(|mut other| self.list_schemas(&mut other)),
+                }
+                .unwrap_or_default();
+                Database {
+                    name,
+                    schemas,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(databases)
+    }
+
+    fn load_table(&self, request: LoadTableRequest) -> ApiResult<TableData> {
+        let schema = request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA);
+        let table = format!(
+            "{}.{}",
+            DriverKind::Postgres.quote_identifier(schema),
+            DriverKind::Postgres.quote_identifier(&request.table)
+        );
+        let mut client = self.client(&request.db)?;
+        let mut page = requested_page(&request);
+        let query = table_query(DriverKind::Postgres, &table, &request, &page)?;
+        let params: Vec<TextParam> = query
+            .params
+            .into_iter()
+            .map(|param| TextParam(Some(param)))
+            .collect();
+        let count_params: Vec<&(dyn ToSql + Sync)> = params
+            .iter()
+            .map(|param| param as &(dyn ToSql + Sync))
+            .collect();
+        page.total = client
+            .query_one(query.count.as_str(), &count_params)
+            .into_api()?
+            .get::<_, i64>(0) as usize;
+
+        let mut sink = ResultSink::collect();
+        self.query_into(&mut client, &query.select, &params, &mut sink)?;
+        let (count, table_fields) = sink.into_fields();
+
+        Ok(TableData {
+            db_name: request.db,
+            table_name: request.table,
+            count,
+            table_fields,
+            page: Some(page),
+        })
+    }
+
+    fn run_query(
+        &self,
+        request: RunQueryRequest,
+        params: Vec<Option<String>>,
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let db = self.db_or_default(&request.db);
+        let key = (self.profile.name.clone(), Some(db.to_string()));
+        let in_transaction = self.session.transaction().open;
+        let params: Vec<TextParam> = params.into_iter().map(TextParam).collect();
+
+        self.session.pg_pinned.with(
+            key,
+            in_transaction,
+            || {
+                pg_config_from(&self.profile, db)
+                    .connect(NoTls)
+                    .map(PgPinned::new)
+                    .into_api()
+            },
+            |pinned| {
+                // unqualified names resolve against the selected schema first. Only set when
+                // another schema is picked, and not in an aborted transaction, the server would
+                // refuse it and the statement ending the transaction would never run
+                let schema = request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA);
+                if pinned.schema.as_deref() != Some(schema) && !pinned.aborted {
+                    pinned
+                        .client
+                        .batch_execute(&format!(
+                            "SET search_path TO {}, public",
+                            DriverKind::Postgres.quote_identifier(schema)
+                        ))
+                        .into_api()?;
+                    pinned.schema = Some(schema.to_string());
+                }
+
+                let result = self.query_into(&mut pinned.client, &request.query, &params, sink);
+                match &result {
+                    // only a statement ending the transaction or rolling back to a savepoint
+                    // succeeds once it is aborted, either way it no longer is
+                    Ok(_) => pinned.aborted = false,
+                    // refused by the server
+                    Err(err) if err.sql_state.is_some() => pinned.aborted = in_transaction,
+                    Err(_) => {}
+                }
+                // a rollback undoes a search_path set since the transaction or savepoint began,
+                // a commit too once the transaction is aborted
+                let keyword = first_keyword(DriverKind::Postgres, &request.query);
+                if matches!(
+                    keyword.as_deref(),
+                    Some("ROLLBACK") | Some("ABORT") | Some("COMMIT") | Some("END")
+                ) {
+                    pinned.schema = None;
+                }
+                result
+            },
+        )
+    }
+
+    fn describe_table(&self, request: DescribeTableRequest) -> ApiResult<TableDescription> {
+        let schema = request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA);
+        let mut client = self.client(&request.db)?;
+        let rows = client
+            .query(DESCRIBE_COLUMNS, &[&schema, &request.table])
+            .into_api()?;
+        let index_rows = client
+            .query(DESCRIBE_INDEXES, &[&schema, &request.table])
+            .into_api()?;
+        let key_rows = client
+            .query(
+                format!("{} {}", FOREIGN_KEY_PARTS, DESCRIBE_FOREIGN_KEYS).as_str(),
+                &[&schema, &request.table],
+            )
+            .into_api()?;
+
+        let parts = key_rows
+            .iter()
+            .map(|row| foreign_key_from(row, &request.db))
+            .collect();
+        let (foreign_keys, referenced_by) = split_foreign_keys(
+            parts,
+            &TableRef {
+                db: request.db.clone(),
+                schema: Some(schema.to_string()),
+                table: request.table.clone(),
+            },
+        );
+
+        let mut indexes: Vec<IndexDescription> = vec![];
+        for row in index_rows {
+            let name: String = row.get(0);
+            let attribute: Option<String> = row.get(6);
+            let column = IndexColumn {
+                expression: attribute.is_none(),
+                name: attribute.unwrap_or_else(|| row.get(7)),
+                sub_part: None,
+                descending: row.get(8),
+            };
+
+            match indexes.last_mut() {
+                Some(index) if index.name == name => index.columns.push(column),
+                _ => indexes.push(IndexDescription {
+                    name,
+                    columns: vec![column],
+                    unique: row.get(1),
+                    primary: row.get(2),
+                    index_type: row.get(3),
+                    visible: row.get(4),
+                    cardinality: row.get::<_, Option<i64>>(5).map(|tuples| tuples as u64),
+                }),
+            }
+        }
+
+        let columns = rows
+            .iter()
+            .map(|row| {
+                let identity: String = row.get(4);
+                let key: String = row.get(5);
+                let generated: String = row.get(6);
+
+                ColumnDescription {
+                    name: row.get(0),
+                    sql_type: row.get(1),
+                    nullable: row.get::<_, String>(2) == "YES",
+                    default: row.get(3),
+                    extra: match (identity.as_str(), generated.as_str()) {
+                        ("YES", _) => String::from("identity"),
+                        (_, "ALWAYS") => format!(
+                            "STORED GENERATED AS ({})",
+                            row.get::<_, Option<String>>(7).unwrap_or_default()
+                        ),
+                        _ => String::new(),
+                    },
+                    primary_key: key == "PRI",
+                    key,
+                    collation: row.get(8),
+                    comment: row.get(9),
+                }
+            })
+            .collect();
+
+        Ok(TableDescription {
+            db_name: request.db,
+            table_name: request.table,
+            columns,
+            indexes,
+            foreign_keys,
+            referenced_by,
+        })
+    }
+
+    fn object_definition(&self, request: DefinitionRequest) -> ApiResult<ObjectDefinition> {
+        let schema = request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA);
+        let mut client = self.client(&request.db)?;
+        let row = match request.kind {
+            ObjectKind::View => client.query_opt(DEFINE_VIEW, &[&schema, &request.name]),
+            ObjectKind::Procedure | ObjectKind::Function => {
+                let prokind = match request.kind {
+                    ObjectKind::Procedure => 'p' as i8,
+                    _ => 'f' as i8,
+                };
+                client.query_opt(DEFINE_ROUTINE, &[&schema, &request.name, &prokind])
+            }
+            ObjectKind::Trigger => client.query_opt(DEFINE_TRIGGER, &[&schema, &request.name]),
+            ObjectKind::Event => return Err(unsupported_kind(DriverKind::Postgres, request.kind)),
+        }
+        .into_api()?;
+
+        let definition = row.and_then(|row| row.get::<_, Option<String>>(0));
+        found_definition(request, definition)
+    }
+
+    fn schema_graph(&self, request: SchemaGraphRequest) -> ApiResult<SchemaGraph> {
+        let schema = request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA);
+        let mut client = self.client(&request.db)?;
+        let columns = client
+            .query(GRAPH_COLUMNS, &[&schema])
+            .into_api()?
+            .iter()
+            .map(|row| {
+                let column = GraphColumn {
+                    name: row.get(1),
+                    sql_type: row.get(2),
+                    primary_key: row.get(3),
+                };
+                (row.get(0), column)
+            })
+            .collect();
+        let parts = client
+            .query(
+                format!("{} {}", FOREIGN_KEY_PARTS, GRAPH_FOREIGN_KEYS).as_str(),
+                &[&schema],
+            )
+            .into_api()?
+            .iter()
+            .map(|row| foreign_key_from(row, &request.db))
+            .collect();
+
+        Ok(SchemaGraph {
+            db: request.db,
+            schema: Some(schema.to_string()),
+            tables: graph_tables(columns),
+            foreign_keys: merge_foreign_keys(parts),
+        })
+    }
+
+    fn execute_ddl(&self, db: &str, statement: &str) -> ApiResult<()> {
+        self.client(db)?.batch_execute(statement).into_api()
+    }
+
+    fn end_transaction(&self, commit: bool) -> ApiResult<()> {
+        self.session
+            .pg_pinned
+            .with_current(|pinned| {
+                pinned
+                    .client
+                    .batch_execute(end_statement(commit))
+                    .into_api()?;
+                pinned.aborted = false;
+                pinned.schema = None;
+                Ok(())
+            })
+            .map(|_| ())
+    }
+
+    fn cancel(&self) -> ApiResult<usize> {
+        let tokens = self.session.pg_running.cancel(&self.profile.name);
+
+        Ok(tokens
+            .iter()
+            .filter(|token| token.cancel_query(NoTls).is_ok())
+            .count())
+    }
+}
+
+// One key part of a FOREIGN_KEY_PARTS row, keys never leave their database
+fn foreign_key_from(row: &Row, db: &str) -> ForeignKey {
+    let table_ref = |schema: String, table: String| TableRef {
+        db: db.to_string(),
+        schema: Some(schema),
+        table,
+    };
+
+    ForeignKey {
+        name: row.get(0),
+        table: table_ref(row.get(1), row.get(2)),
+        columns: vec![row.get(3)],
+        referenced_table: table_ref(row.get(4), row.get(5)),
+        referenced_columns: vec![row.get(6)],
+    }
+}
+
+// Prepared first so the columns are known even when no rows come back
+fn read_rows(
+    client: &mut Client,
+    query: &str,
+    params: &[TextParam],
+    sink: &mut ResultSink,
+) -> ApiResult<()> {
+    let statement = client.prepare(query).into_api()?;
+    let columns = statement.columns();
+    sink.set_fields(columns.iter().map(field_from).collect());
+
+    let mut rows = client.query_raw(&statement, params.iter()).into_api()?;
+    while let Some(row) = rows.next().into_api()? {
+        let values = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| value_from(&row, index, column.type_()))
+            .collect::<ApiResult<Vec<Value>>>()?;
+
+        // dropping the rows discards the rest as they arrive, cancelling the statement
+        // instead would abort a transaction open on the connection
+        if !sink.push_row(values) {
+            return Ok(());
+        }
+    }
+
+    // only known once the rows are read
+    if columns.is_empty() {
+        sink.set_status(rows.rows_affected(), None, None);
+    }
+    Ok(())
+}
+
+// Types read inside arrays as well
+fn array_member(sql_type: &Type) -> bool {
+    matches!(
+        *sql_type,
+        Type::BOOL
+            | Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::NUMERIC
+            | Type::TEXT
+            | Type::VARCHAR
+            | Type::BPCHAR
+            | Type::NAME
+            | Type::UUID
+            | Type::DATE
+            | Type::TIME
+            | Type::TIMESTAMP
+            | Type::TIMESTAMPTZ
+    )
+}
+
+fn field_from(column: &Column) -> TableField {
+    TableField {
+        name: column.name().to_string(),
+        sql_type: column.type_().name().to_string(),
+        meta: ColumnMeta::default(),
+        values: vec![],
+    }
+}
+
+fn value_from(row: &Row, index: usize, sql_type: &Type) -> ApiResult<Value> {
+    let value = match *sql_type {
+        Type::BOOL => typed(row, index, Value::Bool),
+        Type::CHAR => typed(row, index, |value: i8| Value::Int(value.into())),
+        Type::INT2 => typed(row, index, |value: i16| Value::Int(value.into())),
+        Type::INT4 => typed(row, index, |value: i32| Value::Int(value.into())),
+        Type::INT8 => typed(row, index, Value::Int),
+        Type::OID => typed(row, index, |value: u32| Value::UInt(value.into())),
+        Type::FLOAT4 => typed(row, index, float_value),
+        Type::FLOAT8 => typed(row, index, double_value),
+        Type::NUMERIC => typed(row, index, |value: Decimal| {
+            Value::Decimal(value.to_string())
+        }),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => {
+            typed(row, index, Value::Text)
+        }
+        Type::JSON | Type::JSONB => typed(row, index, |value: serde_json::Value| {
+            Value::Json(value.to_string())
+        }),
+        Type::DATE => typed(row, index, |value: NaiveDate| {
+            Value::Date(value.to_string())
+        }),
+        Type::TIME => typed(row, index, |value: NaiveTime| {
+            Value::Time(value.to_string())
+        }),
+        Type::TIMESTAMP => typed(row, index, |value: NaiveDateTime| {
+            Value::DateTime(value.to_string())
+        }),
+        Type::TIMESTAMPTZ => typed(row, index, |value: DateTime<Utc>| {
+            Value::DateTime(value.to_string())
+        }),
+        Type::BYTEA => typed(row, index, Value::Bytes),
+        Type::UUID => typed(row, index, |value: Uuid| Value::Text(value.to_string())),
+        _ => match sql_type.kind() {
+            Kind::Array(member) if array_member(member) => typed(row, index, |array: JsonArray| {
+                Value::Json(array.0.to_string())
+            }),
+            _ => typed(row, index, |raw: RawValue| raw.0),
+        },
+    };
+
+    value.map_err(|err| ApiError::new(ErrorCategory::Conversion, err.to_string()))
+}
+
+fn typed<'a, T: FromSql<'a>>(
+    row: &'a Row,
+    index: usize,
+    to_value: impl FnOnce(T) -> Value,
+) -> Result<Value, postgres::Error> {
+    row.try_get::<_, Option<T>>(index)
+        .map(|value| value.map(to_value).unwrap_or(Value::Null))
+}
+
+// Arrays of the array_member types as json, nested once per dimension
+struct JsonArray(serde_json::Value);
+
+impl<'a> FromSql<'a> for JsonArray {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let member = match ty.kind() {
+            Kind::Array(member) => member,
+            _ => return Err(format!("{} is not an array", ty).into()),
+        };
+
+        // dimensions, a has-nulls flag and the member oid, then a length and lower bound
+        // per dimension, then each member's length (-1 for NULL) and value
+        let mut raw = raw;
+        let dimensions = read_i32(&mut raw)?;
+        read_i32(&mut raw)?;
+        read_i32(&mut raw)?;
+        let lengths = (0..dimensions)
+            .map(|_| {
+                let length = read_i32(&mut raw)?;
+                read_i32(&mut raw)?;
+                Ok(length.max(1) as usize)
+            })
+            .collect::<Result<Vec<usize>, Box<dyn Error + Sync + Send>>>()?;
+
+        let mut values = vec![];
+        while !raw.is_empty() {
+            let length = read_i32(&mut raw)?;
+            if length < 0 {
+                values.push(serde_json::Value::Null);
+                continue;
+            }
+            let length = length as usize;
+            if raw.len() < length {
+                return Err("array member runs past the value".into());
+            }
+            values.push(member_json(member, &raw[..length])?);
+            raw = &raw[length..];
+        }
+
+        for length in lengths.iter().skip(1).rev() {
+            values = values
+                .chunks(*length)
+                .map(|chunk| serde_json::Value::Array(chunk.to_vec()))
+                .collect();
+        }
+        Ok(JsonArray(serde_json::Value::Array(values)))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(ty.kind(), Kind::Array(member) if array_member(member))
+    }
+}
+
+fn read_i32(raw: &mut &[u8]) -> Result<i32, Box<dyn Error + Sync + Send>> {
+    if raw.len() < 4 {
+        return Err("array value ends early".into());
+    }
+    let (int, rest) = raw.split_at(4);
+    *raw = rest;
+    Ok(i32::from_be_bytes(int.try_into()?))
+}
+
+fn member_json(ty: &Type, raw: &[u8]) -> Result<serde_json::Value, Box<dyn Error + Sync + Send>> {
+    Ok(match *ty {
+        Type::BOOL => bool::from_sql(ty, raw)?.into(),
+        Type::INT2 => i16::from_sql(ty, raw)?.into(),
+        Type::INT4 => i32::from_sql(ty, raw)?.into(),
+        Type::INT8 => i64::from_sql(ty, raw)?.into(),
+        Type::FLOAT4 => float_json(f32::from_sql(ty, raw)?.into()),
+        Type::FLOAT8 => float_json(f64::from_sql(ty, raw)?),
+        // as a string, a json number would lose the precision
+        Type::NUMERIC => Decimal::from_sql(ty, raw)?.to_string().into(),
+        Type::UUID => Uuid::from_sql(ty, raw)?.to_string().into(),
+        Type::DATE => NaiveDate::from_sql(ty, raw)?.to_string().into(),
+        Type::TIME => NaiveTime::from_sql(ty, raw)?.to_string().into(),
+        Type::TIMESTAMP => NaiveDateTime::from_sql(ty, raw)?.to_string().into(),
+        Type::TIMESTAMPTZ => DateTime::<Utc>::from_sql(ty, raw)?.to_string().into(),
+        _ => String::from_sql(ty, raw)?.into(),
+    })
+}
+
+fn float_json(value: f64) -> serde_json::Value {
+    match value.is_finite() {
+        true => value.into(),
+        false => non_finite_text(value).into(),
+    }
+}
+
+// The types value_from has no arm for. A few common ones are decoded here, the rest are shown
+// as their binary form, which for enums and text-like types is the text itself
+struct RawValue(Value);
+
+impl<'a> FromSql<'a> for RawValue {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        if let Some(value) = decoded_value(ty, raw)? {
+            return Ok(RawValue(value));
+        }
+
+        Ok(RawValue(match std::str::from_utf8(raw) {
+            Ok(text) => Value::Text(text.to_string()),
+            Err(_) => Value::Bytes(raw.to_vec()),
+        }))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+// Written the way psql shows them
+fn decoded_value(ty: &Type, raw: &[u8]) -> Result<Option<Value>, Box<dyn Error + Sync + Send>> {
+    let mut rest = raw;
+    let value = match *ty {
+        Type::MONEY => Value::Decimal(Decimal::new(read_i64(&mut rest)?, 2).to_string()),
+        Type::INTERVAL => {
+            let micros = read_i64(&mut rest)?;
+            let days = read_i32(&mut rest)?;
+            let months = read_i32(&mut rest)?;
+            Value::Text(interval_text(months, days, micros))
+        }
+        Type::TIMETZ => {
+            let micros = read_i64(&mut rest)?;
+            // seconds west of UTC
+            let offset = -read_i32(&mut rest)?;
+            let sign = if offset < 0 { '-' } else { '+' };
+            let offset = offset.abs();
+            let minutes = match (offset / 60) % 60 {
+                0 => String::new(),
+                minutes => format!(":{:02}", minutes),
+            };
+            Value::Time(format!(
+                "{}{}{:02}{}",
+                clock_text(micros),
+                sign,
+                offset / 3600,
+                minutes
+            ))
+        }
+        Type::INET | Type::CIDR => Value::Text(inet_text(*ty == Type::CIDR, raw)?),
+        Type::MACADDR | Type::MACADDR8 => Value::Text(
+            raw.iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<String>>()
+                .join(":"),
+        ),
+        Type::BIT | Type::VARBIT => {
+            let length = read_i32(&mut rest)?.max(0) as usize;
+            Value::Text(
+                (0..length)
+                    .map(|bit| match rest.get(bit / 8) {
+                        Some(byte) if byte & (0x80 >> (bit % 8)) != 0 => '1',
+                        _ => '0',
+                    })
+                    .collect(),
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(value))
+}
+
+fn read_i64(raw: &mut &[u8]) -> Result<i64, Box<dyn Error + Sync + Send>> {
+    if raw.len() < 8 {
+        return Err("value ends early".into());
+    }
+    let (int, rest) = raw.split_at(8);
+    *raw = rest;
+    Ok(i64::from_be_bytes(int.try_into()?))
+}
+
+fn interval_text(months: i32, days: i32, micros: i64) -> String {
+    let plural = |count: i32, unit: &str, units: &str| match count {
+        1 | -1 => format!("{} {}", count, unit),
+        _ => format!("{} {}", count, units),
+    };
+    let mut parts = vec![];
+    if months / 12 != 0 {
+        parts.push(plural(months / 12, "year", "years"));
+    }
+    if months % 12 != 0 {
+        parts.push(plural(months % 12, "mon", "mons"));
+    }
+    if days != 0 {
+        parts.push(plural(days, "day", "days"));
+    }
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 { "-" } else { "" };
+        parts.push(format!("{}{}", sign, clock_text(micros.abs())));
+    }
+
+    parts.join(" ")
+}
+
+// Time of day from microseconds, the hours aren't wrapped so intervals can go past a day
+fn clock_text(micros: i64) -> String {
+    let seconds = micros / 1_000_000;
+    let fraction = match micros % 1_000_000 {
+        0 => String::new(),
+        fraction => format!(".{:06}", fraction)
+            .trim_end_matches('0')
+            .to_string(),
+    };
+
+    format!(
+        "{:02}:{:02}:{:02}{}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60,
+        fraction
+    )
+}
+
+// Family, netmask bits, a cidr flag and the address length, then the address. The netmask is
+// shown for cidr values and for inet values that aren't a single host
+fn inet_text(cidr: bool, raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let (bits, address, host_bits) = match raw {
+        [_, bits, _, 4, address @ ..] if address.len() == 4 => {
+            let octets: [u8; 4] = address.try_into()?;
+            (*bits, Ipv4Addr::from(octets).to_string(), 32)
+        }
+        [_, bits, _, 16, address @ ..] if address.len() == 16 => {
+            let octets: [u8; 16] = address.try_into()?;
+            (*bits, Ipv6Addr::from(octets).to_string(), 128)
+        }
+        _ => return Err("invalid inet value".into()),
+    };
+
+    Ok(match cidr || bits != host_bits {
+        true => format!("{}/{}", address, bits),
+        false => address,
+    })
+}
+
+// A filter or placeholder value typed in by the user, None for NULL. Postgres infers the
+// parameter's type from the column it is compared with, so the text is parsed into that type here
+#[derive(Debug)]
+struct TextParam(Option<String>);
+
+impl ToSql for TextParam {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        let raw = match self.0.as_deref() {
+            Some(raw) => raw,
+            None => return Ok(IsNull::Yes),
+        };
+        let text = raw.trim();
+
+        match *ty {
+            Type::BOOL => text.parse::<bool>()?.to_sql(ty, out),
+            Type::CHAR => text.parse::<i8>()?.to_sql(ty, out),
+            Type::INT2 => text.parse::<i16>()?.to_sql(ty, out),
+            Type::INT4 => text.parse::<i32>()?.to_sql(ty, out),
+            Type::INT8 => text.parse::<i64>()?.to_sql(ty, out),
+            Type::OID => text.parse::<u32>()?.to_sql(ty, out),
+            Type::FLOAT4 => text.parse::<f32>()?.to_sql(ty, out),
+            Type::FLOAT8 => text.parse::<f64>()?.to_sql(ty, out),
+            Type::NUMERIC => text.parse::<Decimal>()?.to_sql(ty, out),
+            Type::JSON | Type::JSONB => {
+                serde_json::from_str::<serde_json::Value>(text)?.to_sql(ty, out)
+            }
+            Type::DATE => text.parse::<NaiveDate>()?.to_sql(ty, out),
+            Type::TIME => text.parse::<NaiveTime>()?.to_sql(ty, out),
+            Type::TIMESTAMP => parse_timestamp(text)?.to_sql(ty, out),
+            Type::TIMESTAMPTZ => Utc
+                .from_utc_datetime(&parse_timestamp(text)?)
+                .to_sql(ty, out),
+            Type::UUID => text.parse::<Uuid>()?.to_sql(ty, out),
+            Type::BYTEA => match text.strip_prefix("\\x").or_else(|| text.strip_prefix("0x")) {
+                Some(hex) => decode_hex(hex)?.to_sql(ty, out),
+                None => raw.as_bytes().to_sql(ty, out),
+            },
+            // sent in the text format, see encode_format
+            _ => {
+                out.extend_from_slice(raw.as_bytes());
+                Ok(IsNull::No)
+            }
+        }
+    }
+
+    // any other type is parsed by the server from its text form
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, ty: &Type) -> Format {
+        match binary_param(ty) {
+            true => Format::Binary,
+            false => Format::Text,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+// Types TextParam parses itself and sends in the binary format
+fn binary_param(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::BOOL
+            | Type::CHAR
+            | Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::OID
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::NUMERIC
+            | Type::JSON
+            | Type::JSONB
+            | Type::DATE
+            | Type::TIME
+            | Type::TIMESTAMP
+            | Type::TIMESTAMPTZ
+            | Type::UUID
+            | Type::BYTEA
+    )
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Box<dyn Error + Sync + Send>> {
+    let not_hex = || format!("{} is not hex", hex);
+    let digits = hex
+        .chars()
+        .map(|next| next.to_digit(16))
+        .collect::<Option<Vec<u32>>>()
+        .ok_or_else(not_hex)?;
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(not_hex().into());
+    }
+
+    Ok(pairs.map(|pair| (pair[0] * 16 + pair[1]) as u8).collect())
+}
+
+fn parse_timestamp(text: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").or_else(|_| {
+        NaiveDateTime::parse_from_str(&format!("{} 00:00:00", text), "%Y-%m-%d %H:%M:%S")
+    })
+}
+
+// Run against a local server, with RUSTQL_TEST_POSTGRES set to the postgres user's password.
+// Skipped when it isn't set
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::actions::run_query as run_script;
+    use rustql_types::{QueryLimits, QueryParams, Response, TableFields};
+    use std::cell::RefCell;
+
+    // A schema of its own per test, dropped when the test ends
+    struct TestDb {
+        profile: ConnectionProfile,
+        schema: String,
+        session: Session,
+    }
+
+    impl TestDb {
+        fn new(name: &str) -> Option<Self> {
+            let password = std::env::var("RUSTQL_TEST_POSTGRES").ok()?;
+            let profile = ConnectionProfile {
+                name: String::from("test"),
+                driver: DriverKind::Postgres,
+                port: 5432,
+                user: String::from("postgres"),
+                password,
+                ..Default::default()
+            };
+            let schema = format!("rustql_{}_{}", name, std::process::id());
+            pg_config_from(&profile, DEFAULT_DB)
+                .connect(NoTls)
+                .and_then(|mut client| {
+                    client.batch_execute(&format!(
+                        "DROP SCHEMA IF EXISTS {0} CASCADE;
+                        CREATE SCHEMA {0};
+                        CREATE TABLE {0}.pets (id serial PRIMARY KEY, name text NOT NULL);",
+                        schema
+                    ))
+                })
+                .expect("create test schema");
+
+            Some(TestDb {
+                profile,
+                schema,
+                session: Session::new(None),
+            })
+        }
+
+        fn driver(&self) -> PostgresDriver<'_> {
+            PostgresDriver::new(self.profile.clone(), &self.session)
+        }
+
+        fn request(&self, query: &str) -> RunQueryRequest {
+            RunQueryRequest {
+                db: String::new(),
+                schema: Some(self.schema.clone()),
+                table: String::new(),
+                query: query.to_string(),
+                continue_on_error: false,
+                params: QueryParams::default(),
+            }
+        }
+
+        // Runs a script as the editor does, the errors of its statements in order
+        fn script(&self, query: &str) -> Vec<Option<ApiError>> {
+            let errors = RefCell::new(vec![]);
+            self.session.set_profile(Some(self.profile.clone()));
+            run_script(self.request(query), &self.session, &|response| {
+                if let Response::StatementFinished(result) = response {
+                    errors.borrow_mut().push(result.error);
+                }
+            })
+            .unwrap();
+            errors.into_inner()
+        }
+
+        fn run(&self, query: &str) -> ApiResult<(usize, TableFields)> {
+            let mut sink = ResultSink::collect();
+            self.driver()
+                .run_query(self.request(query), vec![], &mut sink)?;
+            Ok(sink.into_fields())
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = pg_config_from(&self.profile, DEFAULT_DB)
+                .connect(NoTls)
+                .and_then(|mut client| {
+                    client.batch_execute(&format!("DROP SCHEMA {} CASCADE", self.schema))
+                });
+        }
+    }
+
+    fn row(fields: &TableFields) -> Vec<Value> {
+        fields.iter().map(|field| field.values[0].clone()).collect()
+    }
+
+    #[test]
+    fn decodes_types_without_a_value_arm() {
+        let db = match TestDb::new("decode") {
+            Some(db) => db,
+            None => return,
+        };
+        let (_, fields) = db
+            .run(
+                "SELECT interval '1 year 2 mons 3 days 04:05:06.5', interval '-00:00:01',
+                    inet '10.0.0.1', inet '10.0.0.1/8', cidr '10.0.0.0/8', inet '::1',
+                    macaddr '08:00:2b:01:02:03', 12.34::money, timetz '04:05:06.25-02:30',
+                    B'10110', 'ok'::name",
+            )
+            .unwrap();
+
+        assert_eq!(
+            row(&fields),
+            vec![
+                Value::Text(String::from("1 year 2 mons 3 days 04:05:06.5")),
+                Value::Text(String::from("-00:00:01")),
+                Value::Text(String::from("10.0.0.1")),
+                Value::Text(String::from("10.0.0.1/8")),
+                Value::Text(String::from("10.0.0.0/8")),
+                Value::Text(String::from("::1")),
+                Value::Text(String::from("08:00:2b:01:02:03")),
+                Value::Decimal(String::from("12.34")),
+                Value::Time(String::from("04:05:06.25-02:30")),
+                Value::Text(String::from("10110")),
+                Value::Text(String::from("ok")),
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_statements_as_written() {
+        let db = match TestDb::new("written") {
+            Some(db) => db,
+            None => return,
+        };
+
+        let (count, fields) = db
+            .run("INSERT INTO pets (name) VALUES ('rex') RETURNING id, name, interval '1 day'")
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            row(&fields),
+            vec![
+                Value::Int(1),
+                Value::Text(String::from("rex")),
+                Value::Text(String::from("1 day")),
+            ]
+        );
+
+        // can't go in a WITH, so it must reach the server untouched
+        db.run("SELECT name, interval '1 hour' AS wait INTO pet_names FROM pets")
+            .unwrap();
+        let (count, _) = db.run("SELECT * FROM pet_names").unwrap();
+        assert_eq!(count, 1);
+
+        let err = db.run("SELECT 1 +").unwrap_err();
+        assert_eq!(err.category, ErrorCategory::Query);
+        assert_eq!(err.sql_state.as_deref(), Some("42601"));
+        let err = db.run("SELECT 1 / 0").unwrap_err();
+        assert_eq!(err.sql_state.as_deref(), Some("22012"));
+        assert_eq!(err.category, ErrorCategory::Conversion);
+    }
+
+    #[test]
+    fn stops_reading_at_the_row_cap() {
+        let db = match TestDb::new("cap") {
+            Some(db) => db,
+            None => return,
+        };
+
+        let mut sink = ResultSink::stream(
+            TableData::default(),
+            Some(2),
+            QueryLimits::default(),
+            |_| {},
+        );
+        db.driver()
+            .run_query(
+                db.request("SELECT generate_series(1, 100000)"),
+                vec![],
+                &mut sink,
+            )
+            .unwrap();
+        let result = sink.finish();
+        assert_eq!((result.rows, result.truncated), (2, true));
+
+        // nothing was cancelled, the connection takes the next statement as usual
+        let (count, _) = db.run("SELECT 1").unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn rolls_back_after_an_error_in_a_transaction() {
+        let db = match TestDb::new("aborted") {
+            Some(db) => db,
+            None => return,
+        };
+
+        let errors = db.script("BEGIN; INSERT INTO pets (name) VALUES ('rex'); SELECT 1 / 0");
+        assert!(errors[..2].iter().all(Option::is_none));
+        assert_eq!(
+            errors[2].as_ref().unwrap().sql_state.as_deref(),
+            Some("22012")
+        );
+        assert!(db.session.transaction().open);
+
+        // the transaction is aborted, nothing may be sent before the ROLLBACK
+        let errors = db.script("ROLLBACK");
+        assert_eq!(errors, vec![None]);
+        assert!(!db.session.transaction().open);
+
+        // the insert is gone, and unqualified names still resolve in the test schema
+        let (count, _) = db.run("SELECT * FROM pets").unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,307 @@
+use rustql_types::{
+    ApiError, ErrorCategory, QueryChunk, QueryLimits, StatementResult, StatementWarning, TableData,
+    TableField, TableFields, Value,
+};
+use std::{io, mem};
+
+// Rows per QueryRows response
+static CHUNK_ROWS: usize = 500;
+
+// Receives the rows of a result as the driver reads them. Either keeps them all,
+// for results small enough to answer at once, or passes them on in batches
+pub struct ResultSink<'a> {
+    fields: TableFields,
+    // rows in `fields` not sent yet
+    count: usize,
+    sent: usize,
+    // the server's cap truncates the result, the profile's limits fail it
+    max_rows: Option<usize>,
+    truncated: bool,
+    limits: QueryLimits,
+    bytes: usize,
+    exceeded: Option<ApiError>,
+    // what the server reported besides the rows, merged into the result
+    status: StatementResult,
+    // names the batches, filled in with their columns and rows
+    table: TableData,
+    on_chunk: Option<Box<dyn FnMut(QueryChunk) + 'a>>,
+}
+
+impl<'a> ResultSink<'a> {
+    pub fn collect() -> Self {
+        ResultSink {
+            fields: vec![],
+            count: 0,
+            sent: 0,
+            max_rows: None,
+            truncated: false,
+            limits: QueryLimits::default(),
+            bytes: 0,
+            exceeded: None,
+            status: StatementResult::default(),
+            table: TableData::default(),
+            on_chunk: None,
+        }
+    }
+
+    pub fn stream(
+        table: TableData,
+        max_rows: Option<usize>,
+        limits: QueryLimits,
+        on_chunk: impl FnMut(QueryChunk) + 'a,
+    ) -> Self {
+        ResultSink {
+            max_rows,
+            limits,
+            table,
+            on_chunk: Some(Box::new(on_chunk)),
+            ..ResultSink::collect()
+        }
+    }
+
+    pub fn set_fields(&mut self, fields: TableFields) {
+        self.fields = fields;
+    }
+
+    pub fn set_status(
+        &mut self,
+        affected_rows: Option<u64>,
+        last_insert_id: Option<u64>,
+        info: Option<String>,
+    ) {
+        self.status.affected_rows = affected_rows;
+        self.status.last_insert_id = last_insert_id;
+        self.status.info = info.filter(|info| !info.is_empty());
+    }
+
+    pub fn set_warnings(&mut self, warnings: Vec<StatementWarning>) {
+        self.status.warnings = warnings;
+    }
+
+    // false once the row cap or a limit is reached, the driver should then stop the statement
+    // and drop the rest of the result
+    pub fn push_row(&mut self, values: impl IntoIterator<Item = Value>) -> bool {
+        let rows = self.sent + self.count;
+        if let Some(max_rows) = self.limits.max_rows.filter(|max_rows| *max_rows == rows) {
+            self.exceeded = Some(ApiError::new(
+                ErrorCategory::RowLimit,
+                format!(
+                    "Query returned more than {} rows, the most this profile allows",
+                    max_rows
+                ),
+            ));
+            return false;
+        }
+        if self.max_rows == Some(rows) {
+            self.truncated = true;
+            return false;
+        }
+
+        let values: Vec<Value> = values.into_iter().collect();
+        if let Some(max_bytes) = self.limits.max_response_bytes {
+            self.bytes += values.iter().map(serialized_size).sum::<usize>();
+            if self.bytes > max_bytes {
+                self.exceeded = Some(ApiError::new(
+                    ErrorCategory::SizeLimit,
+                    format!(
+                        "Query result grew past {} bytes after {} rows, the largest this profile allows",
+                        max_bytes, rows
+                    ),
+                ));
+                return false;
+            }
+        }
+
+        for (field, value) in self.fields.iter_mut().zip(values) {
+            field.values.push(value);
+        }
+        self.count += 1;
+
+        if self.count == CHUNK_ROWS {
+            self.flush();
+        }
+        true
+    }
+
+    // the rows kept by a collecting sink
+    pub fn into_fields(self) -> (usize, TableFields) {
+        (self.count, self.fields)
+    }
+
+    // sends what is left, at least one batch so the client gets the columns of an empty result.
+    // The result holds the limit's error when one stopped the statement
+    pub fn finish(mut self) -> StatementResult {
+        if self.count > 0 || self.sent == 0 {
+            self.flush();
+        }
+
+        StatementResult {
+            rows: self.sent,
+            truncated: self.truncated,
+            error: self.exceeded,
+            ..self.status
+        }
+    }
+
+    fn flush(&mut self) {
+        let on_chunk = match self.on_chunk.as_mut() {
+            Some(on_chunk) => on_chunk,
+            None => return,
+        };
+
+        let table_fields = self
+            .fields
+            .iter_mut()
+            .map(|field| {
+                let values = mem::take(&mut field.values);
+                TableField {
+                    values,
+                    ..field.clone()
+                }
+            })
+            .collect();
+
+        on_chunk(QueryChunk {
+            // numbered by the caller running the script
+            statement: 0,
+            offset: self.sent,
+            data: TableData {
+                table_fields,
+                count: self.count,
+                ..self.table.clone()
+            },
+        });
+
+        self.sent += self.count;
+        self.count = 0;
+    }
+}
+
+// Bytes the value takes up in a response, what the response size limit counts
+fn serialized_size(value: &Value) -> usize {
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, value).ok();
+    counter.0
+}
+
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Streams `rows` single column rows, numbered from 0, until the sink stops taking them
+    fn stream(
+        rows: usize,
+        max_rows: Option<usize>,
+        limits: QueryLimits,
+    ) -> (usize, StatementResult, Vec<QueryChunk>) {
+        let mut chunks = vec![];
+        let mut pushed = 0;
+        let result = {
+            let mut sink = ResultSink::stream(TableData::default(), max_rows, limits, |chunk| {
+                chunks.push(chunk)
+            });
+            sink.set_fields(vec![TableField {
+                name: String::from("id"),
+                ..Default::default()
+            }]);
+            while pushed < rows && sink.push_row(vec![Value::Int(pushed as i64)]) {
+                pushed += 1;
+            }
+            sink.finish()
+        };
+
+        (pushed, result, chunks)
+    }
+
+    #[test]
+    fn truncates_at_the_row_cap() {
+        // a result exactly the size of the cap is whole
+        let (pushed, result, _) = stream(3, Some(3), QueryLimits::default());
+        assert_eq!((pushed, result.rows, result.truncated), (3, 3, false));
+
+        let (pushed, result, _) = stream(4, Some(3), QueryLimits::default());
+        assert_eq!((pushed, result.rows, result.truncated), (3, 3, true));
+        assert!(result.error.is_none());
+    }
+
+    #[test]
+    fn flushes_full_chunks() {
+        let (_, result, chunks) = stream(CHUNK_ROWS * 2 + 1, None, QueryLimits::default());
+
+        assert_eq!(result.rows, CHUNK_ROWS * 2 + 1);
+        let batches: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|chunk| (chunk.offset, chunk.data.count))
+            .collect();
+        assert_eq!(
+            batches,
+            vec![
+                (0, CHUNK_ROWS),
+                (CHUNK_ROWS, CHUNK_ROWS),
+                (CHUNK_ROWS * 2, 1)
+            ]
+        );
+        let last = &chunks[2].data.table_fields[0];
+        assert_eq!(last.name, "id");
+        assert_eq!(last.values, vec![Value::Int((CHUNK_ROWS * 2) as i64)]);
+    }
+
+    #[test]
+    fn sends_the_columns_of_an_empty_result() {
+        let (_, result, chunks) = stream(0, None, QueryLimits::default());
+
+        assert_eq!(result.rows, 0);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data.table_fields[0].name, "id");
+    }
+
+    #[test]
+    fn fails_past_the_profile_row_limit() {
+        let limits = |max_rows| QueryLimits {
+            max_rows: Some(max_rows),
+            ..Default::default()
+        };
+
+        // with both at the same count the profile's limit wins, the result fails
+        let (pushed, result, _) = stream(3, Some(2), limits(2));
+        assert_eq!((pushed, result.rows, result.truncated), (2, 2, false));
+        assert_eq!(result.error.unwrap().category, ErrorCategory::RowLimit);
+
+        // a lower server cap truncates before the limit is reached
+        let (pushed, result, _) = stream(5, Some(2), limits(4));
+        assert_eq!((pushed, result.truncated), (2, true));
+        assert!(result.error.is_none());
+
+        let (pushed, result, _) = stream(2, None, limits(2));
+        assert_eq!(pushed, 2);
+        assert!(result.error.is_none());
+    }
+
+    #[test]
+    fn counts_the_serialized_size_of_values() {
+        let row_bytes = serialized_size(&Value::Int(0));
+        assert_eq!(row_bytes, r#"{"type":"Int","value":0}"#.len());
+
+        let limits = QueryLimits {
+            max_response_bytes: Some(row_bytes * 2),
+            ..Default::default()
+        };
+        // the limit is inclusive, the row that goes past it is dropped
+        let (pushed, result, _) = stream(3, None, limits);
+        assert_eq!((pushed, result.rows), (2, 2));
+        assert_eq!(result.error.unwrap().category, ErrorCategory::SizeLimit);
+    }
+}
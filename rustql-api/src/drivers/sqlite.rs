@@ -0,0 +1,791 @@
+use super::{
+    double_value, end_statement, found_definition, graph_tables, merge_foreign_keys,
+    requested_page, split_foreign_keys, table_query, unsupported_kind, DatabaseDriver, ResultSink,
+};
+use crate::helpers::{
+    errors::{ApiResult, IntoApiResult},
+    pools::{sqlite_file_from, SqliteConn},
+    session::Session,
+};
+use rusqlite::{params, types::ValueRef, Connection, OptionalExtension, ToSql, NO_PARAMS};
+use rustql_types::{
+    ColumnDescription, ColumnMeta, ConnectionProfile, Database, DefinitionRequest,
+    DescribeTableRequest, DriverKind, ForeignKey, GraphColumn, IndexColumn, IndexDescription,
+    LoadTableRequest, ObjectDefinition, ObjectKind, RunQueryRequest, SchemaGraph,
+    SchemaGraphRequest, SchemaObject, TableData, TableDescription, TableField, TableRef, Value,
+};
+use std::sync::Arc;
+
+static LIST_DATABASES: &str = "SELECT name FROM pragma_database_list ORDER BY seq";
+
+static TOTAL_CHANGES: &str = "SELECT total_changes()";
+
+// sqlite keeps the statement each table, view and trigger was created with
+static DEFINE_OBJECT: &str = "SELECT sql FROM {schema}.sqlite_master WHERE type = ?1 AND name = ?2";
+
+// hidden is 2 for virtual and 3 for stored generated columns
+static DESCRIBE_COLUMNS: &str = "SELECT name, type, \"notnull\", dflt_value, pk, hidden
+    FROM pragma_table_xinfo(?1, ?2)
+    ORDER BY cid";
+
+// One row per key part, cid is -2 for an expression whose text sqlite does not keep here.
+// Every sqlite index is a b-tree and there are no statistics without ANALYZE
+static DESCRIBE_INDEXES: &str =
+    "SELECT il.name, il.\"unique\", il.origin, ix.name, ix.cid, ix.\"desc\"
+    FROM pragma_index_list(?1, ?2) il
+    JOIN pragma_index_xinfo(il.name, ?2) ix
+    WHERE ix.key
+    ORDER BY il.origin = 'pk' DESC, il.name, ix.seqno";
+
+// Keys of every table in the database, filtered to the table's own and those pointing at it.
+// A key without target columns points at the referenced table's primary key.
+// Table names compare without case, a reference is reported with the table's name as given
+static DESCRIBE_FOREIGN_KEYS: &str = "SELECT f.id, m.name, f.\"from\",
+        CASE WHEN f.\"table\" = ?1 COLLATE NOCASE THEN ?1 ELSE f.\"table\" END,
+        COALESCE(f.\"to\", (
+            SELECT p.name FROM pragma_table_info(f.\"table\", ?2) p WHERE p.pk = f.seq + 1
+        ))
+    FROM {schema}.sqlite_master m
+    JOIN pragma_foreign_key_list(m.name, ?2) f
+    WHERE m.type = 'table' AND (m.name = ?1 OR f.\"table\" = ?1 COLLATE NOCASE)
+    ORDER BY m.name, f.id, f.seq";
+
+// Keys between the database's own tables, named the way the referenced table was created
+static GRAPH_FOREIGN_KEYS: &str = "SELECT f.id, m.name, f.\"from\", COALESCE(t.name, f.\"table\"),
+        COALESCE(f.\"to\", (
+            SELECT p.name FROM pragma_table_info(f.\"table\", ?1) p WHERE p.pk = f.seq + 1
+        ))
+    FROM {schema}.sqlite_master m
+    JOIN pragma_foreign_key_list(m.name, ?1) f
+    LEFT JOIN {schema}.sqlite_master t ON t.type = 'table' AND t.name = f.\"table\" COLLATE NOCASE
+    WHERE m.type = 'table'
+    ORDER BY m.name, f.id, f.seq";
+
+static GRAPH_COLUMNS: &str = "SELECT m.name, p.name, p.type, p.pk > 0
+    FROM {schema}.sqlite_master m
+    JOIN pragma_table_info(m.name, ?1) p
+    WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+    ORDER BY m.name, p.cid";
+
+pub struct SqliteDriver<'a> {
+    profile: ConnectionProfile,
+    session: &'a Session,
+}
+
+impl<'a> SqliteDriver<'a> {
+    pub fn new(profile: ConnectionProfile, session: &'a Session) -> Self {
+        SqliteDriver { profile, session }
+    }
+
+    fn conn(&self) -> ApiResult<SqliteConn> {
+        self.session.sqlite_pools.get_sqlite(&self.profile)
+    }
+
+    // Runs the statement registered as running under its connection's interrupt handle
+    fn query_into(
+        &self,
+        conn: &Connection,
+        query: &str,
+        params: &[Option<String>],
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let handle = Arc::new(conn.get_interrupt_handle());
+        let running = self
+            .session
+            .sqlite_running
+            .track(&self.profile.name, handle.clone());
+
+        running.run(
+            self.profile.limits.max_execution_time(),
+            move || {
+                handle.interrupt();
+                Ok(1)
+            },
+            || read_rows(conn, query, params, sink),
+        )
+    }
+}
+
+impl DatabaseDriver for SqliteDriver<'_> {
+    fn connect(&self) -> ApiResult<()> {
+        self.conn().map(|_| ())
+    }
+
+    fn test_connection(&self) -> ApiResult<String> {
+        let (path, flags) = sqlite_file_from(&self.profile)?;
+        let conn = Connection::open_with_flags(path, flags).into_api()?;
+        let version: String = conn
+            .query_row("SELECT sqlite_version()", NO_PARAMS, |row| row.get(0))
+            .into_api()?;
+
+        Ok(format!("SQLite {} ({})", version, path))
+    }
+
+    fn list_databases(&self) -> ApiResult<Vec<Database>> {
+        let conn = self.conn()?;
+
+        let db_names = conn
+            .prepare(LIST_DATABASES)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(NO_PARAMS, |row| row.get(0))?
+                    .collect::<Result<Vec<String>, _>>()
+            })
+            .into_api()?;
+
+        let databases = db_names
+            .into_iter()
+            .map(|name| {
+                let query = format!(
+                    "SELECT name, type FROM {}.sqlite_master
+                    WHERE type IN ('table', 'view', 'trigger') AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+                    ORDER BY type, name",
+                    DriverKind::Sqlite.quote_identifier(&name)
+                );
+                let entries = conn
+                    .prepare(&query)
+                    .and_then(|mut statement| {
+                        statement
+                            .query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?)))?
+                            .collect::<Result<Vec<(String, String)>, _>>()
+                    })
+                    .unwrap_or_default();
+
+                let mut database = Database {
+                    name,
+                    ..Default::default()
+                };
+                for (entry, entry_type) in entries {
+                    match entry_type.as_str() {
+                        "table" => database.tables.push(entry),
+                        "view" => database.objects.push(SchemaObject {
+                            name: entry,
+                            kind: ObjectKind::View,
+                        }),
+                        _ => database.objects.push(SchemaObject {
+                            name: entry,
+                            kind: ObjectKind::Trigger,
+                        }),
+                    }
+                }
+                database
+            })
+            .collect();
+
+        Ok(databases)
+    }
+
+    fn load_table(&self, request: LoadTableRequest) -> ApiResult<TableData> {
+        let table = format!(
+            "{}.{}",
+            DriverKind::Sqlite.quote_identifier(&request.db),
+            DriverKind::Sqlite.quote_identifier(&request.table)
+        );
+        let mut page = requested_page(&request);
+        let query = table_query(DriverKind::Sqlite, &table, &request, &page)?;
+        page.total = self
+            .conn()?
+            .query_row(&query.count, &query.params, |row| row.get::<_, i64>(0))
+            .into_api()? as usize;
+
+        let params: Vec<Option<String>> = query.params.into_iter().map(Some).collect();
+        let mut sink = ResultSink::collect();
+        self.query_into(&*self.conn()?, &query.select, &params, &mut sink)?;
+        let (count, table_fields) = sink.into_fields();
+
+        Ok(TableData {
+            db_name: request.db,
+            table_name: request.table,
+            count,
+            table_fields,
+            page: Some(page),
+        })
+    }
+
+    fn run_query(
+        &self,
+        request: RunQueryRequest,
+        params: Vec<Option<String>>,
+        sink: &mut ResultSink,
+    ) -> ApiResult<()> {
+        let key = (self.profile.name.clone(), None);
+        let in_transaction = self.session.transaction().open;
+
+        self.session.sqlite_pinned.with(
+            key,
+            in_transaction,
+            || {
+                let (path, flags) = sqlite_file_from(&self.profile)?;
+                Connection::open_with_flags(path, flags).into_api()
+            },
+            |conn| self.query_into(conn, &request.query, &params, sink),
+        )
+    }
+
+    fn describe_table(&self, request: DescribeTableRequest) -> ApiResult<TableDescription> {
+        let conn = self.conn()?;
+
+        let columns = conn
+            .prepare(DESCRIBE_COLUMNS)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params![request.table, request.db], |row| {
+                        let primary_key = row.get::<_, i64>(4)? > 0;
+                        Ok(ColumnDescription {
+                            name: row.get(0)?,
+                            sql_type: row.get(1)?,
+                            nullable: !row.get::<_, bool>(2)?,
+                            default: row.get(3)?,
+                            primary_key,
+                            extra: match row.get::<_, i64>(5)? {
+                                2 => String::from("VIRTUAL GENERATED"),
+                                3 => String::from("STORED GENERATED"),
+                                _ => String::new(),
+                            },
+                            key: match primary_key {
+                                true => String::from("PRI"),
+                                false => String::new(),
+                            },
+                            collation: None,
+                            comment: String::new(),
+                        })
+                    })?
+                    .collect::<Result<Vec<ColumnDescription>, _>>()
+            })
+            .into_api()?;
+
+        let mut indexes: Vec<IndexDescription> = vec![];
+        conn.prepare(DESCRIBE_INDEXES)
+            .and_then(|mut statement| {
+                let mut rows = statement.query(params![request.table, request.db])?;
+                while let Some(row) = rows.next()? {
+                    let name: String = row.get(0)?;
+                    let column = IndexColumn {
+                        name: row
+                            .get::<_, Option<String>>(3)?
+                            .unwrap_or_else(|| String::from("<expression>")),
+                        expression: row.get::<_, i64>(4)? == -2,
+                        sub_part: None,
+                        descending: row.get(5)?,
+                    };
+
+                    match indexes.last_mut() {
+                        Some(index) if index.name == name => index.columns.push(column),
+                        _ => indexes.push(IndexDescription {
+                            name,
+                            columns: vec![column],
+                            unique: row.get(1)?,
+                            primary: row.get::<_, String>(2)? == "pk",
+                            index_type: String::from("BTREE"),
+                            cardinality: None,
+                            visible: true,
+                        }),
+                    }
+                }
+                Ok(())
+            })
+            .into_api()?;
+
+        let parts = read_foreign_keys(
+            &conn,
+            DESCRIBE_FOREIGN_KEYS,
+            params![request.table, request.db],
+            &request.db,
+        )?;
+        let (foreign_keys, referenced_by) = split_foreign_keys(
+            parts,
+            &TableRef {
+                db: request.db.clone(),
+                schema: None,
+                table: request.table.clone(),
+            },
+        );
+
+        Ok(TableDescription {
+            db_name: request.db,
+            table_name: request.table,
+            columns,
+            indexes,
+            foreign_keys,
+            referenced_by,
+        })
+    }
+
+    fn object_definition(&self, request: DefinitionRequest) -> ApiResult<ObjectDefinition> {
+        let entry_type = match request.kind {
+            ObjectKind::View => "view",
+            ObjectKind::Trigger => "trigger",
+            kind => return Err(unsupported_kind(DriverKind::Sqlite, kind)),
+        };
+        let definition = self
+            .conn()?
+            .query_row(
+                &in_schema(DEFINE_OBJECT, &request.db),
+                params![entry_type, request.name],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .into_api()?
+            .flatten();
+
+        found_definition(request, definition)
+    }
+
+    fn schema_graph(&self, request: SchemaGraphRequest) -> ApiResult<SchemaGraph> {
+        let conn = self.conn()?;
+        let columns = conn
+            .prepare(&in_schema(GRAPH_COLUMNS, &request.db))
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params![request.db], |row| {
+                        let column = GraphColumn {
+                            name: row.get(1)?,
+                            sql_type: row.get(2)?,
+                            primary_key: row.get(3)?,
+                        };
+                        Ok((row.get(0)?, column))
+                    })?
+                    .collect::<Result<Vec<(String, GraphColumn)>, _>>()
+            })
+            .into_api()?;
+        let parts = read_foreign_keys(&conn, GRAPH_FOREIGN_KEYS, params![request.db], &request.db)?;
+
+        Ok(SchemaGraph {
+            db: request.db,
+            schema: None,
+            tables: graph_tables(columns),
+            foreign_keys: merge_foreign_keys(parts),
+        })
+    }
+
+    fn execute_ddl(&self, _db: &str, statement: &str) -> ApiResult<()> {
+        self.conn()?.execute_batch(statement).into_api()
+    }
+
+    fn end_transaction(&self, commit: bool) -> ApiResult<()> {
+        self.session
+            .sqlite_pinned
+            .with_current(|conn| conn.execute_batch(end_statement(commit)).into_api())
+            .map(|_| ())
+    }
+
+    fn cancel(&self) -> ApiResult<usize> {
+        let handles = self.session.sqlite_running.cancel(&self.profile.name);
+        handles.iter().for_each(|handle| handle.interrupt());
+
+        Ok(handles.len())
+    }
+}
+
+// The query with its {schema} placeholder naming the attached database `db`
+fn in_schema(query: &str, db: &str) -> String {
+    query.replace("{schema}", &DriverKind::Sqlite.quote_identifier(db))
+}
+
+// Reads DESCRIBE_FOREIGN_KEYS or GRAPH_FOREIGN_KEYS rows, keys never leave their database
+fn read_foreign_keys(
+    conn: &Connection,
+    query: &str,
+    params: &[&dyn ToSql],
+    db: &str,
+) -> ApiResult<Vec<ForeignKey>> {
+    let table_ref = |table: String| TableRef {
+        db: db.to_string(),
+        schema: None,
+        table,
+    };
+
+    conn.prepare(&in_schema(query, db))
+        .and_then(|mut statement| {
+            statement
+                .query_map(params, |row| {
+                    Ok(ForeignKey {
+                        name: row.get::<_, i64>(0)?.to_string(),
+                        table: table_ref(row.get(1)?),
+                        columns: vec![row.get(2)?],
+                        referenced_table: table_ref(row.get(3)?),
+                        referenced_columns: vec![row
+                            .get::<_, Option<String>>(4)?
+                            .unwrap_or_default()],
+                    })
+                })?
+                .collect()
+        })
+        .into_api()
+}
+
+fn read_rows(
+    conn: &Connection,
+    query: &str,
+    params: &[Option<String>],
+    sink: &mut ResultSink,
+) -> ApiResult<()> {
+    let mut statement = conn.prepare(query).into_api()?;
+    sink.set_fields(
+        statement
+            .columns()
+            .iter()
+            .map(|column| TableField {
+                name: column.name().to_string(),
+                sql_type: column.decl_type().unwrap_or_default().to_string(),
+                meta: ColumnMeta::default(),
+                values: vec![],
+            })
+            .collect(),
+    );
+
+    // changes() keeps the count of the last insert, update or delete, even past other statements
+    let changes_before = total_changes(conn)?;
+    let insert_id_before = conn.last_insert_rowid();
+
+    // rows are computed as they are stepped through, stopping early needs nothing more
+    let columns = statement.column_count();
+    let mut rows = statement.query(params).into_api()?;
+    while let Some(row) = rows.next().into_api()? {
+        let values = (0..columns)
+            .map(|index| row.get_raw_checked(index).map(value_from))
+            .collect::<Result<Vec<Value>, _>>()
+            .into_api()?;
+
+        if !sink.push_row(values) {
+            return Ok(());
+        }
+    }
+
+    if columns == 0 {
+        let insert_id = conn.last_insert_rowid();
+        sink.set_status(
+            Some((total_changes(conn)? - changes_before) as u64),
+            Some(insert_id as u64).filter(|_| insert_id != insert_id_before),
+            None,
+        );
+    }
+    Ok(())
+}
+
+fn total_changes(conn: &Connection) -> ApiResult<i64> {
+    conn.query_row(TOTAL_CHANGES, NO_PARAMS, |row| row.get(0))
+        .into_api()
+}
+
+fn value_from(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(value) => Value::Int(value),
+        ValueRef::Real(value) => double_value(value),
+        ValueRef::Text(text) => Value::Text(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => Value::Bytes(bytes.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustql_types::{FilterOperator, QueryParams, TableFilter, TableSort};
+    use std::{fs, path::PathBuf};
+
+    static SCHEMA: &str = "
+        CREATE TABLE owners (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE pets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER DEFAULT 1,
+            owner_id INTEGER REFERENCES owners (id)
+        );
+        CREATE UNIQUE INDEX pets_name ON pets (name DESC);
+        CREATE TABLE tags (hash BLOB PRIMARY KEY, label TEXT);
+        CREATE TABLE sqlitexdata (id INTEGER);
+        CREATE VIEW old_pets AS SELECT * FROM pets WHERE age > 5;
+        CREATE TRIGGER pets_touch AFTER UPDATE ON pets BEGIN SELECT 1; END;
+        INSERT INTO owners VALUES (1, 'ann'), (2, 'bob');
+        INSERT INTO pets (name, age, owner_id) VALUES
+            ('rex', 3, 1), ('tom', 7, 2), ('kit', 1, NULL), ('max', 9, 1), ('fido', 4, 2);
+        INSERT INTO tags VALUES (X'00ff', 'first'), (X'0a0b', 'second');";
+
+    // A database file of its own per test, removed when the test ends
+    struct TestDb {
+        path: PathBuf,
+        session: Session,
+    }
+
+    impl TestDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "rustql-sqlite-{}-{}.db",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_file(&path);
+            Connection::open(&path)
+                .and_then(|conn| conn.execute_batch(SCHEMA))
+                .expect("create test database");
+
+            TestDb {
+                path,
+                session: Session::new(None),
+            }
+        }
+
+        fn driver(&self) -> SqliteDriver<'_> {
+            let profile = ConnectionProfile {
+                name: String::from("test"),
+                driver: DriverKind::Sqlite,
+                path: Some(self.path.to_string_lossy().into_owned()),
+                ..Default::default()
+            };
+            SqliteDriver::new(profile, &self.session)
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    fn load_request() -> LoadTableRequest {
+        LoadTableRequest {
+            db: String::from("main"),
+            schema: None,
+            table: String::from("pets"),
+            offset: 0,
+            page_size: 100,
+            sort: None,
+            filters: vec![],
+        }
+    }
+
+    fn column<'a>(data: &'a TableData, name: &str) -> &'a [Value] {
+        &data
+            .table_fields
+            .iter()
+            .find(|field| field.name == name)
+            .expect("column in result")
+            .values
+    }
+
+    fn texts(values: &[&str]) -> Vec<Value> {
+        values
+            .iter()
+            .map(|value| Value::Text(value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_tables_and_objects() {
+        let db = TestDb::new("list");
+        let databases = db.driver().list_databases().unwrap();
+
+        assert_eq!(databases.len(), 1);
+        assert_eq!(databases[0].name, "main");
+        // sqlite_sequence, created for AUTOINCREMENT, is left out
+        assert_eq!(
+            databases[0].tables,
+            vec!["owners", "pets", "sqlitexdata", "tags"]
+        );
+        assert_eq!(
+            databases[0].objects,
+            vec![
+                SchemaObject {
+                    name: String::from("pets_touch"),
+                    kind: ObjectKind::Trigger,
+                },
+                SchemaObject {
+                    name: String::from("old_pets"),
+                    kind: ObjectKind::View,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn loads_sorted_pages() {
+        let db = TestDb::new("page");
+        let request = LoadTableRequest {
+            offset: 1,
+            page_size: 2,
+            sort: Some(TableSort {
+                column: String::from("age"),
+                descending: true,
+            }),
+            ..load_request()
+        };
+        let data = db.driver().load_table(request).unwrap();
+
+        assert_eq!(data.count, 2);
+        assert_eq!(column(&data, "name"), texts(&["tom", "fido"]).as_slice());
+        let page = data.page.unwrap();
+        assert_eq!((page.offset, page.page_size, page.total), (1, 2, 5));
+    }
+
+    #[test]
+    fn loads_filtered_rows() {
+        let db = TestDb::new("filter");
+        let filter = |column: &str, operator, value: &str| TableFilter {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+            binary: false,
+        };
+        let request = LoadTableRequest {
+            sort: Some(TableSort {
+                column: String::from("name"),
+                descending: false,
+            }),
+            filters: vec![
+                filter("age", FilterOperator::GreaterOrEqual, "3"),
+                filter("name", FilterOperator::NotLike, "%x"),
+            ],
+            ..load_request()
+        };
+        let data = db.driver().load_table(request).unwrap();
+
+        assert_eq!(column(&data, "name"), texts(&["fido", "tom"]).as_slice());
+        assert_eq!(data.page.unwrap().total, 2);
+
+        let request = LoadTableRequest {
+            filters: vec![filter("owner_id", FilterOperator::IsNull, "")],
+            ..load_request()
+        };
+        let data = db.driver().load_table(request).unwrap();
+        assert_eq!(column(&data, "name"), texts(&["kit"]).as_slice());
+    }
+
+    #[test]
+    fn loads_rows_by_binary_value() {
+        let db = TestDb::new("binary");
+        let filter = TableFilter::equal(String::from("hash"), &Value::Bytes(vec![0x0a, 0x0b]));
+        assert_eq!(filter.value, "0a0b");
+
+        let request = LoadTableRequest {
+            table: String::from("tags"),
+            filters: vec![filter.clone()],
+            ..load_request()
+        };
+        let data = db.driver().load_table(request).unwrap();
+        assert_eq!(column(&data, "label"), texts(&["second"]).as_slice());
+
+        let request = LoadTableRequest {
+            table: String::from("tags"),
+            filters: vec![TableFilter {
+                value: String::from("0a' OR '1"),
+                ..filter
+            }],
+            ..load_request()
+        };
+        assert!(db.driver().load_table(request).is_err());
+    }
+
+    #[test]
+    fn runs_queries_with_params() {
+        let db = TestDb::new("query");
+        let request = RunQueryRequest {
+            db: String::from("main"),
+            schema: None,
+            table: String::new(),
+            query: String::from(
+                "SELECT p.name, o.name AS owner, p.age * 1.5 AS half_again
+                FROM pets p JOIN owners o ON o.id = p.owner_id
+                WHERE o.name = ? ORDER BY p.id",
+            ),
+            continue_on_error: false,
+            params: QueryParams::default(),
+        };
+        let mut sink = ResultSink::collect();
+        db.driver()
+            .run_query(request, vec![Some(String::from("ann"))], &mut sink)
+            .unwrap();
+        let (count, fields) = sink.into_fields();
+
+        assert_eq!(count, 2);
+        let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, vec!["name", "owner", "half_again"]);
+        assert_eq!(fields[0].values, texts(&["rex", "max"]));
+        assert_eq!(
+            fields[2].values,
+            vec![Value::Double(4.5), Value::Double(13.5)]
+        );
+    }
+
+    #[test]
+    fn sends_infinities_as_text() {
+        let db = TestDb::new("infinity");
+        let request = RunQueryRequest {
+            db: String::from("main"),
+            schema: None,
+            table: String::new(),
+            query: String::from("SELECT 1e999, -1e999, 0.5"),
+            continue_on_error: false,
+            params: QueryParams::default(),
+        };
+        let mut sink = ResultSink::collect();
+        db.driver().run_query(request, vec![], &mut sink).unwrap();
+        let values: Vec<Value> = sink
+            .into_fields()
+            .1
+            .into_iter()
+            .flat_map(|field| field.values)
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                Value::Text(String::from("Infinity")),
+                Value::Text(String::from("-Infinity")),
+                Value::Double(0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn describes_tables() {
+        let db = TestDb::new("describe");
+        let description = db
+            .driver()
+            .describe_table(DescribeTableRequest {
+                db: String::from("main"),
+                schema: None,
+                table: String::from("pets"),
+            })
+            .unwrap();
+
+        let columns: Vec<(&str, &str, bool, bool)> = description
+            .columns
+            .iter()
+            .map(|column| {
+                (
+                    column.name.as_str(),
+                    column.sql_type.as_str(),
+                    column.nullable,
+                    column.primary_key,
+                )
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", "INTEGER", true, true),
+                ("name", "TEXT", false, false),
+                ("age", "INTEGER", true, false),
+                ("owner_id", "INTEGER", true, false),
+            ]
+        );
+        assert_eq!(description.columns[2].default.as_deref(), Some("1"));
+
+        let index = description
+            .indexes
+            .iter()
+            .find(|index| index.name == "pets_name")
+            .expect("pets_name index");
+        assert!(index.unique && !index.primary);
+        assert_eq!(index.columns.len(), 1);
+        assert!(index.columns[0].name == "name" && index.columns[0].descending);
+
+        assert_eq!(description.foreign_keys.len(), 1);
+        let key = &description.foreign_keys[0];
+        assert_eq!(key.columns, vec!["owner_id"]);
+        assert_eq!(key.referenced_table.table, "owners");
+        assert_eq!(key.referenced_columns, vec!["id"]);
+        assert!(description.referenced_by.is_empty());
+    }
+}
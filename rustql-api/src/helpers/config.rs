@@ -0,0 +1,66 @@
+use std::env;
+
+static DEFAULT_HOST: &str = "127.0.0.1";
+static DEFAULT_PORT: u16 = 8888;
+static DEFAULT_MAX_ROWS: usize = 100_000;
+
+static USAGE: &str = "Usage: rustql-api [--host <address>] [--port <port>] [--max-rows <rows>]
+
+Options:
+    --host <address>    interface to listen on (env RUSTQL_HOST, default 127.0.0.1)
+    --port <port>       port to listen on (env RUSTQL_PORT, default 8888)
+    --max-rows <rows>   rows a query may return before it is stopped and its result truncated,
+                        0 for no limit (env RUSTQL_MAX_ROWS, default 100000)";
+
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub max_rows: Option<usize>,
+}
+
+impl ServerConfig {
+    // Command line flags take precedence over the environment
+    pub fn load() -> Result<ServerConfig, String> {
+        let mut config = ServerConfig {
+            host: env::var("RUSTQL_HOST").unwrap_or_else(|_| DEFAULT_HOST.to_string()),
+            port: match env::var("RUSTQL_PORT") {
+                Ok(port) => parse_port(&port)?,
+                Err(_) => DEFAULT_PORT,
+            },
+            max_rows: match env::var("RUSTQL_MAX_ROWS") {
+                Ok(rows) => parse_max_rows(&rows)?,
+                Err(_) => Some(DEFAULT_MAX_ROWS),
+            },
+        };
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--host" => config.host = args.next().ok_or(USAGE)?,
+                "--port" => config.port = parse_port(&args.next().ok_or(USAGE)?)?,
+                "--max-rows" => config.max_rows = parse_max_rows(&args.next().ok_or(USAGE)?)?,
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                _ => return Err(format!("Unknown argument: {}\n\n{}", arg, USAGE)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    port.parse()
+        .map_err(|_| format!("Invalid port: {}\n\n{}", port, USAGE))
+}
+
+fn parse_max_rows(rows: &str) -> Result<Option<usize>, String> {
+    match rows.parse() {
+        Ok(0) => Ok(None),
+        Ok(rows) => Ok(Some(rows)),
+        Err(_) => Err(format!("Invalid row limit: {}\n\n{}", rows, USAGE)),
+    }
+}
@@ -0,0 +1,137 @@
+use rustql_types::{ApiError, ErrorCategory};
+use std::{error::Error, io};
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+// ApiError lives in rustql-types so it can't implement From for these foreign errors
+pub trait IntoApiError {
+    fn into_api_error(self) -> ApiError;
+}
+
+impl IntoApiError for mysql::Error {
+    fn into_api_error(self) -> ApiError {
+        match self {
+            mysql::Error::MySqlError(err) => ApiError {
+                category: ErrorCategory::Query,
+                message: err.message,
+                code: Some(err.code),
+                sql_state: Some(err.state),
+                action: None,
+            },
+            mysql::Error::FromValueError(_) | mysql::Error::FromRowError(_) => {
+                ApiError::new(ErrorCategory::Conversion, self.to_string())
+            }
+            // placeholders that don't match the values bound to them
+            mysql::Error::DriverError(
+                mysql::DriverError::MismatchedStmtParams(..)
+                | mysql::DriverError::MissingNamedParameter(_)
+                | mysql::DriverError::NamedParamsForPositionalQuery
+                | mysql::DriverError::MixedParams,
+            ) => ApiError::request(self.to_string()),
+            _ => ApiError::new(ErrorCategory::Connection, self.to_string()),
+        }
+    }
+}
+
+impl IntoApiError for mysql::FromValueError {
+    fn into_api_error(self) -> ApiError {
+        ApiError::new(
+            ErrorCategory::Conversion,
+            format!("Could not convert value: {:?}", self.0),
+        )
+    }
+}
+
+impl IntoApiError for postgres::Error {
+    fn into_api_error(self) -> ApiError {
+        match self.as_db_error() {
+            Some(err) => {
+                let sql_state = err.code().code();
+                // by SQLSTATE class, 08 is a connection exception and 28 a failed login
+                let category = match sql_state {
+                    "57014" => ErrorCategory::Cancelled,
+                    _ if sql_state.starts_with("08") || sql_state.starts_with("28") => {
+                        ErrorCategory::Connection
+                    }
+                    _ if sql_state.starts_with("22") => ErrorCategory::Conversion,
+                    _ => ErrorCategory::Query,
+                };
+                ApiError {
+                    category,
+                    message: err.message().to_string(),
+                    code: None,
+                    sql_state: Some(sql_state.to_string()),
+                    action: None,
+                }
+            }
+            None => {
+                // without a SQLSTATE the server wasn't reached or the statement was never sent,
+                // which short of a lost connection is a parameter that doesn't fit its placeholder
+                let io_error = matches!(self.source(), Some(source) if source.is::<io::Error>());
+                let category = match self.is_closed() || io_error {
+                    true => ErrorCategory::Connection,
+                    false => ErrorCategory::Request,
+                };
+                ApiError::new(category, self.to_string())
+            }
+        }
+    }
+}
+
+impl IntoApiError for rusqlite::Error {
+    fn into_api_error(self) -> ApiError {
+        match self {
+            rusqlite::Error::SqliteFailure(err, message) => ApiError {
+                category: ErrorCategory::Query,
+                message: message.unwrap_or_else(|| err.to_string()),
+                code: Some(err.extended_code as u16),
+                sql_state: None,
+                action: None,
+            },
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::Utf8Error(_) => {
+                ApiError::new(ErrorCategory::Conversion, self.to_string())
+            }
+            rusqlite::Error::InvalidParameterCount(..)
+            | rusqlite::Error::InvalidParameterName(_)
+            | rusqlite::Error::ToSqlConversionFailure(_)
+            | rusqlite::Error::ExecuteReturnedResults
+            | rusqlite::Error::MultipleStatement
+            | rusqlite::Error::NulError(_) => ApiError::request(self.to_string()),
+            rusqlite::Error::InvalidPath(_) | rusqlite::Error::SqliteSingleThreadedMode => {
+                ApiError::new(ErrorCategory::Connection, self.to_string())
+            }
+            _ => ApiError::new(ErrorCategory::Query, self.to_string()),
+        }
+    }
+}
+
+impl IntoApiError for r2d2::Error {
+    fn into_api_error(self) -> ApiError {
+        ApiError::new(ErrorCategory::Connection, self.to_string())
+    }
+}
+
+impl IntoApiError for serde_json::Error {
+    fn into_api_error(self) -> ApiError {
+        ApiError::request(format!("Invalid json object in request: {}", self))
+    }
+}
+
+impl IntoApiError for io::Error {
+    fn into_api_error(self) -> ApiError {
+        ApiError::new(ErrorCategory::Profile, self.to_string())
+    }
+}
+
+pub trait IntoApiResult<T> {
+    fn into_api(self) -> ApiResult<T>;
+}
+
+impl<T, E: IntoApiError> IntoApiResult<T> for Result<T, E> {
+    fn into_api(self) -> ApiResult<T> {
+        self.map_err(IntoApiError::into_api_error)
+    }
+}
@@ -1 +1,5 @@
-pub mod api_types;
+pub mod config;
+pub mod errors;
+pub mod pools;
+pub mod profiles;
+pub mod session;
@@ -0,0 +1,233 @@
+use mysql::{
+    prelude::Queryable, OptsBuilder, Pool, PoolConstraints, PoolOpts, PooledConn,
+};
+use postgres::NoTls;
+use r2d2_postgres::PostgresConnectionManager;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::OpenFlags;
+use rustql_types::{ApiError, ConnectionProfile, ErrorCategory, PoolSettings};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use super::errors::{ApiResult, IntoApiResult};
+
+static SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+static CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type PgPool = r2d2::Pool<PostgresConnectionManager<NoTls>>;
+pub type PgConn = r2d2::PooledConnection<PostgresConnectionManager<NoTls>>;
+pub type SqlitePool = r2d2::Pool<SqliteConnectionManager>;
+pub type SqliteConn = r2d2::PooledConnection<SqliteConnectionManager>;
+
+pub fn opts_from(profile: &ConnectionProfile) -> OptsBuilder {
+    OptsBuilder::new()
+        .ip_or_hostname(Some(&profile.host))
+        .tcp_port(profile.port)
+        .user(Some(&profile.user))
+        .pass(Some(&profile.password))
+        .db_name(profile.database.as_ref())
+}
+
+pub fn pg_config_from(profile: &ConnectionProfile, db: &str) -> postgres::Config {
+    let mut config = postgres::Config::new();
+    config
+        .host(&profile.host)
+        .port(profile.port)
+        .user(&profile.user)
+        .password(&profile.password)
+        .dbname(db)
+        .connect_timeout(CONNECT_TIMEOUT);
+    config
+}
+
+// Opens an existing database file, a typo in the path should not create an empty database
+pub fn sqlite_file_from(profile: &ConnectionProfile) -> ApiResult<(&str, OpenFlags)> {
+    let path = profile
+        .path
+        .as_deref()
+        .filter(|path| !path.trim().is_empty())
+        .ok_or_else(|| ApiError::request("Profile has no database file"))?;
+
+    if !Path::new(path).is_file() {
+        return Err(ApiError::new(
+            ErrorCategory::Connection,
+            format!("Database file not found: {}", path),
+        ));
+    }
+
+    Ok((
+        path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    ))
+}
+
+struct SessionPool<P> {
+    pool: P,
+    idle_timeout: Duration,
+    last_used: Instant,
+}
+
+impl<P> SessionPool<P> {
+    fn is_idle(&self) -> bool {
+        self.last_used.elapsed() > self.idle_timeout
+    }
+}
+
+// profile name, plus the database for backends whose connections are bound to one
+pub type PoolKey = (String, Option<String>);
+
+// Long lived pools, one per profile (and database), shared by every handler of a websocket session
+pub struct SessionPools<P> {
+    pools: Arc<Mutex<HashMap<PoolKey, SessionPool<P>>>>,
+}
+
+impl<P> Default for SessionPools<P> {
+    fn default() -> Self {
+        SessionPools {
+            pools: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<P> Clone for SessionPools<P> {
+    fn clone(&self) -> Self {
+        SessionPools {
+            pools: self.pools.clone(),
+        }
+    }
+}
+
+impl SessionPools<Pool> {
+    pub fn get_conn(&self, profile: &ConnectionProfile) -> mysql::Result<PooledConn> {
+        let mut conn = self.mysql_pool(profile)?.get_conn()?;
+
+        // stale connections (e.g. after a server restart) fail here, start over with a fresh pool
+        if profile.pool.health_check && conn.query_drop("DO 1").is_err() {
+            self.evict(&profile.name);
+            conn = self.mysql_pool(profile)?.get_conn()?;
+        }
+
+        Ok(conn)
+    }
+
+    fn mysql_pool(&self, profile: &ConnectionProfile) -> mysql::Result<Pool> {
+        self.pool((profile.name.clone(), None), &profile.pool, || {
+            let settings = &profile.pool;
+            let constraints = PoolConstraints::new(
+                settings.min_connections,
+                settings.max_connections.max(settings.min_connections).max(1),
+            )
+            .unwrap_or_default();
+            let opts =
+                opts_from(profile).pool_opts(PoolOpts::default().with_constraints(constraints));
+            Pool::new(opts)
+        })
+    }
+}
+
+impl SessionPools<PgPool> {
+    // r2d2 checks connections on checkout when the profile asks for health checks
+    pub fn get_client(&self, profile: &ConnectionProfile, db: &str) -> Result<PgConn, r2d2::Error> {
+        let key = (profile.name.clone(), Some(db.to_string()));
+
+        self.pool(key, &profile.pool, || {
+            let settings = &profile.pool;
+            let manager = PostgresConnectionManager::new(pg_config_from(profile, db), NoTls);
+            r2d2::Pool::builder()
+                .min_idle(Some(settings.min_connections as u32))
+                .max_size(settings.max_connections.max(settings.min_connections).max(1) as u32)
+                .test_on_check_out(settings.health_check)
+                .connection_timeout(CONNECT_TIMEOUT)
+                .build(manager)
+        })?
+        .get()
+    }
+}
+
+impl SessionPools<SqlitePool> {
+    pub fn get_sqlite(&self, profile: &ConnectionProfile) -> ApiResult<SqliteConn> {
+        let (path, flags) = sqlite_file_from(profile)?;
+
+        self.pool((profile.name.clone(), None), &profile.pool, || {
+            let settings = &profile.pool;
+            let manager = SqliteConnectionManager::file(path).with_flags(flags);
+            r2d2::Pool::builder()
+                .min_idle(Some(settings.min_connections as u32))
+                .max_size(settings.max_connections.max(settings.min_connections).max(1) as u32)
+                .test_on_check_out(settings.health_check)
+                .connection_timeout(CONNECT_TIMEOUT)
+                .build(manager)
+        })
+        .and_then(|pool| pool.get())
+        .into_api()
+    }
+}
+
+impl<P: Clone + Send + 'static> SessionPools<P> {
+    pub fn evict(&self, name: &str) {
+        self.lock().retain(|(profile, _), _| profile != name);
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    // Closes pools that have not been used within their idle timeout,
+    // runs until the session owning the pools is dropped
+    pub fn sweeper(&self) -> impl Future<Output = ()> {
+        let pools = Arc::downgrade(&self.pools);
+
+        async move {
+            loop {
+                tokio::time::sleep(SWEEP_INTERVAL).await;
+
+                match pools.upgrade() {
+                    Some(pools) => pools
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .retain(|_, pool| !pool.is_idle()),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    fn pool<E>(
+        &self,
+        key: PoolKey,
+        settings: &PoolSettings,
+        create: impl FnOnce() -> Result<P, E>,
+    ) -> Result<P, E> {
+        let mut pools = self.lock();
+
+        if let Some(entry) = pools.get_mut(&key) {
+            if !entry.is_idle() {
+                entry.last_used = Instant::now();
+                return Ok(entry.pool.clone());
+            }
+        }
+
+        let pool = create()?;
+        pools.insert(
+            key,
+            SessionPool {
+                pool: pool.clone(),
+                idle_timeout: Duration::from_secs(settings.idle_timeout_secs),
+                last_used: Instant::now(),
+            },
+        );
+
+        Ok(pool)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<PoolKey, SessionPool<P>>> {
+        self.pools
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,66 @@
+use lazy_static::lazy_static;
+use rustql_types::ConnectionProfile;
+use std::{
+    env, fs, io,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+
+lazy_static! {
+    static ref PROFILES_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// Location can be overridden with RUSTQL_PROFILES, otherwise the user config dir is used
+pub fn profiles_path() -> PathBuf {
+    match env::var_os("RUSTQL_PROFILES") {
+        Some(path) => PathBuf::from(path),
+        None => dirs::config_dir()
+            .unwrap_or_default()
+            .join("rustql")
+            .join("profiles.json"),
+    }
+}
+
+pub fn load_profiles() -> io::Result<Vec<ConnectionProfile>> {
+    match fs::read_to_string(profiles_path()) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        // first run, offer the local dev server until the user saves their own
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![default_profile()]),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn save_profiles(profiles: &[ConnectionProfile]) -> io::Result<()> {
+    let path = profiles_path();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // write beside the file and rename over it, a crash mid-write keeps the old profiles
+    let mut temp = path.clone().into_os_string();
+    temp.push(".tmp");
+    fs::write(&temp, serde_json::to_string_pretty(profiles)?)?;
+    fs::rename(temp, path)
+}
+
+// Held across a load and save so concurrent sessions don't overwrite each other's edits
+pub fn lock_profiles() -> MutexGuard<'static, ()> {
+    PROFILES_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn find_profile(name: &str) -> io::Result<Option<ConnectionProfile>> {
+    Ok(load_profiles()?
+        .into_iter()
+        .find(|profile| profile.name == name))
+}
+
+fn default_profile() -> ConnectionProfile {
+    ConnectionProfile {
+        name: String::from("localhost"),
+        password: String::from("rustqlpw"),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,389 @@
+use super::{
+    errors::ApiResult,
+    pools::{PgPool, PoolKey, SessionPools, SqlitePool},
+};
+use log::warn;
+use mysql::prelude::Queryable;
+use postgres::CancelToken;
+use rusqlite::{Connection, InterruptHandle};
+use rustql_types::{ApiError, ConnectionProfile, ErrorCategory, TransactionStatus};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+// State held for the lifetime of a single websocket connection,
+// shared by the requests of that connection running concurrently
+#[derive(Default)]
+pub struct Session {
+    profile: Mutex<Option<ConnectionProfile>>,
+    // set once the client has sent a compatible Init
+    initialised: AtomicBool,
+    pub mysql_pools: SessionPools<mysql::Pool>,
+    pub pg_pools: SessionPools<PgPool>,
+    pub sqlite_pools: SessionPools<SqlitePool>,
+    // the editor's queries run on these, never on the pools
+    pub mysql_pinned: PinnedConn<mysql::Conn>,
+    pub pg_pinned: PinnedConn<PgPinned>,
+    pub sqlite_pinned: PinnedConn<Connection>,
+    transaction: Mutex<TransactionStatus>,
+    pub mysql_running: RunningQueries<u64>,
+    pub pg_running: RunningQueries<CancelToken>,
+    pub sqlite_running: RunningQueries<Arc<InterruptHandle>>,
+    // queries returning more rows are stopped and their result truncated
+    pub max_rows: Option<usize>,
+}
+
+impl Session {
+    pub fn new(max_rows: Option<usize>) -> Self {
+        Session {
+            max_rows,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_initialised(&self) -> bool {
+        self.initialised.load(Ordering::SeqCst)
+    }
+
+    pub fn set_initialised(&self) {
+        self.initialised.store(true, Ordering::SeqCst);
+    }
+
+    pub fn profile(&self) -> Option<ConnectionProfile> {
+        self.lock_profile().clone()
+    }
+
+    pub fn set_profile(&self, profile: Option<ConnectionProfile>) {
+        *self.lock_profile() = profile;
+    }
+
+    pub fn transaction(&self) -> TransactionStatus {
+        self.lock_transaction().clone()
+    }
+
+    pub fn set_transaction(&self, transaction: TransactionStatus) {
+        *self.lock_transaction() = transaction;
+    }
+
+    // Whether an editor connection is open, a statement failing on it may have closed it
+    pub fn is_pinned(&self) -> bool {
+        self.mysql_pinned.is_open() || self.pg_pinned.is_open() || self.sqlite_pinned.is_open()
+    }
+
+    // Closes the pinned connections, the server rolls back a transaction left open on them
+    pub fn unpin(&self) {
+        self.mysql_pinned.clear();
+        self.pg_pinned.clear();
+        self.sqlite_pinned.clear();
+        self.lock_transaction().open = false;
+    }
+
+    // Settings may have changed, the next request builds new pools
+    pub fn evict_pools(&self, name: &str) {
+        self.mysql_pools.evict(name);
+        self.pg_pools.evict(name);
+        self.sqlite_pools.evict(name);
+    }
+
+    // Drops the session's pools, closing their connections once in-flight requests finish
+    pub fn close(&self) {
+        self.set_profile(None);
+        self.unpin();
+        self.mysql_pools.clear();
+        self.pg_pools.clear();
+        self.sqlite_pools.clear();
+    }
+
+    fn lock_profile(&self) -> MutexGuard<'_, Option<ConnectionProfile>> {
+        self.profile
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_transaction(&self) -> MutexGuard<'_, TransactionStatus> {
+        self.transaction
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Checks a pinned connection after a statement failed on it with a connection error
+pub trait PinnedLink {
+    // false once the server can't be reached on it any more
+    fn is_alive(&mut self) -> bool;
+}
+
+impl PinnedLink for mysql::Conn {
+    fn is_alive(&mut self) -> bool {
+        self.query_drop("DO 1").is_ok()
+    }
+}
+
+impl PinnedLink for PgPinned {
+    fn is_alive(&mut self) -> bool {
+        !self.client.is_closed()
+    }
+}
+
+// the database is a file opened in process, there is no link to lose
+impl PinnedLink for Connection {
+    fn is_alive(&mut self) -> bool {
+        true
+    }
+}
+
+// The editor's postgres connection, with what the driver knows of its state. The server
+// doesn't report it back, so it is worked out from the statements run on it
+pub struct PgPinned {
+    pub client: postgres::Client,
+    // what the search_path was last set to
+    pub schema: Option<String>,
+    // a statement failed in the open transaction, only ending it is accepted until then
+    pub aborted: bool,
+}
+
+impl PgPinned {
+    pub fn new(client: postgres::Client) -> Self {
+        PgPinned {
+            client,
+            schema: None,
+            aborted: false,
+        }
+    }
+}
+
+// A connection of the session's own, kept between requests so transactions, session variables
+// and temporary tables carry over from one query to the next. Requests using it run one at a time
+pub struct PinnedConn<C> {
+    conn: Mutex<Option<(PoolKey, C)>>,
+}
+
+impl<C> Default for PinnedConn<C> {
+    fn default() -> Self {
+        PinnedConn {
+            conn: Mutex::new(None),
+        }
+    }
+}
+
+impl<C> PinnedConn<C> {
+    // Runs `statement` on the connection for `key`, opening it first when there is none.
+    // Another key's connection is replaced, unless a transaction is open on it.
+    // A connection lost while running it is dropped, the next request opens a fresh one.
+    // One still alive is kept, dropping it would roll back its transaction
+    pub fn with<T>(
+        &self,
+        key: PoolKey,
+        in_transaction: bool,
+        open: impl FnOnce() -> ApiResult<C>,
+        statement: impl FnOnce(&mut C) -> ApiResult<T>,
+    ) -> ApiResult<T>
+    where
+        C: PinnedLink,
+    {
+        let mut pinned = self.lock();
+
+        let conn = match pinned.take() {
+            Some((pinned_key, conn)) if pinned_key == key => pinned.insert((pinned_key, conn)),
+            Some((pinned_key, conn)) if in_transaction => {
+                *pinned = Some((pinned_key, conn));
+                return Err(ApiError::request(
+                    "A transaction is open on another database, commit or roll it back first",
+                ));
+            }
+            _ => pinned.insert((key, open()?)),
+        };
+
+        let result = statement(&mut conn.1);
+        if matches!(&result, Err(err) if err.category == ErrorCategory::Connection)
+            && !conn.1.is_alive()
+        {
+            *pinned = None;
+        }
+        result
+    }
+
+    // Runs `statement` on the connection if one is open, whatever its key
+    pub fn with_current<T>(
+        &self,
+        statement: impl FnOnce(&mut C) -> ApiResult<T>,
+    ) -> ApiResult<Option<T>> {
+        match self.lock().as_mut() {
+            Some((_, conn)) => statement(conn).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.lock() = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<(PoolKey, C)>> {
+        self.conn
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Handles of statements in flight per profile, so a cancel request can stop them
+// from another thread. Mysql connection ids, postgres cancel tokens and sqlite interrupt handles
+pub struct RunningQueries<H> {
+    next_token: Arc<AtomicU64>,
+    handles: Arc<Mutex<HashMap<String, ProfileQueries<H>>>>,
+}
+
+// keyed by the token handed out by `track`
+type ProfileQueries<H> = HashMap<u64, Running<H>>;
+
+struct Running<H> {
+    handle: H,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<H> Default for RunningQueries<H> {
+    fn default() -> Self {
+        RunningQueries {
+            next_token: Arc::new(AtomicU64::new(0)),
+            handles: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<H> Clone for RunningQueries<H> {
+    fn clone(&self) -> Self {
+        RunningQueries {
+            next_token: self.next_token.clone(),
+            handles: self.handles.clone(),
+        }
+    }
+}
+
+impl<H: Clone> RunningQueries<H> {
+    // The statement counts as running until the returned guard is dropped
+    pub fn track(&self, profile: &str, handle: H) -> RunningQuery<H> {
+        let token = self.next_token.fetch_add(1, Ordering::SeqCst);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.lock().entry(profile.to_string()).or_default().insert(
+            token,
+            Running {
+                handle,
+                cancelled: cancelled.clone(),
+            },
+        );
+
+        RunningQuery {
+            queries: self.clone(),
+            profile: profile.to_string(),
+            token,
+            cancelled,
+        }
+    }
+
+    // Flags the profile's statements as cancelled and returns their handles for the driver to stop
+    pub fn cancel(&self, profile: &str) -> Vec<H> {
+        self.lock()
+            .get(profile)
+            .map(|running| {
+                running
+                    .values()
+                    .map(|running| {
+                        running.cancelled.store(true, Ordering::SeqCst);
+                        running.handle.clone()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, ProfileQueries<H>>> {
+        self.handles
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub struct RunningQuery<H: Clone> {
+    queries: RunningQueries<H>,
+    profile: String,
+    token: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<H: Clone> RunningQuery<H> {
+    // Runs the statement, a watchdog calls `stop` if it is still running once `timeout` passed
+    pub fn run<T>(
+        &self,
+        timeout: Option<Duration>,
+        stop: impl FnOnce() -> ApiResult<usize> + Send,
+        statement: impl FnOnce() -> ApiResult<T>,
+    ) -> ApiResult<T> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return self.finish(statement()),
+        };
+
+        let timed_out = &AtomicBool::new(false);
+        let result = thread::scope(|scope| {
+            let (done, finished) = mpsc::channel::<()>();
+            scope.spawn(move || {
+                if finished.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    if let Err(err) = stop() {
+                        warn!("Could not stop a query past its timeout: {}", err);
+                    }
+                }
+            });
+
+            let result = statement();
+            drop(done);
+            result
+        });
+
+        match timed_out.load(Ordering::SeqCst) && !self.cancelled.load(Ordering::SeqCst) {
+            true => Err(ApiError::new(
+                ErrorCategory::Timeout,
+                format!(
+                    "Query stopped after running for {}s, the longest this profile allows",
+                    timeout.as_secs()
+                ),
+            )),
+            false => self.finish(result),
+        }
+    }
+
+    // A cancelled statement may fail or stop early with a partial result,
+    // either way it is reported as cancelled
+    fn finish<T>(&self, result: ApiResult<T>) -> ApiResult<T> {
+        match self.cancelled.load(Ordering::SeqCst) {
+            true => Err(ApiError::new(
+                ErrorCategory::Cancelled,
+                "Query cancelled by request",
+            )),
+            false => result,
+        }
+    }
+}
+
+impl<H: Clone> Drop for RunningQuery<H> {
+    fn drop(&mut self) {
+        let mut handles = self.queries.lock();
+
+        if let Some(running) = handles.get_mut(&self.profile) {
+            running.remove(&self.token);
+            if running.is_empty() {
+                handles.remove(&self.profile);
+            }
+        }
+    }
+}
@@ -1,51 +1,176 @@
-use controllers::mysql::{self, send_event, send_json};
-use log::debug;
-use rustql_types::{ApiAction, ApiRequest};
-use std::net::TcpListener;
-use tungstenite::Message;
+use controllers::actions::{self, send_error, send_response, server_hello};
+use futures::{SinkExt, StreamExt};
+use helpers::{config::ServerConfig, session::Session};
+use log::{debug, error, info};
+use rustql_types::{ApiError, ApiRequest, ErrorCategory, Response};
+use std::{process, sync::Arc, time::Duration};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+    time,
+};
+use tokio_tungstenite::{
+    accept_async,
+    tungstenite::{
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
+};
 
 mod controllers;
+mod drivers;
 mod helpers;
 
-use tungstenite::{
-    accept_hdr,
-    handshake::server::{Request, Response},
-};
+// How long sessions get to finish in-flight requests once shutdown starts
+static SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    create_websocket_listener();
+
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+
+    create_websocket_listener(config).await;
+}
+
+async fn create_websocket_listener(config: ServerConfig) {
+    let server = match TcpListener::bind(config.address()).await {
+        Ok(server) => server,
+        Err(err) => return error!("Could not bind {}: {}", config.address(), err),
+    };
+    info!("Listening on ws://{}", config.address());
+
+    let (shutdown, shutdown_signal) = watch::channel(false);
+    // every session holds a sender, recv returns None once they have all finished
+    let (session_guard, mut sessions_closed) = mpsc::channel::<()>(1);
+
+    loop {
+        tokio::select! {
+            accepted = server.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let session = Session::new(config.max_rows);
+                    tokio::spawn(handle_session(stream, session, shutdown_signal.clone(), session_guard.clone()));
+                }
+                Err(err) => debug!("Failed to accept connection: {}", err),
+            },
+            _ = shutdown_requested() => break,
+        }
+    }
+
+    info!("Shutting down, closing open sessions");
+    shutdown.send(true).ok();
+    drop(session_guard);
+
+    if time::timeout(SHUTDOWN_GRACE, sessions_closed.recv()).await.is_err() {
+        info!("Sessions did not close in time");
+    }
+}
+
+async fn shutdown_requested() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(_) => {
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.ok();
+    }
 }
 
-fn create_websocket_listener() {
-    let server = TcpListener::bind("127.0.0.1:8888").unwrap();
-    for stream in server.incoming() {
-        tokio::spawn(async {
-            let callback = |_req: &Request, response: Response| Ok(response);
-            let mut websocket = accept_hdr(stream.unwrap(), callback).unwrap();
-
-            // initial message to react on socket reconnect
-            websocket
-                .write_message(Message::Text(send_event(ApiAction::Init)))
-                .unwrap();
-
-            loop {
-                let msg = websocket.read_message().unwrap();
-
-                if msg.is_text() && !msg.is_empty() {
-                    match serde_json::from_str::<ApiRequest>(&msg.to_string()) {
-                        Ok(request) => {
-                            let response = mysql::run_action(request);
-
-                            websocket
-                                .write_message(Message::Text(response.await))
-                                .unwrap();
-                        }
-                        Err(err) => debug!("{}", err.to_string()),
-                    }
+async fn handle_session(
+    stream: TcpStream,
+    session: Session,
+    mut shutdown: watch::Receiver<bool>,
+    _guard: mpsc::Sender<()>,
+) {
+    let websocket = match accept_async(stream).await {
+        Ok(websocket) => websocket,
+        Err(err) => return debug!("Websocket handshake failed: {}", err),
+    };
+
+    let (mut sink, mut incoming) = websocket.split();
+    let session = Arc::new(session);
+    let (responses, mut outgoing) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(session.mysql_pools.sweeper());
+    tokio::spawn(session.pg_pools.sweeper());
+    tokio::spawn(session.sqlite_pools.sweeper());
+
+    // initial message to react on socket reconnect, the client answers with Init
+    let hello = send_response(None, Response::Hello(server_hello()));
+    if let Err(err) = sink.send(Message::Text(hello)).await {
+        return debug!("Failed to send hello: {}", err);
+    }
+
+    loop {
+        tokio::select! {
+            Some(response) = outgoing.recv() => {
+                if let Err(err) = sink.send(Message::Text(response)).await {
+                    debug!("Failed to send response: {}", err);
+                    break;
+                }
+            }
+            msg = incoming.next() => match msg {
+                Some(Ok(Message::Text(text))) if !text.is_empty() => {
+                    handle_message(text, &session, &responses);
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(err)) => {
+                    debug!("Websocket closed: {}", err);
+                    break;
                 }
+            },
+            _ = shutdown.changed() => {
+                let frame = CloseFrame {
+                    code: CloseCode::Away,
+                    reason: "Server shutting down".into(),
+                };
+                sink.send(Message::Close(Some(frame))).await.ok();
+                break;
             }
-        });
+        }
+    }
+
+    session.close();
+}
+
+fn handle_message(text: String, session: &Arc<Session>, responses: &mpsc::UnboundedSender<String>) {
+    match serde_json::from_str::<ApiRequest>(&text) {
+        Ok(ApiRequest { id, request }) => {
+            // answered out of order, the client matches responses by request id
+            let session = session.clone();
+            let responses = responses.clone();
+            tokio::spawn(actions::run_action(id, request, session, responses));
+        }
+        Err(err) => {
+            // still answer the request id when only the payload is unknown
+            let id = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|value| value["id"].as_u64());
+            let error = ApiError::new(
+                ErrorCategory::Protocol,
+                format!("Unsupported request: {}", err),
+            );
+            responses.send(send_error(error, id)).ok();
+        }
     }
 }
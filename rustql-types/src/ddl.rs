@@ -0,0 +1,331 @@
+use crate::{AlterIndexRequest, DriverKind, IndexChange, IndexColumn, IndexDefinition};
+
+// MySQL index kinds written ahead of INDEX rather than after USING
+static MYSQL_INDEX_KINDS: [&str; 2] = ["FULLTEXT", "SPATIAL"];
+
+// PostgreSQL's schema for requests without one
+static DEFAULT_SCHEMA: &str = "public";
+
+// The statement an AlterIndex request runs. The client shows it before sending the request
+// and the api runs exactly this. MySQL changes indexes with ALTER TABLE, PostgreSQL and SQLite
+// have no such clause and use CREATE INDEX and DROP INDEX
+pub fn alter_index_ddl(driver: DriverKind, request: &AlterIndexRequest) -> String {
+    match &request.change {
+        IndexChange::Create(index) => create_index(driver, request, index),
+        IndexChange::Drop { name, primary } => {
+            let name = driver.quote_identifier(name);
+            match driver {
+                DriverKind::MySql if *primary => {
+                    format!(
+                        "ALTER TABLE {} DROP PRIMARY KEY",
+                        table_name(driver, request)
+                    )
+                }
+                DriverKind::MySql => {
+                    format!(
+                        "ALTER TABLE {} DROP INDEX {}",
+                        table_name(driver, request),
+                        name
+                    )
+                }
+                DriverKind::Postgres if *primary => format!(
+                    "ALTER TABLE {} DROP CONSTRAINT {}",
+                    table_name(driver, request),
+                    name
+                ),
+                DriverKind::Postgres | DriverKind::Sqlite => {
+                    format!("DROP INDEX {}.{}", container(driver, request), name)
+                }
+            }
+        }
+    }
+}
+
+fn create_index(
+    driver: DriverKind,
+    request: &AlterIndexRequest,
+    index: &IndexDefinition,
+) -> String {
+    let columns = index
+        .columns
+        .iter()
+        .map(|column| key_part(driver, column))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let index_type = index
+        .index_type
+        .as_deref()
+        .map(str::trim)
+        .filter(|index_type| !index_type.is_empty());
+    let unique = match index.unique {
+        true => "UNIQUE ",
+        false => "",
+    };
+
+    match driver {
+        DriverKind::MySql => {
+            let index_type = index_type.map(str::to_uppercase);
+            let (kind, using) = match index_type {
+                Some(kind) if MYSQL_INDEX_KINDS.contains(&kind.as_str()) => {
+                    (format!("{} ", kind), None)
+                }
+                using => (unique.to_string(), using),
+            };
+
+            let mut ddl = format!(
+                "ALTER TABLE {} ADD {}INDEX ",
+                table_name(driver, request),
+                kind
+            );
+            if !index.name.is_empty() {
+                ddl.push_str(&driver.quote_identifier(&index.name));
+                ddl.push(' ');
+            }
+            ddl.push_str(&format!("({})", columns));
+            if let Some(using) = using {
+                ddl.push_str(&format!(" USING {}", using));
+            }
+            ddl
+        }
+        DriverKind::Postgres => {
+            let mut ddl = format!("CREATE {}INDEX ", unique);
+            if !index.name.is_empty() {
+                ddl.push_str(&driver.quote_identifier(&index.name));
+                ddl.push(' ');
+            }
+            ddl.push_str(&format!("ON {}", table_name(driver, request)));
+            if let Some(using) = index_type {
+                ddl.push_str(&format!(" USING {}", using.to_lowercase()));
+            }
+            ddl.push_str(&format!(" ({})", columns));
+            ddl
+        }
+        // the index lives in the table's database, so only its own name is qualified
+        DriverKind::Sqlite => {
+            let name = match index.name.is_empty() {
+                true => default_index_name(request, index),
+                false => index.name.clone(),
+            };
+            format!(
+                "CREATE {}INDEX {}.{} ON {} ({})",
+                unique,
+                container(driver, request),
+                driver.quote_identifier(&name),
+                driver.quote_identifier(&request.table),
+                columns
+            )
+        }
+    }
+}
+
+// `idx_<table>_<columns>` for a driver that won't name an index itself, expressions left out
+fn default_index_name(request: &AlterIndexRequest, index: &IndexDefinition) -> String {
+    let mut name = format!("idx_{}", request.table);
+    for column in index.columns.iter().filter(|column| !column.expression) {
+        name.push('_');
+        name.push_str(&column.name);
+    }
+    name
+}
+
+fn key_part(driver: DriverKind, column: &IndexColumn) -> String {
+    let mut part = match column.expression {
+        true => format!("({})", column.name),
+        false => driver.quote_identifier(&column.name),
+    };
+    if let (DriverKind::MySql, Some(length), false) = (driver, column.sub_part, column.expression) {
+        part.push_str(&format!("({})", length));
+    }
+    if column.descending {
+        part.push_str(" DESC");
+    }
+    part
+}
+
+// The database on MySQL and SQLite, the schema on PostgreSQL
+fn container(driver: DriverKind, request: &AlterIndexRequest) -> String {
+    match driver {
+        DriverKind::Postgres => {
+            driver.quote_identifier(request.schema.as_deref().unwrap_or(DEFAULT_SCHEMA))
+        }
+        DriverKind::MySql | DriverKind::Sqlite => driver.quote_identifier(&request.db),
+    }
+}
+
+fn table_name(driver: DriverKind, request: &AlterIndexRequest) -> String {
+    format!(
+        "{}.{}",
+        container(driver, request),
+        driver.quote_identifier(&request.table)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(change: IndexChange) -> AlterIndexRequest {
+        AlterIndexRequest {
+            db: String::from("main"),
+            schema: None,
+            table: String::from("orders"),
+            change,
+        }
+    }
+
+    fn column(name: &str) -> IndexColumn {
+        IndexColumn {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn index(name: &str, columns: Vec<IndexColumn>) -> IndexDefinition {
+        IndexDefinition {
+            name: name.to_string(),
+            columns,
+            unique: false,
+            index_type: None,
+        }
+    }
+
+    fn create(driver: DriverKind, name: &str) -> String {
+        let expression = IndexColumn {
+            expression: true,
+            ..column("lower(note)")
+        };
+        let change = IndexChange::Create(index(name, vec![column("customer_id"), expression]));
+        alter_index_ddl(driver, &request(change))
+    }
+
+    fn drop_index(driver: DriverKind, name: &str, primary: bool) -> String {
+        let change = IndexChange::Drop {
+            name: name.to_string(),
+            primary,
+        };
+        alter_index_ddl(driver, &request(change))
+    }
+
+    #[test]
+    fn names_unnamed_sqlite_indexes() {
+        assert_eq!(
+            create(DriverKind::Sqlite, ""),
+            "CREATE INDEX \"main\".\"idx_orders_customer_id\" ON \"orders\" (\"customer_id\", (lower(note)))"
+        );
+        assert_eq!(
+            create(DriverKind::Sqlite, "by_customer"),
+            "CREATE INDEX \"main\".\"by_customer\" ON \"orders\" (\"customer_id\", (lower(note)))"
+        );
+        // the server names them
+        assert_eq!(
+            create(DriverKind::Postgres, ""),
+            "CREATE INDEX ON \"public\".\"orders\" (\"customer_id\", (lower(note)))"
+        );
+    }
+
+    #[test]
+    fn adds_mysql_indexes() {
+        let mysql =
+            |index| alter_index_ddl(DriverKind::MySql, &request(IndexChange::Create(index)));
+        let note = IndexColumn {
+            sub_part: Some(10),
+            descending: true,
+            ..column("note")
+        };
+
+        assert_eq!(
+            mysql(IndexDefinition {
+                index_type: Some(String::from("btree")),
+                ..index("by_note", vec![note.clone(), column("customer_id")])
+            }),
+            "ALTER TABLE `main`.`orders` ADD INDEX `by_note` (`note`(10) DESC, `customer_id`) USING BTREE"
+        );
+        assert_eq!(
+            mysql(IndexDefinition {
+                unique: true,
+                ..index("", vec![column("code")])
+            }),
+            "ALTER TABLE `main`.`orders` ADD UNIQUE INDEX (`code`)"
+        );
+        // prefix lengths are mysql's own, and never apply to expressions
+        assert_eq!(
+            mysql(index(
+                "by_lower",
+                vec![IndexColumn {
+                    expression: true,
+                    ..note.clone()
+                }]
+            )),
+            "ALTER TABLE `main`.`orders` ADD INDEX `by_lower` ((note) DESC)"
+        );
+        assert_eq!(
+            alter_index_ddl(
+                DriverKind::Postgres,
+                &request(IndexChange::Create(index("by_note", vec![note])))
+            ),
+            "CREATE INDEX \"by_note\" ON \"public\".\"orders\" (\"note\" DESC)"
+        );
+    }
+
+    #[test]
+    fn writes_mysql_index_kinds_ahead_of_index() {
+        let mysql = |index_type: &str, unique| {
+            let index = IndexDefinition {
+                unique,
+                index_type: Some(index_type.to_string()),
+                ..index("search", vec![column("note")])
+            };
+            alter_index_ddl(DriverKind::MySql, &request(IndexChange::Create(index)))
+        };
+
+        assert_eq!(
+            mysql("fulltext", false),
+            "ALTER TABLE `main`.`orders` ADD FULLTEXT INDEX `search` (`note`)"
+        );
+        // neither kind can be unique
+        assert_eq!(
+            mysql(" Spatial ", true),
+            "ALTER TABLE `main`.`orders` ADD SPATIAL INDEX `search` (`note`)"
+        );
+        assert_eq!(
+            mysql("hash", true),
+            "ALTER TABLE `main`.`orders` ADD UNIQUE INDEX `search` (`note`) USING HASH"
+        );
+    }
+
+    #[test]
+    fn drops_indexes_and_primary_keys() {
+        assert_eq!(
+            drop_index(DriverKind::MySql, "by_note", false),
+            "ALTER TABLE `main`.`orders` DROP INDEX `by_note`"
+        );
+        assert_eq!(
+            drop_index(DriverKind::MySql, "PRIMARY", true),
+            "ALTER TABLE `main`.`orders` DROP PRIMARY KEY"
+        );
+        assert_eq!(
+            drop_index(DriverKind::Postgres, "orders_pkey", true),
+            "ALTER TABLE \"public\".\"orders\" DROP CONSTRAINT \"orders_pkey\""
+        );
+        assert_eq!(
+            drop_index(DriverKind::Postgres, "by_note", false),
+            "DROP INDEX \"public\".\"by_note\""
+        );
+        assert_eq!(
+            drop_index(DriverKind::Sqlite, "by_note", false),
+            "DROP INDEX \"main\".\"by_note\""
+        );
+
+        let in_schema = AlterIndexRequest {
+            schema: Some(String::from("sales")),
+            ..request(IndexChange::Drop {
+                name: String::from("by_note"),
+                primary: false,
+            })
+        };
+        assert_eq!(
+            alter_index_ddl(DriverKind::Postgres, &in_schema),
+            "DROP INDEX \"sales\".\"by_note\""
+        );
+    }
+}
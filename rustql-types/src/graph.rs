@@ -0,0 +1,664 @@
+use crate::ForeignKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use strum_macros::{Display, EnumIter};
+
+// Size of a table's box, shared by the diagram page and the SVG export so both draw alike
+pub static TABLE_WIDTH: f64 = 240.0;
+pub static HEADER_HEIGHT: f64 = 28.0;
+pub static ROW_HEIGHT: f64 = 20.0;
+
+// Space between boxes placed without a saved position
+static GRID_GAP_X: f64 = 80.0;
+static GRID_GAP_Y: f64 = 60.0;
+// How far an edge leaves its box before bending towards the other one
+static EDGE_HANDLE: f64 = 40.0;
+// Blank border around the exported drawing
+static SVG_MARGIN: f64 = 20.0;
+
+// The tables of a database (or schema) and the foreign keys between them
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SchemaGraph {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub tables: Vec<GraphTable>,
+    // keys between two tables of the graph, with their columns in order
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct GraphTable {
+    pub name: String,
+    pub columns: Vec<GraphColumn>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct GraphColumn {
+    pub name: String,
+    pub sql_type: String,
+    pub primary_key: bool,
+}
+
+// Top left corner of a table's box
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+// Box positions by table name
+pub type GraphLayout = BTreeMap<String, Point>;
+
+// A table and the tables up to `hops` foreign keys away from it
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct GraphFocus {
+    pub table: String,
+    pub hops: usize,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Display, EnumIter)]
+pub enum GraphFormat {
+    #[strum(to_string = "SVG")]
+    Svg,
+    #[strum(to_string = "DOT")]
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Svg => "svg",
+            GraphFormat::Dot => "dot",
+            GraphFormat::Mermaid => "mmd",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Svg => "image/svg+xml",
+            GraphFormat::Dot | GraphFormat::Mermaid => "text/plain",
+        }
+    }
+}
+
+impl GraphTable {
+    pub fn height(&self) -> f64 {
+        HEADER_HEIGHT + ROW_HEIGHT * self.columns.len() as f64
+    }
+
+    // Middle of the column's row from the top of the box, the header's middle for unknown columns
+    pub fn column_y(&self, column: &str) -> f64 {
+        match self.columns.iter().position(|found| found.name == column) {
+            Some(index) => HEADER_HEIGHT + ROW_HEIGHT * (index as f64 + 0.5),
+            None => HEADER_HEIGHT / 2.0,
+        }
+    }
+}
+
+impl SchemaGraph {
+    pub fn table(&self, name: &str) -> Option<&GraphTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+
+    // Keys whose tables are both drawn
+    pub fn edges(&self) -> impl Iterator<Item = &ForeignKey> {
+        self.foreign_keys.iter().filter(move |key| {
+            self.table(&key.table.table).is_some()
+                && self.table(&key.referenced_table.table).is_some()
+        })
+    }
+
+    // The focused table and its neighbours, following keys both ways
+    pub fn focused(&self, focus: Option<&GraphFocus>) -> SchemaGraph {
+        let focus = match focus {
+            Some(focus) if self.table(&focus.table).is_some() => focus,
+            _ => return self.clone(),
+        };
+
+        let mut kept = BTreeSet::new();
+        kept.insert(focus.table.as_str());
+        let mut frontier = vec![focus.table.as_str()];
+        for _ in 0..focus.hops {
+            let mut next = vec![];
+            for key in self.edges() {
+                let (from, to) = (
+                    key.table.table.as_str(),
+                    key.referenced_table.table.as_str(),
+                );
+                for (near, far) in [(from, to), (to, from)].iter() {
+                    if frontier.contains(near) && kept.insert(*far) {
+                        next.push(*far);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        SchemaGraph {
+            db: self.db.clone(),
+            schema: self.schema.clone(),
+            tables: self
+                .tables
+                .iter()
+                .filter(|table| kept.contains(table.name.as_str()))
+                .cloned()
+                .collect(),
+            foreign_keys: self.foreign_keys.clone(),
+        }
+    }
+
+    // Saved positions, with the tables that have none laid out on a grid in name order
+    pub fn layout(&self, saved: &GraphLayout) -> GraphLayout {
+        let per_row = (self.tables.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let mut layout = GraphLayout::new();
+        let mut y = 0.0;
+
+        for row in self.tables.chunks(per_row) {
+            for (index, table) in row.iter().enumerate() {
+                let position = saved.get(&table.name).copied().unwrap_or(Point {
+                    x: index as f64 * (TABLE_WIDTH + GRID_GAP_X),
+                    y,
+                });
+                layout.insert(table.name.clone(), position);
+            }
+            y += row.iter().map(GraphTable::height).fold(0.0, f64::max) + GRID_GAP_Y;
+        }
+
+        layout
+    }
+
+    // SVG path of a key's edge, from its first column to the referenced one.
+    // It leaves and enters the boxes on their facing sides, a self reference loops on the right
+    pub fn edge_path(&self, layout: &GraphLayout, key: &ForeignKey) -> Option<String> {
+        let table = self.table(&key.table.table)?;
+        let referenced = self.table(&key.referenced_table.table)?;
+        let from = layout.get(&table.name)?;
+        let to = layout.get(&referenced.name)?;
+
+        let y1 = from.y + table.column_y(key.columns.first()?);
+        let y2 = to.y + referenced.column_y(key.referenced_columns.first()?);
+        let (x1, x2, handle1, handle2) = if table.name == referenced.name {
+            let x = from.x + TABLE_WIDTH;
+            (x, x, EDGE_HANDLE, EDGE_HANDLE)
+        } else if from.x + TABLE_WIDTH / 2.0 <= to.x + TABLE_WIDTH / 2.0 {
+            (from.x + TABLE_WIDTH, to.x, EDGE_HANDLE, -EDGE_HANDLE)
+        } else {
+            (from.x, to.x + TABLE_WIDTH, -EDGE_HANDLE, EDGE_HANDLE)
+        };
+
+        Some(format!(
+            "M {} {} C {} {}, {} {}, {} {}",
+            x1,
+            y1,
+            x1 + handle1,
+            y1,
+            x2 + handle2,
+            y2,
+            x2,
+            y2
+        ))
+    }
+
+    pub fn render(&self, format: GraphFormat, layout: &GraphLayout) -> String {
+        match format {
+            GraphFormat::Svg => self.to_svg(layout),
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    // A standalone drawing of the diagram as the page shows it
+    pub fn to_svg(&self, layout: &GraphLayout) -> String {
+        let layout = self.layout(layout);
+        let boxes = self
+            .tables
+            .iter()
+            .filter_map(|table| Some((table, *layout.get(&table.name)?)));
+
+        let (min_x, min_y, max_x, max_y) = boxes
+            .clone()
+            .map(|(table, position)| {
+                (
+                    position.x,
+                    position.y,
+                    position.x + TABLE_WIDTH,
+                    position.y + table.height(),
+                )
+            })
+            .fold(None, |bounds, (x1, y1, x2, y2)| match bounds {
+                Some((min_x, min_y, max_x, max_y)) => Some((
+                    f64::min(min_x, x1),
+                    f64::min(min_y, y1),
+                    f64::max(max_x, x2),
+                    f64::max(max_y, y2),
+                )),
+                None => Some((x1, y1, x2, y2)),
+            })
+            .unwrap_or_default();
+        let (x, y) = (min_x - SVG_MARGIN, min_y - SVG_MARGIN);
+        let (width, height) = (
+            max_x - min_x + SVG_MARGIN * 2.0,
+            max_y - min_y + SVG_MARGIN * 2.0,
+        );
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+            viewBox=\"{x} {y} {w} {h}\" font-family=\"monospace\" font-size=\"12\">\n",
+            x = x,
+            y = y,
+            w = width,
+            h = height
+        );
+        svg.push_str(
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+            markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\">\
+            <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#485fc7\"/></marker></defs>\n",
+        );
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n",
+            x, y, width, height
+        ));
+
+        for key in self.edges() {
+            if let Some(path) = self.edge_path(&layout, key) {
+                svg.push_str(&format!(
+                    "<path d=\"{}\" fill=\"none\" stroke=\"#485fc7\" stroke-width=\"1.5\" \
+                    marker-end=\"url(#arrow)\"/>\n",
+                    path
+                ));
+            }
+        }
+
+        for (table, position) in boxes {
+            svg.push_str(&format!(
+                "<g transform=\"translate({} {})\">\n\
+                <rect width=\"{}\" height=\"{}\" rx=\"4\" fill=\"#ffffff\" stroke=\"#b5b5b5\"/>\n\
+                <rect width=\"{}\" height=\"{}\" rx=\"4\" fill=\"#3e8ed0\"/>\n\
+                <text x=\"8\" y=\"19\" fill=\"#ffffff\" font-weight=\"bold\">{}</text>\n",
+                position.x,
+                position.y,
+                TABLE_WIDTH,
+                table.height(),
+                TABLE_WIDTH,
+                HEADER_HEIGHT,
+                escape_xml(&table.name)
+            ));
+            for column in &table.columns {
+                let y = table.column_y(&column.name) + 4.0;
+                let weight = match column.primary_key {
+                    true => " font-weight=\"bold\"",
+                    false => "",
+                };
+                svg.push_str(&format!(
+                    "<text x=\"8\" y=\"{y}\"{w}>{}</text>\
+                    <text x=\"{x}\" y=\"{y}\" text-anchor=\"end\" fill=\"#7a7a7a\">{}</text>\n",
+                    escape_xml(&column.name),
+                    escape_xml(&column.sql_type),
+                    x = TABLE_WIDTH - 8.0,
+                    y = y,
+                    w = weight
+                ));
+            }
+            svg.push_str("</g>\n");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Graphviz, a box per table with a port per column the edges attach to
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", dot_id(&self.db));
+        dot.push_str("    graph [rankdir=LR];\n");
+        dot.push_str("    node [shape=plaintext, fontname=\"monospace\"];\n");
+
+        for table in &self.tables {
+            let rows = table
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let name = match column.primary_key {
+                        true => format!("<b>{}</b>", escape_xml(&column.name)),
+                        false => escape_xml(&column.name),
+                    };
+                    format!(
+                        "<tr><td port=\"c{}\" align=\"left\">{} <font color=\"grey\">{}</font></td></tr>",
+                        index,
+                        name,
+                        escape_xml(&column.sql_type)
+                    )
+                })
+                .collect::<String>();
+
+            dot.push_str(&format!(
+                "    {} [label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\">\
+                <tr><td bgcolor=\"#3e8ed0\"><font color=\"white\"><b>{}</b></font></td></tr>{}</table>>];\n",
+                dot_id(&table.name),
+                escape_xml(&table.name),
+                rows
+            ));
+        }
+
+        for key in self.edges() {
+            let port = |table: &str, column: Option<&String>| {
+                let index = self
+                    .table(table)
+                    .and_then(|table| {
+                        table
+                            .columns
+                            .iter()
+                            .position(|found| Some(&found.name) == column)
+                    })
+                    .unwrap_or_default();
+                format!("{}:c{}", dot_id(table), index)
+            };
+            dot.push_str(&format!(
+                "    {} -> {};\n",
+                port(&key.table.table, key.columns.first()),
+                port(&key.referenced_table.table, key.referenced_columns.first())
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    // A mermaid erDiagram, names are reduced to the characters mermaid accepts
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("erDiagram\n");
+
+        for table in &self.tables {
+            mermaid.push_str(&format!("    {} {{\n", mermaid_name(&table.name)));
+            for column in &table.columns {
+                let foreign = self
+                    .edges()
+                    .any(|key| key.table.table == table.name && key.columns.contains(&column.name));
+                let keys = match (column.primary_key, foreign) {
+                    (true, true) => " PK, FK",
+                    (true, false) => " PK",
+                    (false, true) => " FK",
+                    (false, false) => "",
+                };
+                mermaid.push_str(&format!(
+                    "        {} {}{}\n",
+                    mermaid_name(&column.sql_type),
+                    mermaid_name(&column.name),
+                    keys
+                ));
+            }
+            mermaid.push_str("    }\n");
+        }
+
+        for key in self.edges() {
+            mermaid.push_str(&format!(
+                "    {} }}o--|| {} : \"{}\"\n",
+                mermaid_name(&key.table.table),
+                mermaid_name(&key.referenced_table.table),
+                key.columns.join(", ").replace('"', "'")
+            ));
+        }
+
+        mermaid
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || "_-()[]".contains(c) {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+
+    match name.is_empty() {
+        true => String::from("_"),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableRef;
+
+    fn table(name: &str, columns: &[&str]) -> GraphTable {
+        GraphTable {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|column| GraphColumn {
+                    name: column.to_string(),
+                    sql_type: String::from("int"),
+                    primary_key: *column == "id",
+                })
+                .collect(),
+        }
+    }
+
+    fn key(table: &str, column: &str, referenced: &str) -> ForeignKey {
+        let table_ref = |table: &str| TableRef {
+            db: String::from("shop"),
+            schema: None,
+            table: table.to_string(),
+        };
+        ForeignKey {
+            name: format!("{}_{}", table, column),
+            table: table_ref(table),
+            columns: vec![column.to_string()],
+            referenced_table: table_ref(referenced),
+            referenced_columns: vec![String::from("id")],
+        }
+    }
+
+    // items -> orders -> customers -> regions, and orders referencing themselves
+    fn shop() -> SchemaGraph {
+        SchemaGraph {
+            db: String::from("shop"),
+            schema: None,
+            tables: vec![
+                table("customers", &["id", "region_id"]),
+                table("items", &["id", "order_id"]),
+                table("orders", &["id", "customer_id", "parent_id"]),
+                table("regions", &["id"]),
+            ],
+            foreign_keys: vec![
+                key("orders", "customer_id", "customers"),
+                key("orders", "parent_id", "orders"),
+                key("items", "order_id", "orders"),
+                key("customers", "region_id", "regions"),
+            ],
+        }
+    }
+
+    fn names(graph: &SchemaGraph) -> Vec<&str> {
+        graph
+            .tables
+            .iter()
+            .map(|table| table.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn focuses_on_tables_within_reach() {
+        let graph = shop();
+        let focused = |hops| {
+            graph.focused(Some(&GraphFocus {
+                table: String::from("items"),
+                hops,
+            }))
+        };
+
+        assert_eq!(names(&focused(0)), vec!["items"]);
+        assert_eq!(names(&focused(1)), vec!["items", "orders"]);
+        assert_eq!(names(&focused(2)), vec!["customers", "items", "orders"]);
+        assert_eq!(names(&focused(3)).len(), 4);
+        // only the keys between kept tables are drawn, the self reference among them
+        let one_hop = focused(1);
+        let edges: Vec<&str> = one_hop.edges().map(|key| key.name.as_str()).collect();
+        assert_eq!(edges, vec!["orders_parent_id", "items_order_id"]);
+
+        // keys are followed both ways
+        let regions = graph.focused(Some(&GraphFocus {
+            table: String::from("regions"),
+            hops: 1,
+        }));
+        assert_eq!(names(&regions), vec!["customers", "regions"]);
+
+        let unknown = GraphFocus {
+            table: String::from("missing"),
+            hops: 0,
+        };
+        assert_eq!(graph.focused(Some(&unknown)), graph);
+        assert_eq!(graph.focused(None), graph);
+    }
+
+    #[test]
+    fn lays_out_unsaved_tables_on_a_grid() {
+        let graph = shop();
+        let mut saved = GraphLayout::new();
+        saved.insert(String::from("regions"), Point { x: 5.0, y: 7.0 });
+        let layout = graph.layout(&saved);
+
+        let position = |name: &str| {
+            let point = layout[name];
+            (point.x, point.y)
+        };
+        // two per row, a row as tall as its tallest box and the gap
+        assert_eq!(position("customers"), (0.0, 0.0));
+        assert_eq!(position("items"), (TABLE_WIDTH + GRID_GAP_X, 0.0));
+        assert_eq!(
+            position("orders"),
+            (0.0, HEADER_HEIGHT + ROW_HEIGHT * 2.0 + GRID_GAP_Y)
+        );
+        assert_eq!(position("regions"), (5.0, 7.0));
+    }
+
+    #[test]
+    fn draws_edges_between_facing_sides() {
+        let graph = shop();
+        let layout = graph.layout(&GraphLayout::new());
+        let path = |name: &str| {
+            let key = graph
+                .foreign_keys
+                .iter()
+                .find(|key| key.name == name)
+                .unwrap();
+            graph.edge_path(&layout, key).unwrap()
+        };
+
+        // items sits right of orders, the edge leaves its left side
+        assert_eq!(
+            path("items_order_id"),
+            "M 320 58 C 280 58, 280 166, 240 166"
+        );
+        // a self reference loops out of and back into the right side
+        assert_eq!(
+            path("orders_parent_id"),
+            "M 240 206 C 280 206, 280 166, 240 166"
+        );
+        // boxes in one column are joined from the right side to the left one
+        assert_eq!(
+            path("orders_customer_id"),
+            "M 240 186 C 280 186, -40 38, 0 38"
+        );
+
+        let mut unplaced = layout.clone();
+        unplaced.remove("orders");
+        assert_eq!(graph.edge_path(&unplaced, &graph.foreign_keys[2]), None);
+    }
+
+    #[test]
+    fn exports_svg() {
+        let graph = shop();
+        let svg = graph.to_svg(&GraphLayout::new());
+
+        // the boxes span 560 by 216, with the margin around them
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"600\" height=\"256\" \
+            viewBox=\"-20 -20 600 256\""
+        ));
+        assert_eq!(svg.matches("marker-end=\"url(#arrow)\"").count(), 4);
+        assert!(svg.contains("<path d=\"M 240 206 C 280 206, 280 166, 240 166\""));
+        assert!(svg.contains("<g transform=\"translate(320 0)\">"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn exports_dot_and_mermaid() {
+        let graph = shop().focused(Some(&GraphFocus {
+            table: String::from("items"),
+            hops: 1,
+        }));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph \"shop\" {\n"));
+        assert!(dot.contains("    \"orders\":c2 -> \"orders\":c0;\n"));
+        assert!(dot.contains("    \"items\":c1 -> \"orders\":c0;\n"));
+        // customers isn't drawn, neither is the key to it
+        assert!(!dot.contains("customers\""));
+
+        assert_eq!(
+            graph.to_mermaid(),
+            "erDiagram
+    items {
+        int id PK
+        int order_id FK
+    }
+    orders {
+        int id PK
+        int customer_id
+        int parent_id FK
+    }
+    orders }o--|| orders : \"parent_id\"
+    items }o--|| orders : \"order_id\"
+"
+        );
+    }
+
+    #[test]
+    fn escapes_identifiers() {
+        let mut odd = table("a\"b<c>", &["x&y"]);
+        odd.columns[0].sql_type = String::from("varchar(10)");
+        let graph = SchemaGraph {
+            db: String::from("my \"db\\"),
+            schema: None,
+            tables: vec![odd, table("plain", &["id"])],
+            foreign_keys: vec![ForeignKey {
+                columns: vec![String::from("x&y")],
+                ..key("a\"b<c>", "x\"y", "plain")
+            }],
+        };
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph \"my \\\"db\\\\\" {\n"));
+        assert!(dot.contains("    \"a\\\"b<c>\" [label=<"));
+        assert!(dot.contains("<b>a&quot;b&lt;c&gt;</b>"));
+        assert!(dot.contains(">x&amp;y <font color=\"grey\">varchar(10)</font>"));
+        assert!(dot.contains("    \"a\\\"b<c>\":c0 -> \"plain\":c0;\n"));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.contains("    a_b_c_ {\n        varchar(10) x_y FK\n    }\n"));
+        assert!(mermaid.contains("    a_b_c_ }o--|| plain : \"x&y\"\n"));
+
+        let svg = graph.to_svg(&GraphLayout::new());
+        assert!(svg.contains(">a&quot;b&lt;c&gt;</text>"));
+        assert!(svg.contains(">x&amp;y</text>"));
+    }
+}
@@ -1,53 +1,281 @@
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, usize};
-use strum_macros::{Display, EnumString};
+use std::{fmt, time::Duration};
+use strum_macros::{Display, EnumIter, EnumString};
 
-#[derive(Serialize, EnumString, Display)]
-pub enum ApiAction {
-    LoadTables,
-    RunQuery,
-    LoadTable,
-    Init,
-    Error,
+mod ddl;
+mod graph;
+mod protocol;
+mod script;
+
+pub use ddl::*;
+pub use graph::*;
+pub use protocol::*;
+pub use script::*;
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Display)]
+pub enum ErrorCategory {
+    // malformed or unsupported request from the client
+    Request,
+    // could not reach or authenticate with the database server
+    Connection,
+    // the database server rejected the statement
+    Query,
+    // a value could not be converted between the server and the api
+    Conversion,
+    // connection profiles could not be read or written
+    Profile,
+    // the client and api disagree on the protocol
+    Protocol,
+    // the statement was stopped by a cancel request
+    Cancelled,
+    // the statement ran longer than its profile's max execution time
+    Timeout,
+    // the result went over its profile's max rows
+    #[strum(to_string = "Row Limit")]
+    RowLimit,
+    // the result went over its profile's max response size
+    #[strum(to_string = "Size Limit")]
+    SizeLimit,
+    Internal,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
-pub struct ApiRequest {
-    pub action: String,
-    pub data: Option<String>,
+impl Default for ErrorCategory {
+    fn default() -> Self {
+        ErrorCategory::Internal
+    }
 }
 
-impl ApiRequest {
-    pub fn create_data<T: Serialize>(action: ApiAction, data: T) -> ApiRequest {
-        let request_data = match serde_json::to_string(&data) {
-            Ok(d) => Some(d),
-            Err(_) => None,
-        };
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ApiError {
+    pub category: ErrorCategory,
+    pub message: String,
+    pub code: Option<u16>,
+    pub sql_state: Option<String>,
+    pub action: Option<String>,
+}
+
+impl ApiError {
+    pub fn new<T: Into<String>>(category: ErrorCategory, message: T) -> ApiError {
+        ApiError {
+            category,
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn request<T: Into<String>>(message: T) -> ApiError {
+        ApiError::new(ErrorCategory::Request, message)
+    }
+
+    pub fn with_action<T: ToString>(mut self, action: T) -> ApiError {
+        self.action = Some(action.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, &self.sql_state) {
+            (Some(code), Some(state)) => write!(
+                f,
+                "{} ({}, {}): {}",
+                self.category, code, state, self.message
+            ),
+            (Some(code), None) => write!(f, "{} ({}): {}", self.category, code, self.message),
+            _ => write!(f, "{}: {}", self.category, self.message),
+        }
+    }
+}
+
+// Database backend a profile connects to, picks the driver used by the api
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Display, EnumIter, EnumString)]
+pub enum DriverKind {
+    MySql,
+    Postgres,
+    Sqlite,
+}
 
-        ApiRequest {
-            action: action.to_string(),
-            data: request_data,
+impl Default for DriverKind {
+    fn default() -> Self {
+        DriverKind::MySql
+    }
+}
+
+impl DriverKind {
+    // None for file backed drivers, which connect through the profile's path
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            DriverKind::MySql => Some(3306),
+            DriverKind::Postgres => Some(5432),
+            DriverKind::Sqlite => None,
+        }
+    }
+
+    pub fn is_file_based(&self) -> bool {
+        self.default_port().is_none()
+    }
+
+    // Quotes a table or column name for use in a query against this backend
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            DriverKind::MySql => format!("`{}`", name.replace('`', "``")),
+            DriverKind::Postgres | DriverKind::Sqlite => {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ConnectionProfile {
+    pub name: String,
+    #[serde(default)]
+    pub driver: DriverKind,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub database: Option<String>,
+    // database file for file based drivers (sqlite)
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub pool: PoolSettings,
+    #[serde(default)]
+    pub limits: QueryLimits,
+}
+
+impl Default for ConnectionProfile {
+    fn default() -> Self {
+        ConnectionProfile {
+            name: String::new(),
+            driver: DriverKind::default(),
+            host: String::from("localhost"),
+            port: 3306,
+            user: String::from("root"),
+            password: String::new(),
+            database: None,
+            path: None,
+            pool: PoolSettings::default(),
+            limits: QueryLimits::default(),
         }
     }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(default)]
+pub struct PoolSettings {
+    pub min_connections: usize,
+    pub max_connections: usize,
+    pub idle_timeout_secs: u64,
+    pub health_check: bool,
+}
 
-    pub fn create(action: ApiAction) -> ApiRequest {
-        ApiRequest {
-            action: action.to_string(),
-            data: None,
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings {
+            min_connections: 1,
+            max_connections: 10,
+            idle_timeout_secs: 300,
+            health_check: true,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
-pub struct ApiResponse {
-    pub action: String,
-    pub data: Option<String>,
+// Guardrails for statements run from the query editor, None leaves a limit off
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(default)]
+pub struct QueryLimits {
+    pub max_execution_secs: Option<u64>,
+    pub max_rows: Option<usize>,
+    // size of the values sent back, as serialized
+    pub max_response_bytes: Option<usize>,
+}
+
+impl QueryLimits {
+    pub fn max_execution_time(&self) -> Option<Duration> {
+        self.max_execution_secs.map(Duration::from_secs)
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ProfileTestResult {
+    pub name: String,
+    pub success: bool,
+    pub message: String,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub struct Database {
+    pub name: String,
+    // base tables only, views are listed with the other objects
+    pub tables: Vec<String>,
+    // backends with schemas (postgres) list their tables here instead of in tables
+    #[serde(default)]
+    pub schemas: Vec<Schema>,
+    #[serde(default)]
+    pub objects: Vec<SchemaObject>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Schema {
     pub name: String,
     pub tables: Vec<String>,
+    #[serde(default)]
+    pub objects: Vec<SchemaObject>,
+}
+
+// A view, routine, trigger or event living next to the tables
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SchemaObject {
+    pub name: String,
+    pub kind: ObjectKind,
+}
+
+#[derive(
+    Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Display, EnumIter,
+)]
+pub enum ObjectKind {
+    View,
+    Procedure,
+    Function,
+    Trigger,
+    Event,
+}
+
+impl Default for ObjectKind {
+    fn default() -> Self {
+        ObjectKind::View
+    }
+}
+
+impl ObjectKind {
+    // Heading of the kind's group in the schema tree
+    pub fn plural(&self) -> &'static str {
+        match self {
+            ObjectKind::View => "Views",
+            ObjectKind::Procedure => "Procedures",
+            ObjectKind::Function => "Functions",
+            ObjectKind::Trigger => "Triggers",
+            ObjectKind::Event => "Events",
+        }
+    }
+
+    // Views can be browsed like tables, the other kinds only have a definition
+    pub fn has_rows(&self) -> bool {
+        matches!(self, ObjectKind::View)
+    }
+}
+
+// The statement creating an object, as the server reports it
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ObjectDefinition {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub name: String,
+    pub kind: ObjectKind,
+    pub definition: String,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
@@ -56,24 +284,320 @@ pub struct TableData {
     pub table_name: String,
     pub table_fields: TableFields,
     pub count: usize,
+    // set when the rows are one page of a table rather than a query result
+    #[serde(default)]
+    pub page: Option<TablePage>,
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TablePage {
+    pub offset: usize,
+    pub page_size: usize,
+    // rows in the whole table
+    pub total: usize,
+}
+
+impl TablePage {
+    pub fn has_previous(&self) -> bool {
+        self.offset > 0
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.offset + self.page_size < self.total
+    }
+
+    pub fn previous_offset(&self) -> usize {
+        self.offset.saturating_sub(self.page_size)
+    }
+
+    pub fn next_offset(&self) -> usize {
+        self.offset + self.page_size
+    }
+
+    // offset of the page holding the last row
+    pub fn last_offset(&self) -> usize {
+        match self.page_size {
+            0 => 0,
+            size => self.total.saturating_sub(1) / size * size,
+        }
+    }
+
+    // 1-based, for display
+    pub fn number(&self) -> usize {
+        self.offset / self.page_size.max(1) + 1
+    }
+
+    pub fn page_count(&self) -> usize {
+        let page_size = self.page_size.max(1);
+        ((self.total + page_size - 1) / page_size).max(1)
+    }
 }
 
 pub type TableFields = Vec<TableField>;
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TableSort {
+    pub column: String,
+    pub descending: bool,
+}
+
+// A condition on one column when browsing a table, values are bound as parameters
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TableFilter {
+    pub column: String,
+    pub operator: FilterOperator,
+    #[serde(default)]
+    pub value: String,
+    // `value` holds the hex digits of a binary value, written into the query as a hex literal
+    #[serde(default)]
+    pub binary: bool,
+}
+
+impl TableFilter {
+    // Matches the column against a value read from a row, binary values byte for byte
+    pub fn equal(column: String, value: &Value) -> TableFilter {
+        let (value, binary) = match value {
+            Value::Bytes(bytes) => (
+                bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                true,
+            ),
+            value => (value.to_string(), false),
+        };
+
+        TableFilter {
+            column,
+            operator: FilterOperator::Equal,
+            value,
+            binary,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Display, EnumIter, EnumString)]
+pub enum FilterOperator {
+    #[strum(serialize = "=")]
+    Equal,
+    #[strum(serialize = "<>")]
+    NotEqual,
+    #[strum(serialize = "<")]
+    Less,
+    #[strum(serialize = "<=")]
+    LessOrEqual,
+    #[strum(serialize = ">")]
+    Greater,
+    #[strum(serialize = ">=")]
+    GreaterOrEqual,
+    #[strum(serialize = "LIKE")]
+    Like,
+    #[strum(serialize = "NOT LIKE")]
+    NotLike,
+    #[strum(serialize = "IS NULL")]
+    IsNull,
+    #[strum(serialize = "IS NOT NULL")]
+    IsNotNull,
+}
+
+impl Default for FilterOperator {
+    fn default() -> Self {
+        FilterOperator::Equal
+    }
+}
+
+impl FilterOperator {
+    pub fn takes_value(&self) -> bool {
+        !matches!(self, FilterOperator::IsNull | FilterOperator::IsNotNull)
+    }
+
+    // LIKE compares text, columns of other types are cast first where the backend needs it
+    pub fn is_pattern(&self) -> bool {
+        matches!(self, FilterOperator::Like | FilterOperator::NotLike)
+    }
+}
+
+impl fmt::Display for TableFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.operator.takes_value(), self.binary) {
+            (true, true) => write!(f, "{} {} 0x{}", self.column, self.operator, self.value),
+            (true, false) => write!(f, "{} {} '{}'", self.column, self.operator, self.value),
+            (false, _) => write!(f, "{} {}", self.column, self.operator),
+        }
+    }
+}
+
 #[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub struct TableField {
     pub name: String,
     pub sql_type: String,
-    pub values: Vec<String>,
-}
-
-pub enum TableTypes {
-    TableNULL,
-    TableBytes,
-    TableInt,
-    TableUInt,
-    TableFloat,
-    TableDouble,
-    TableDate,
-    TableTime,
+    // column metadata as far as the backend reports it for a result set
+    #[serde(default)]
+    pub meta: ColumnMeta,
+    pub values: Vec<Value>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ColumnMeta {
+    // raw column flags, e.g. NOT_NULL, AUTO_INCREMENT
+    pub flags: Vec<String>,
+    // None when the backend doesn't say
+    pub nullable: Option<bool>,
+    pub primary_key: bool,
+    pub unsigned: bool,
+    pub charset: Option<String>,
+}
+
+// A single cell. Decimals and temporal values keep the server's text so no precision is lost
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "type", content = "value")]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    Decimal(String),
+    Text(String),
+    Bytes(Vec<u8>),
+    Date(String),
+    Time(String),
+    DateTime(String),
+    Json(String),
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Null
+    }
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::Double(_) | Value::Decimal(_)
+        )
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::UInt(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Double(value) => write!(f, "{}", value),
+            Value::Bytes(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            Value::Decimal(text)
+            | Value::Text(text)
+            | Value::Date(text)
+            | Value::Time(text)
+            | Value::DateTime(text)
+            | Value::Json(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TableDescription {
+    pub db_name: String,
+    pub table_name: String,
+    pub columns: Vec<ColumnDescription>,
+    #[serde(default)]
+    pub indexes: Vec<IndexDescription>,
+    // keys of this table and keys of other tables pointing at it
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    pub referenced_by: Vec<ForeignKey>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ColumnDescription {
+    pub name: String,
+    pub sql_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+    pub default: Option<String>,
+    // anything else the server reports, e.g. auto_increment or how a generated column is computed
+    pub extra: String,
+    // PRI, UNI or MUL the way mysql reports them, empty for columns outside any key
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub collation: Option<String>,
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct IndexDescription {
+    pub name: String,
+    // in key order
+    pub columns: Vec<IndexColumn>,
+    pub unique: bool,
+    pub primary: bool,
+    // BTREE, HASH, FULLTEXT... as the server names it
+    pub index_type: String,
+    // estimated number of distinct keys, None when the server keeps no statistics
+    pub cardinality: Option<u64>,
+    // false for indexes the planner leaves alone (invisible on MySQL, invalid on PostgreSQL)
+    pub visible: bool,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct IndexColumn {
+    // the column's name, or the indexed expression of a functional key part
+    pub name: String,
+    #[serde(default)]
+    pub expression: bool,
+    // length of the indexed prefix (MySQL)
+    #[serde(default)]
+    pub sub_part: Option<u64>,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl fmt::Display for IndexColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expression {
+            true => write!(f, "({})", self.name)?,
+            false => write!(f, "{}", self.name)?,
+        }
+        if let Some(length) = self.sub_part {
+            write!(f, "({})", length)?;
+        }
+        if self.descending {
+            write!(f, " DESC")?;
+        }
+        Ok(())
+    }
+}
+
+// A table as the sidebar selects it, schema only for backends with them
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TableRef {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ForeignKey {
+    // the constraint's name, SQLite's unnamed keys are numbered
+    pub name: String,
+    pub table: TableRef,
+    pub columns: Vec<String>,
+    // paired with `columns` in order
+    pub referenced_table: TableRef,
+    pub referenced_columns: Vec<String>,
 }
@@ -0,0 +1,330 @@
+use crate::{
+    ApiError, ConnectionProfile, Database, ForeignKey, GraphFocus, GraphFormat, GraphLayout,
+    IndexColumn, ObjectDefinition, ObjectKind, ProfileTestResult, SchemaGraph, TableData,
+    TableDescription, TableFilter, TableSort, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use strum_macros::IntoStaticStr;
+
+// Bump whenever Request or Response change shape, the api refuses clients on another version
+pub const PROTOCOL_VERSION: u32 = 20;
+
+// Rows per page when a LoadTable request does not ask for a size
+pub const DEFAULT_PAGE_SIZE: usize = 24;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ApiRequest {
+    // generated by the client and echoed back in every response to this request
+    pub id: u64,
+    pub request: Request,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ApiResponse {
+    // id of the request being answered, None for events pushed by the server
+    pub id: Option<u64>,
+    pub response: Response,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, IntoStaticStr)]
+#[serde(tag = "action", content = "data")]
+pub enum Request {
+    Init(ClientHello),
+    ListProfiles,
+    CreateProfile(ConnectionProfile),
+    UpdateProfile(UpdateProfileRequest),
+    DeleteProfile(ProfileName),
+    TestProfile(ConnectionProfile),
+    Connect(ProfileName),
+    LoadTables,
+    LoadTable(LoadTableRequest),
+    RunQuery(RunQueryRequest),
+    DescribeTable(DescribeTableRequest),
+    // answered with the table described again once the index is changed
+    AlterIndex(AlterIndexRequest),
+    // the first rows of other tables whose foreign keys point at a row
+    LoadReferences(LoadReferencesRequest),
+    // the tables of a database (or schema) and the foreign keys between them
+    LoadSchemaGraph(SchemaGraphRequest),
+    ExportSchemaGraph(ExportGraphRequest),
+    // the CREATE statement of a view, routine, trigger or event
+    LoadDefinition(DefinitionRequest),
+    // stops the statements this session is running on the active profile
+    Cancel,
+    // off, the editor's statements run in a transaction left open until Commit or Rollback
+    SetAutocommit(bool),
+    Commit,
+    Rollback,
+}
+
+impl Request {
+    pub fn action(&self) -> &'static str {
+        self.into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "action", content = "data")]
+pub enum Response {
+    // pushed when a socket opens, keep this variant stable across protocol versions
+    Hello(ServerHello),
+    Init(ServerHello),
+    Profiles(Vec<ConnectionProfile>),
+    ProfileTested(ProfileTestResult),
+    Connected(ProfileName),
+    Databases(Vec<Database>),
+    TableLoaded(TableData),
+    // a RunQuery is answered with the rows of each statement in batches, a result once each
+    // statement is done, then a summary once the script finishes or a Cancel stopped it
+    QueryRows(QueryChunk),
+    StatementFinished(StatementResult),
+    QueryFinished(QuerySummary),
+    QueryCancelled(QuerySummary),
+    TableDescribed(TableDescription),
+    References(Vec<RowReferences>),
+    SchemaGraph(SchemaGraph),
+    GraphExported(GraphExport),
+    Definition(ObjectDefinition),
+    Cancelled(CancelResult),
+    Transaction(TransactionStatus),
+    Error(ApiError),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ClientHello {
+    pub protocol_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ServerHello {
+    pub protocol_version: u32,
+    pub server_version: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ProfileName {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct UpdateProfileRequest {
+    pub original_name: String,
+    pub profile: ConnectionProfile,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoadTableRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    #[serde(default)]
+    pub sort: Option<TableSort>,
+    // combined with AND
+    #[serde(default)]
+    pub filters: Vec<TableFilter>,
+}
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RunQueryRequest {
+    // the statements run with this database (or schema) as their default
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    // one or more statements, split by the api
+    pub query: String,
+    // run the statements after one that failed instead of stopping there
+    #[serde(default)]
+    pub continue_on_error: bool,
+    // values for the placeholders in `query`, bound by the server and never spliced into it
+    #[serde(default)]
+    pub params: QueryParams,
+}
+
+// None binds NULL. `positional` holds the `?` values in order across the whole script
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct QueryParams {
+    #[serde(default)]
+    pub named: BTreeMap<String, Option<String>>,
+    #[serde(default)]
+    pub positional: Vec<Option<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DescribeTableRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LoadReferencesRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    // the row's values by column, keys over a missing or NULL column are skipped
+    pub values: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RowReferences {
+    pub foreign_key: ForeignKey,
+    // a page of the referencing rows, its total counting all of them
+    pub data: TableData,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SchemaGraphRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ExportGraphRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub format: GraphFormat,
+    // None exports every table
+    #[serde(default)]
+    pub focus: Option<GraphFocus>,
+    // the diagram's box positions, for the SVG drawing
+    #[serde(default)]
+    pub layout: GraphLayout,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GraphExport {
+    pub format: GraphFormat,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DefinitionRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub name: String,
+    pub kind: ObjectKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct AlterIndexRequest {
+    pub db: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub change: IndexChange,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind", content = "index")]
+pub enum IndexChange {
+    Create(IndexDefinition),
+    // the primary key is dropped as a constraint rather than an index
+    Drop {
+        name: String,
+        #[serde(default)]
+        primary: bool,
+    },
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct IndexDefinition {
+    // empty lets the server name it, SQLite has none so one is made from the table and columns
+    pub name: String,
+    pub columns: Vec<IndexColumn>,
+    pub unique: bool,
+    // BTREE or HASH, FULLTEXT and SPATIAL on MySQL, any access method on PostgreSQL
+    #[serde(default)]
+    pub index_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CancelResult {
+    // number of running statements that were stopped
+    pub cancelled: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct QueryChunk {
+    // index of the statement in the script
+    pub statement: usize,
+    // rows of the statement sent in earlier batches, 0 for the first one
+    pub offset: usize,
+    // every batch repeats the columns, with only this batch's values
+    pub data: TableData,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct StatementResult {
+    pub statement: usize,
+    pub query: String,
+    pub rows: usize,
+    // the row cap was reached and the rest of the result was dropped
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+    // the statement failed, was cancelled or went over a limit, keeping the rows sent before
+    #[serde(default)]
+    pub error: Option<ApiError>,
+    // reported by the server for statements without a result set
+    #[serde(default)]
+    pub affected_rows: Option<u64>,
+    #[serde(default)]
+    pub last_insert_id: Option<u64>,
+    // e.g. "Rows matched: 2  Changed: 1  Warnings: 0"
+    #[serde(default)]
+    pub info: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<StatementWarning>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct StatementWarning {
+    // Note, Warning or Error
+    pub level: String,
+    pub code: u32,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct QuerySummary {
+    // statements run, those after a failure are skipped unless continuing on errors
+    pub statements: usize,
+    pub failed: usize,
+    pub elapsed_ms: u64,
+    pub transaction: TransactionStatus,
+    // as the script left it, a `USE` changes the database later statements run in
+    pub db: String,
+}
+
+// The editor's queries run on a connection kept for the session,
+// so a transaction stays open across queries until it ends
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TransactionStatus {
+    pub autocommit: bool,
+    // uncommitted changes are rolled back when the session closes
+    pub open: bool,
+}
+
+impl Default for TransactionStatus {
+    fn default() -> Self {
+        TransactionStatus {
+            autocommit: true,
+            open: false,
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use crate::DriverKind;
+
+static DEFAULT_DELIMITER: &str = ";";
+
+// A placeholder in a statement, bound to a value when the statement runs
+#[derive(Clone, PartialEq, Debug)]
+pub struct Placeholder {
+    // `:name`, and sqlite's `$name`, None for a positional one
+    pub name: Option<String>,
+    // 1-based position of the value for sqlite's `?NNN` and postgres' `$N`, None for a plain `?`,
+    // which takes the value after the last one used
+    pub number: Option<usize>,
+    // byte range of the placeholder in the statement
+    pub start: usize,
+    pub end: usize,
+}
+
+// Splits a script into its statements on `;`, or on the delimiter set by a mysql `DELIMITER` line.
+// Delimiters inside strings, quoted identifiers, comments and dollar quoted bodies are skipped,
+// following the quoting rules of the driver's dialect
+pub fn split_statements(driver: DriverKind, script: &str) -> Vec<String> {
+    let mut statements = vec![];
+    let mut delimiter = String::from(DEFAULT_DELIMITER);
+    let mut start = 0;
+    let mut position = 0;
+    // anything but whitespace and comments since `start`
+    let mut has_code = false;
+
+    while position < script.len() {
+        let rest = &script[position..];
+
+        if driver == DriverKind::MySql && !has_code && starts_with_keyword(rest, "DELIMITER") {
+            // a mysql client command, it never reaches the server
+            let line = line_end(rest);
+            if let Some(new_delimiter) = rest[..line].split_whitespace().nth(1) {
+                delimiter = new_delimiter.to_string();
+            }
+            position += line;
+            start = position;
+            continue;
+        }
+
+        if rest.starts_with(delimiter.as_str()) {
+            if has_code {
+                statements.push(script[start..position].trim().to_string());
+            }
+            position += delimiter.len();
+            start = position;
+            has_code = false;
+            continue;
+        }
+
+        let token = next_token(driver, rest);
+        has_code = has_code || token.is_code();
+        position += token.len();
+    }
+
+    if has_code {
+        statements.push(script[start..].trim().to_string());
+    }
+    statements
+}
+
+// Placeholders outside strings and comments, in order: `:name` everywhere, `?` outside
+// postgres, sqlite's `?NNN` and `$name` and postgres' `$N`. Postgres uses `?` as a json operator,
+// `::` casts are left alone
+pub fn find_placeholders(driver: DriverKind, statement: &str) -> Vec<Placeholder> {
+    let mut placeholders = vec![];
+    let mut position = 0;
+
+    while position < statement.len() {
+        let rest = &statement[position..];
+        let token = next_token(driver, rest);
+        let before = &statement[..position];
+
+        let found = match token {
+            Token::Code('?') if driver == DriverKind::Sqlite => numbered(rest, position),
+            Token::Code('?') if driver != DriverKind::Postgres => Some(Placeholder {
+                name: None,
+                number: None,
+                start: position,
+                end: position + 1,
+            }),
+            // `$` is part of an identifier when it follows one
+            Token::Code('$') if driver == DriverKind::Postgres && !before.ends_with(is_word) => {
+                numbered(rest, position).filter(|found| found.number.is_some())
+            }
+            Token::Code('$') if driver == DriverKind::Sqlite => named(rest, position),
+            Token::Code(':') if !before.ends_with(':') => named(rest, position),
+            _ => None,
+        };
+
+        match found {
+            Some(found) => {
+                position = found.end;
+                placeholders.push(found);
+            }
+            None => position += token.len(),
+        }
+    }
+
+    placeholders
+}
+
+// A one character sigil and the digits after it, if any
+fn numbered(rest: &str, position: usize) -> Option<Placeholder> {
+    let digits = rest[1..]
+        .find(|next: char| !next.is_ascii_digit())
+        .unwrap_or(rest.len() - 1);
+
+    Some(Placeholder {
+        name: None,
+        number: rest[1..1 + digits].parse().ok(),
+        start: position,
+        end: position + 1 + digits,
+    })
+}
+
+// A one character sigil and the name after it, names start with a letter or underscore
+fn named(rest: &str, position: usize) -> Option<Placeholder> {
+    let name_len = rest[1..]
+        .find(|next| !is_word(next))
+        .unwrap_or(rest.len() - 1);
+    let name = &rest[1..1 + name_len];
+
+    match name.starts_with(|next: char| next.is_alphabetic() || next == '_') {
+        true => Some(Placeholder {
+            name: Some(name.to_string()),
+            number: None,
+            start: position,
+            end: position + 1 + name_len,
+        }),
+        false => None,
+    }
+}
+
+fn is_word(next: char) -> bool {
+    next.is_alphanumeric() || next == '_'
+}
+
+// Statements starting or ending the transaction of the connection they run on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransactionControl {
+    Begin,
+    End,
+}
+
+// `ROLLBACK TO` a savepoint keeps the transaction going, and `COMMIT PREPARED` ends another one.
+// Mysql commits before most schema changes and when autocommit is turned back on
+pub fn transaction_control(driver: DriverKind, statement: &str) -> Option<TransactionControl> {
+    let code = &statement[code_start(driver, statement)..];
+    let mut words = code
+        .split_whitespace()
+        .map(|word| word.to_ascii_uppercase());
+
+    match words.next().as_deref() {
+        Some("BEGIN") => Some(TransactionControl::Begin),
+        Some("START") => match words.next().as_deref() {
+            Some("TRANSACTION") => Some(TransactionControl::Begin),
+            _ => None,
+        },
+        Some("COMMIT") | Some("END") | Some("ROLLBACK") => {
+            match words
+                .find(|word| word != "WORK" && word != "TRANSACTION")
+                .as_deref()
+            {
+                Some("TO") | Some("PREPARED") => None,
+                _ => Some(TransactionControl::End),
+            }
+        }
+        Some("ABORT") if driver == DriverKind::Postgres => Some(TransactionControl::End),
+        Some(_) if driver == DriverKind::MySql => mysql_implicit_control(code),
+        _ => None,
+    }
+}
+
+// Statements mysql commits the open transaction before, temporary tables aside, and
+// `SET autocommit`, which begins one when turned off
+fn mysql_implicit_control(code: &str) -> Option<TransactionControl> {
+    let words: Vec<String> = code
+        .split_whitespace()
+        .take(2)
+        .map(|word| word.to_ascii_uppercase())
+        .collect();
+    let implicit_commit = [
+        "CREATE", "ALTER", "DROP", "RENAME", "TRUNCATE", "GRANT", "REVOKE", "LOCK", "ANALYZE",
+        "OPTIMIZE", "REPAIR", "FLUSH",
+    ];
+    if implicit_commit.contains(&words[0].as_str()) {
+        return match words.get(1).map(String::as_str) {
+            Some("TEMPORARY") => None,
+            _ => Some(TransactionControl::End),
+        };
+    }
+    if words[0] != "SET" {
+        return None;
+    }
+
+    // `SET [SESSION] autocommit = 0` and the `@@` forms, compared without their spaces
+    let assignment: String = code[3..]
+        .chars()
+        .filter(|next| !next.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    let value = ["", "SESSION", "LOCAL", "@@", "@@SESSION.", "@@LOCAL."]
+        .iter()
+        .find_map(|scope| {
+            assignment
+                .strip_prefix(scope)?
+                .strip_prefix("AUTOCOMMIT")?
+                .trim_start_matches(':')
+                .strip_prefix('=')
+        })?;
+    match value.split(|next| next == ',' || next == ';').next()? {
+        "0" | "OFF" | "FALSE" => Some(TransactionControl::Begin),
+        "1" | "ON" | "TRUE" => Some(TransactionControl::End),
+        _ => None,
+    }
+}
+
+// The database a mysql `USE db` statement switches to, unquoted
+pub fn used_database(driver: DriverKind, statement: &str) -> Option<String> {
+    if driver != DriverKind::MySql {
+        return None;
+    }
+
+    let code = &statement[code_start(driver, statement)..];
+    if !starts_with_keyword(code, "USE") {
+        return None;
+    }
+
+    let name = code[3..].trim_start();
+    let name = match name.chars().next()? {
+        quote @ '`' | quote @ '"' => {
+            // a doubled quote stands for itself
+            let mut unquoted = String::new();
+            let mut chars = name[1..].chars().peekable();
+            while let Some(next) = chars.next() {
+                if next == quote && chars.next_if_eq(&quote).is_none() {
+                    break;
+                }
+                unquoted.push(next);
+            }
+            unquoted
+        }
+        _ => name.split_whitespace().next()?.to_string(),
+    };
+
+    Some(name).filter(|name| !name.is_empty())
+}
+
+// The statement's first word, uppercased, past leading comments
+pub fn first_keyword(driver: DriverKind, statement: &str) -> Option<String> {
+    let code = &statement[code_start(driver, statement)..];
+    let word_len = code
+        .find(|next: char| !next.is_alphabetic())
+        .unwrap_or(code.len());
+
+    Some(code[..word_len].to_ascii_uppercase()).filter(|word| !word.is_empty())
+}
+
+// Where the statement's code starts, past leading whitespace and comments
+fn code_start(driver: DriverKind, statement: &str) -> usize {
+    let mut position = 0;
+    while position < statement.len() {
+        let token = next_token(driver, &statement[position..]);
+        if token.is_code() {
+            break;
+        }
+        position += token.len();
+    }
+    position
+}
+
+enum Token {
+    Code(char),
+    // strings, quoted identifiers and dollar quoted bodies, by length
+    Quoted(usize),
+    Comment(usize),
+}
+
+impl Token {
+    fn len(&self) -> usize {
+        match self {
+            Token::Code(next) => next.len_utf8(),
+            Token::Quoted(len) | Token::Comment(len) => *len,
+        }
+    }
+
+    fn is_code(&self) -> bool {
+        match self {
+            Token::Code(next) => !next.is_whitespace(),
+            Token::Quoted(_) => true,
+            Token::Comment(_) => false,
+        }
+    }
+}
+
+// `text` is not empty
+fn next_token(driver: DriverKind, text: &str) -> Token {
+    let next = text.chars().next().unwrap_or_default();
+
+    match next {
+        '-' if is_line_comment(driver, text) => Token::Comment(line_end(text)),
+        '#' if driver == DriverKind::MySql => Token::Comment(line_end(text)),
+        // mysql runs the body of `/*! ... */` and reads `/*+ ... */` as optimizer hints, only
+        // the opening is skipped and the body is scanned as code, the closing `*/` with it
+        '/' if driver == DriverKind::MySql && text.starts_with("/*!") => {
+            Token::Comment(executable_comment_start(text))
+        }
+        '/' if driver == DriverKind::MySql && text.starts_with("/*+") => Token::Comment(3),
+        '/' if text.starts_with("/*") => {
+            Token::Comment(text[2..].find("*/").map_or(text.len(), |end| end + 4))
+        }
+        '\'' | '"' | '`' => Token::Quoted(quoted_end(driver, text, next)),
+        '$' if driver == DriverKind::Postgres => match dollar_quoted_end(text) {
+            Some(end) => Token::Quoted(end),
+            None => Token::Code(next),
+        },
+        _ => Token::Code(next),
+    }
+}
+
+fn starts_with_keyword(text: &str, keyword: &str) -> bool {
+    matches!(text.get(..keyword.len()), Some(word) if word.eq_ignore_ascii_case(keyword))
+        && ends_word(&text[keyword.len()..])
+}
+
+// mysql needs whitespace after the `--`, it reads `1--1` as arithmetic
+fn is_line_comment(driver: DriverKind, text: &str) -> bool {
+    text.starts_with("--") && (driver != DriverKind::MySql || ends_word(&text[2..]))
+}
+
+// `/*!` and the server version that may follow it, digits being one byte each
+fn executable_comment_start(text: &str) -> usize {
+    3 + text[3..].chars().take_while(char::is_ascii_digit).count()
+}
+
+fn ends_word(rest: &str) -> bool {
+    rest.is_empty() || rest.starts_with(char::is_whitespace)
+}
+
+// length up to and including the newline
+fn line_end(text: &str) -> usize {
+    text.find('\n').map_or(text.len(), |end| end + 1)
+}
+
+// Length of the quoted text at the start of `text`, an unterminated quote runs to the end.
+// Doubled quotes need no special case, they close and reopen the quote
+fn quoted_end(driver: DriverKind, text: &str, quote: char) -> usize {
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((index, next)) = chars.next() {
+        match next {
+            // only mysql escapes with backslashes inside quotes
+            '\\' if driver == DriverKind::MySql && quote != '`' => {
+                chars.next();
+            }
+            next if next == quote => return index + next.len_utf8(),
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+// `$tag$ ... $tag$` bodies of postgres functions, None for `$1` style parameters
+fn dollar_quoted_end(text: &str) -> Option<usize> {
+    let tag_end = text[1..].find('$')? + 2;
+    let tag = &text[..tag_end];
+    let valid_tag = tag[1..tag_end - 1]
+        .chars()
+        .enumerate()
+        .all(|(index, next)| {
+            next == '_' || next.is_alphabetic() || (index > 0 && next.is_numeric())
+        });
+
+    match valid_tag {
+        true => Some(
+            text[tag_end..]
+                .find(tag)
+                .map_or(text.len(), |end| tag_end + end + tag.len()),
+        ),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(driver: DriverKind, script: &str) -> Vec<String> {
+        split_statements(driver, script)
+    }
+
+    fn placeholder_names(driver: DriverKind, statement: &str) -> Vec<Option<String>> {
+        find_placeholders(driver, statement)
+            .into_iter()
+            .map(|placeholder| placeholder.name)
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_semicolons() {
+        for driver in [DriverKind::MySql, DriverKind::Postgres, DriverKind::Sqlite] {
+            assert_eq!(
+                split(driver, "SELECT 1;\n SELECT 2 ;;\n\nSELECT 3"),
+                vec!["SELECT 1", "SELECT 2", "SELECT 3"]
+            );
+            // doubled quotes close and reopen the quote
+            assert_eq!(
+                split(driver, "SELECT 'a;''b', \"c;\"\"d\"; SELECT 2"),
+                vec!["SELECT 'a;''b', \"c;\"\"d\"", "SELECT 2"]
+            );
+            assert_eq!(
+                split(
+                    driver,
+                    "-- only a comment\n; /* another */; SELECT 1 /* ; */"
+                ),
+                vec!["SELECT 1 /* ; */"]
+            );
+            assert!(split(driver, "  ; \n").is_empty());
+        }
+    }
+
+    #[test]
+    fn splits_mysql_quotes_and_comments() {
+        assert_eq!(
+            split(DriverKind::MySql, "SELECT `a;b`, 'it\\'s; fine'; SELECT 2"),
+            vec!["SELECT `a;b`, 'it\\'s; fine'", "SELECT 2"]
+        );
+        assert_eq!(
+            split(DriverKind::MySql, "SELECT 1 # ; comment\n; SELECT 2"),
+            vec!["SELECT 1 # ; comment", "SELECT 2"]
+        );
+        // `--` without whitespace after it is a minus and a negative number
+        assert_eq!(
+            split(DriverKind::MySql, "SELECT 1--1; SELECT 2 -- ; comment"),
+            vec!["SELECT 1--1", "SELECT 2 -- ; comment"]
+        );
+    }
+
+    #[test]
+    fn splits_on_mysql_delimiters() {
+        let script = "DELIMITER //
+            CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END//
+            DELIMITER ;
+            SELECT 3;";
+
+        assert_eq!(
+            split(DriverKind::MySql, script),
+            vec![
+                "CREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END",
+                "SELECT 3"
+            ]
+        );
+        // only the mysql client has the command
+        assert_eq!(
+            split(DriverKind::Postgres, "SELECT 1;\ndelimiter //\nSELECT 2//;"),
+            vec!["SELECT 1", "delimiter //\nSELECT 2//"]
+        );
+    }
+
+    #[test]
+    fn splits_postgres_quotes_and_comments() {
+        // backslashes don't escape quotes outside mysql
+        assert_eq!(
+            split(DriverKind::Postgres, "SELECT 'a\\'; SELECT 2"),
+            vec!["SELECT 'a\\'", "SELECT 2"]
+        );
+        assert_eq!(
+            split(DriverKind::Sqlite, "SELECT 'a\\'; SELECT 2"),
+            vec!["SELECT 'a\\'", "SELECT 2"]
+        );
+        // any `--` starts a comment outside mysql
+        assert_eq!(
+            split(DriverKind::Postgres, "SELECT 1--1; SELECT 2\n;SELECT 3"),
+            vec!["SELECT 1--1; SELECT 2", "SELECT 3"]
+        );
+        assert_eq!(
+            split(DriverKind::Sqlite, "SELECT 1 --; SELECT 2\n"),
+            vec!["SELECT 1 --; SELECT 2"]
+        );
+        // `#` is an operator
+        assert_eq!(
+            split(DriverKind::Postgres, "SELECT 1 # 2; SELECT 3"),
+            vec!["SELECT 1 # 2", "SELECT 3"]
+        );
+    }
+
+    #[test]
+    fn splits_around_dollar_quotes() {
+        let script = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql;
+            DO $$ BEGIN PERFORM 1; END $$;
+            SELECT $1, $2";
+
+        assert_eq!(
+            split(DriverKind::Postgres, script),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql",
+                "DO $$ BEGIN PERFORM 1; END $$",
+                "SELECT $1, $2",
+            ]
+        );
+        // only postgres has dollar quotes
+        assert_eq!(
+            split(DriverKind::Sqlite, "SELECT $$a; SELECT $$"),
+            vec!["SELECT $$a", "SELECT $$"]
+        );
+    }
+
+    #[test]
+    fn scans_mysql_executable_comments_as_code() {
+        assert_eq!(
+            split(DriverKind::MySql, "/*!40101 SET NAMES utf8 */; SELECT 1"),
+            vec!["/*!40101 SET NAMES utf8 */", "SELECT 1"]
+        );
+        assert_eq!(
+            first_keyword(DriverKind::MySql, "/*!40101 SET NAMES utf8 */").as_deref(),
+            Some("SET")
+        );
+        assert_eq!(
+            placeholder_names(
+                DriverKind::MySql,
+                "SELECT /*+ MAX_EXECUTION_TIME(?) */ a /* ? */ FROM t WHERE b = ?"
+            ),
+            vec![None, None]
+        );
+        // a plain comment elsewhere
+        assert_eq!(
+            split(DriverKind::Postgres, "/*!40101 SET NAMES utf8 */; SELECT 1"),
+            vec!["SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn finds_placeholders() {
+        let statement = "SELECT * FROM t WHERE a = ? AND b = :name AND c = '?:x' -- ? :y\n";
+        let found = find_placeholders(DriverKind::MySql, statement);
+
+        assert_eq!(
+            found,
+            vec![
+                Placeholder {
+                    name: None,
+                    number: None,
+                    start: 26,
+                    end: 27,
+                },
+                Placeholder {
+                    name: Some(String::from("name")),
+                    number: None,
+                    start: 36,
+                    end: 41,
+                },
+            ]
+        );
+        assert_eq!(&statement[found[1].start..found[1].end], ":name");
+    }
+
+    #[test]
+    fn skips_casts_assignments_and_operators() {
+        // `?` tests for a json key and `::` casts
+        assert_eq!(
+            placeholder_names(
+                DriverKind::Postgres,
+                "SELECT data ? 'key', a::int, :id::text FROM t"
+            ),
+            vec![Some(String::from("id"))]
+        );
+        assert_eq!(
+            placeholder_names(DriverKind::MySql, "SET @a := 1, @b = :value"),
+            vec![Some(String::from("value"))]
+        );
+        assert_eq!(
+            placeholder_names(DriverKind::Sqlite, "SELECT ?, :a1, :1, `:b`"),
+            vec![None, Some(String::from("a1"))]
+        );
+    }
+
+    #[test]
+    fn finds_numbered_placeholders() {
+        let numbers = |driver, statement| -> Vec<(Option<String>, Option<usize>, &str)> {
+            find_placeholders(driver, statement)
+                .into_iter()
+                .map(|found| (found.name, found.number, &statement[found.start..found.end]))
+                .collect()
+        };
+
+        assert_eq!(
+            numbers(DriverKind::Sqlite, "SELECT ?12, ?, $id, :id, '?3'"),
+            vec![
+                (None, Some(12), "?12"),
+                (None, None, "?"),
+                (Some(String::from("id")), None, "$id"),
+                (Some(String::from("id")), None, ":id"),
+            ]
+        );
+        assert_eq!(
+            numbers(
+                DriverKind::Postgres,
+                "SELECT $2, $1::int, a$1, $$x$$, $tag$"
+            ),
+            vec![(None, Some(2), "$2"), (None, Some(1), "$1")]
+        );
+        // mysql has neither
+        assert_eq!(
+            numbers(DriverKind::MySql, "SELECT ?1, $a"),
+            vec![(None, None, "?")]
+        );
+    }
+
+    #[test]
+    fn finds_transaction_control() {
+        let control = |statement| transaction_control(DriverKind::Postgres, statement);
+
+        assert_eq!(control("BEGIN"), Some(TransactionControl::Begin));
+        assert_eq!(
+            control("-- note\nstart transaction read only"),
+            Some(TransactionControl::Begin)
+        );
+        assert_eq!(control("/* go */ COMMIT"), Some(TransactionControl::End));
+        assert_eq!(control("rollback work"), Some(TransactionControl::End));
+        assert_eq!(control("END"), Some(TransactionControl::End));
+        assert_eq!(control("ROLLBACK TO SAVEPOINT a"), None);
+        assert_eq!(control("ROLLBACK TRANSACTION TO a"), None);
+        assert_eq!(control("COMMIT PREPARED 'x'"), None);
+        assert_eq!(control("START SLAVE"), None);
+        assert_eq!(control("SELECT 'BEGIN'"), None);
+    }
+
+    #[test]
+    fn finds_mysql_implicit_commits() {
+        let control = |statement| transaction_control(DriverKind::MySql, statement);
+
+        assert_eq!(
+            control("CREATE TABLE t (a int)"),
+            Some(TransactionControl::End)
+        );
+        assert_eq!(control("drop index i on t"), Some(TransactionControl::End));
+        assert_eq!(control("TRUNCATE t"), Some(TransactionControl::End));
+        assert_eq!(control("CREATE TEMPORARY TABLE t (a int)"), None);
+        assert_eq!(control("INSERT INTO t VALUES (1)"), None);
+        assert_eq!(control("SET autocommit=0"), Some(TransactionControl::Begin));
+        assert_eq!(
+            control("set @@session.autocommit := ON"),
+            Some(TransactionControl::End)
+        );
+        assert_eq!(
+            control("SET SESSION autocommit = 1"),
+            Some(TransactionControl::End)
+        );
+        assert_eq!(control("SET autocommit_x = 0"), None);
+        assert_eq!(control("SET NAMES utf8"), None);
+        // schema changes are transactional elsewhere
+        assert_eq!(
+            transaction_control(DriverKind::Postgres, "CREATE TABLE t (a int)"),
+            None
+        );
+        assert_eq!(
+            transaction_control(DriverKind::Postgres, "ABORT"),
+            Some(TransactionControl::End)
+        );
+    }
+
+    #[test]
+    fn finds_used_database() {
+        let used = |statement| used_database(DriverKind::MySql, statement);
+
+        assert_eq!(used("USE shop").as_deref(), Some("shop"));
+        assert_eq!(used("use `my``db`").as_deref(), Some("my`db"));
+        assert_eq!(used("# switch\nUSE \"x y\"").as_deref(), Some("x y"));
+        assert_eq!(used("USER()"), None);
+        assert_eq!(used("USE ``"), None);
+        assert_eq!(used_database(DriverKind::Postgres, "USE shop"), None);
+    }
+}
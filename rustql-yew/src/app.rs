@@ -1,10 +1,22 @@
-use self::{components::page_view::PageViewMsg, store::AppStore};
+use self::{
+    components::page_view::PageViewMsg,
+    store::{AppStore, QueryProgress},
+};
 use components::page_view::PageView;
-use components::{db_collapse::DBCollapse, navbar::Navbar};
-use helpers::socket::Socket;
-use helpers::socket::SocketMessage;
-use rustql_types::{ApiAction, ApiRequest, Database, TableData};
-use serde_json;
+use components::{
+    db_collapse::{DBCollapse, ObjectSelection, SchemaSelection, TableSelection},
+    navbar::Navbar,
+};
+use helpers::{
+    components::view_api_error,
+    socket::{api_address, set_api_address, Socket, SocketMessage},
+};
+use rustql_types::{
+    ApiError, ApiRequest, ClientHello, ConnectionProfile, Database, ErrorCategory, GraphExport,
+    ObjectDefinition, ProfileName, ProfileTestResult, QueryChunk, QuerySummary, Request, Response, RowReferences,
+    SchemaGraph, ServerHello,
+    StatementResult, TableData, TableDescription, TableRef, TransactionStatus, PROTOCOL_VERSION,
+};
 use std::{cell::RefCell, rc::Rc};
 use structs::page_view_link::CustomLink;
 use yew::prelude::*;
@@ -22,38 +34,73 @@ pub struct App {
     state: State,
     store: Rc<RefCell<AppStore>>,
     page_link: CustomLink<PageView>,
+    next_request_id: u64,
+    // latest request that will replace the table view, older answers are stale
+    table_request: Option<u64>,
+    // websocket address shown in the reconnect dialog, saved on reconnect
+    api_address: String,
 }
 
 pub enum State {
     Loaded,
     Loading,
-    Errored { error: String },
+    Errored { error: ApiError },
     Closed { message: String },
 }
 
 pub enum Msg {
-    LoadDatabases(Result<Vec<Database>, serde_json::Error>),
-    UpdateTableData(TableData),
+    LoadProfiles(Vec<ConnectionProfile>),
+    ProfileTested(ProfileTestResult),
+    Connected(String),
+    ShowProfiles,
+    LoadDatabases(Vec<Database>),
+    UpdateTableData(Option<u64>, TableData),
+    QueryRows(Option<u64>, QueryChunk),
+    StatementFinished(Option<u64>, StatementResult),
+    QueryFinished(Option<u64>, QuerySummary),
+    QueryCancelled(Option<u64>, QuerySummary),
+    Transaction(TransactionStatus),
+    TableDescribed(TableDescription),
+    References(Vec<RowReferences>),
+    SchemaGraph(SchemaGraph),
+    GraphExported(GraphExport),
+    Definition(ObjectDefinition),
     Ignore,
+    SocketHello(ServerHello),
     SocketInit,
     ResetSocket,
+    UpdateApiAddress(String),
     SocketClosed,
-    SocketSend(ApiRequest),
-    SocketError(String),
-    TableSelected((usize, usize)),
+    SocketSend(Request),
+    SocketError(Option<u64>, ApiError),
+    DismissError,
+    TableSelected(TableSelection),
+    DiagramSelected(SchemaSelection),
+    ObjectSelected(ObjectSelection),
 }
 
 impl Socket<App> for App {
-    fn on_message(action: ApiAction, data: String) -> Msg {
-        match action {
-            ApiAction::LoadTables => Msg::LoadDatabases(serde_json::from_str(&data)),
-            ApiAction::Error => Msg::SocketError(data),
-            ApiAction::Init => Msg::SocketInit,
-            ApiAction::LoadTable => match serde_json::from_str(&data) {
-                Ok(value) => Msg::UpdateTableData(value),
-                Err(err) => Msg::SocketError(err.to_string()),
-            },
-            _ => Msg::Ignore,
+    fn on_message(id: Option<u64>, response: Response) -> Msg {
+        match response {
+            Response::Hello(hello) => Msg::SocketHello(hello),
+            Response::Init(_) => Msg::SocketInit,
+            Response::Profiles(profiles) => Msg::LoadProfiles(profiles),
+            Response::ProfileTested(result) => Msg::ProfileTested(result),
+            Response::Connected(ProfileName { name }) => Msg::Connected(name),
+            Response::Databases(databases) => Msg::LoadDatabases(databases),
+            Response::TableLoaded(data) => Msg::UpdateTableData(id, data),
+            Response::QueryRows(chunk) => Msg::QueryRows(id, chunk),
+            Response::StatementFinished(result) => Msg::StatementFinished(id, result),
+            Response::QueryFinished(summary) => Msg::QueryFinished(id, summary),
+            Response::QueryCancelled(summary) => Msg::QueryCancelled(id, summary),
+            Response::Transaction(transaction) => Msg::Transaction(transaction),
+            Response::TableDescribed(description) => Msg::TableDescribed(description),
+            Response::References(references) => Msg::References(references),
+            Response::SchemaGraph(graph) => Msg::SchemaGraph(graph),
+            Response::GraphExported(export) => Msg::GraphExported(export),
+            Response::Definition(definition) => Msg::Definition(definition),
+            Response::Cancelled(_) => Msg::Ignore,
+            Response::Error(error) => Msg::SocketError(id, error),
         }
     }
 
@@ -79,44 +126,157 @@ impl Component for App {
             socket: Self::create_socket(link.clone()),
             store,
             page_link: CustomLink::new(),
+            next_request_id: 1,
+            table_request: None,
+            api_address: api_address(),
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
+            Msg::SocketHello(hello) => {
+                if hello.protocol_version == PROTOCOL_VERSION {
+                    self.send(Request::Init(ClientHello {
+                        protocol_version: PROTOCOL_VERSION,
+                    }));
+                    false
+                } else {
+                    self.state = State::Errored {
+                        error: ApiError::new(
+                            ErrorCategory::Protocol,
+                            format!(
+                                "Incompatible api {}: it speaks protocol version {} but this client speaks version {}",
+                                hello.server_version, hello.protocol_version, PROTOCOL_VERSION
+                            ),
+                        ),
+                    };
+                    true
+                }
+            }
 			Msg::SocketInit => {
-                Self::s_send(&mut self.socket, ApiRequest::create(ApiAction::LoadTables));
+                self.send(Request::ListProfiles);
+
+                // a new socket is a new server session, reconnect the last profile
+                let active_profile = self.store.borrow().active_profile.clone();
+                if let Some(profile) = active_profile {
+                    self.send(Request::Connect(ProfileName { name: profile }));
+                }
                 self.state = State::Loaded;
                 true
             }
+            Msg::LoadProfiles(profiles) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::LoadProfiles)");
+
+                    // the server drops the session profile when it is deleted
+                    let active_deleted = store.active_profile.as_ref().map_or(false, |active| {
+                        !profiles.iter().any(|profile| &profile.name == active)
+                    });
+                    if active_deleted {
+                        store.active_profile = None;
+                    }
+                    store.profiles = profiles;
+                }
+                self.page_link.send_message(PageViewMsg::Update);
+                true
+            }
+            Msg::ProfileTested(result) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::ProfileTested)")
+                    .profile_test = Some(result);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::Connected(profile) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::Connected)");
+
+                    if store.active_profile.as_ref() != Some(&profile) {
+                        store.databases = Vec::new();
+                        store.selected_db = None;
+                        store.selected_schema = None;
+                        store.selected_table = None;
+                        store.editor_target = None;
+                        store.table_data = None;
+                        store.show_diagram = false;
+                    }
+                    store.active_profile = Some(profile);
+                    store.show_profiles = false;
+                    // the api rolled back whatever was open on the previous connection
+                    let autocommit = store.transaction.autocommit;
+                    store.set_transaction(TransactionStatus { autocommit, open: false });
+                }
+                self.send(Request::LoadTables);
+                self.page_link.send_message(PageViewMsg::Update);
+                true
+            }
+            Msg::ShowProfiles => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::ShowProfiles)")
+                    .show_profiles = true;
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
             Msg::SocketSend(request) => {
-                Self::s_send(&mut self.socket, request);
+                self.send(request);
                 false
             }
             Msg::SocketClosed => {
+                // the session ended, a new one starts in autocommit
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::SocketClosed)")
+                    .set_transaction(TransactionStatus::default());
+                self.page_link.send_message(PageViewMsg::Update);
                 self.state = State::Closed {
                     message: String::from("Socket Closed"),
                 };
                 true
             }
-            Msg::SocketError(error) => {
+            Msg::SocketError(id, error) => {
+                // a failed query is no longer running
+                if id.is_some() && id == self.table_request {
+                    self.store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::SocketError)")
+                        .query_progress = None;
+                    self.page_link.send_message(PageViewMsg::Update);
+                }
                 self.state = State::Errored { error };
                 true
             }
+            Msg::DismissError => {
+                self.state = State::Loaded;
+                true
+            }
+            Msg::UpdateApiAddress(address) => {
+                self.api_address = address;
+                true
+            }
             Msg::ResetSocket => {
+                set_api_address(&self.api_address);
+                self.api_address = api_address();
                 self.socket = {
                     let new_socket = Self::create_socket(self.link.clone());
                     self.state = match new_socket {
                         Some(_) => State::Loading,
-                        None => State::Errored {
-                            error: String::from("Socket Failed to reset"),
+                        None => State::Closed {
+                            message: String::from("Socket Failed to reset"),
                         },
                     };
                     new_socket
                 };
                 true
             }
-            Msg::LoadDatabases(Ok(dbs)) => {
+            Msg::LoadDatabases(dbs) => {
                 self.store
                     .try_borrow_mut()
                     .expect("Can't Borrow Store (Msg::LoadDatabases)")
@@ -124,30 +284,60 @@ impl Component for App {
 
                 true
             }
-            Msg::TableSelected((table_id, db_id)) => {
-                self.store
+            Msg::TableSelected(selection) => {
+                let db = self.get_db(selection.db_id);
+                let schema = selection
+                    .schema_id
+                    .map(|schema_id| self.get_schema(selection.db_id, schema_id));
+                let table = self.get_table(selection);
+                let mut store = self
+                    .store
                     .try_borrow_mut()
-                    .expect("Can't Borrow Store (Msg::TableSelected)")
-                    .selected_db = Some(self.get_db(db_id));
+                    .expect("Can't Borrow Store (Msg::TableSelected)");
+                store.open_table(TableRef { db, schema, table }, vec![]);
+                false
+            }
+            Msg::DiagramSelected(selection) => {
+                let db = self.get_db(selection.db_id);
+                let schema = selection
+                    .schema_id
+                    .map(|schema_id| self.get_schema(selection.db_id, schema_id));
                 self.store
                     .try_borrow_mut()
-                    .expect("Can't Borrow Store (Msg::TableSelected)")
-                    .selected_table = Some(self.get_table(db_id, table_id));
-                self.link
-                    .send_message(Msg::SocketSend(ApiRequest::create_data(
-                        ApiAction::LoadTable,
-                        (
-                            self.store.borrow().selected_db.as_ref().unwrap(),
-                            self.store.borrow().selected_table.as_ref().unwrap(),
-                        ),
-                    )));
+                    .expect("Can't Borrow Store (Msg::DiagramSelected)")
+                    .open_diagram(db, schema);
+                self.page_link.send_message(PageViewMsg::Update);
                 false
             }
-            Msg::UpdateTableData(fields) => {
-                self.store
+            Msg::ObjectSelected(selection) => {
+                let db = self.get_db(selection.db_id);
+                let schema = selection
+                    .schema_id
+                    .map(|schema_id| self.get_schema(selection.db_id, schema_id));
+                let object = self.get_object(selection);
+                let mut store = self
+                    .store
                     .try_borrow_mut()
-                    .expect("Can't Borrow Store (Msg::UpdateTableData)")
-                    .table_data = Some(fields);
+                    .expect("Can't Borrow Store (Msg::ObjectSelected)");
+
+                // a view's rows are browsed like a table's, its definition is a click away
+                match selection.definition || !object.kind.has_rows() {
+                    true => store.load_definition(db, schema, object),
+                    false => store.open_table(TableRef { db, schema, table: object.name }, vec![]),
+                }
+                false
+            }
+            Msg::UpdateTableData(id, _) if id.is_some() && id != self.table_request => false,
+            Msg::UpdateTableData(_, fields) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::UpdateTableData)");
+                    store.table_data = Some(fields);
+                    store.query_progress = None;
+                    store.query_results.clear();
+                }
 
                 // update page only on successful query
                 self.page_link
@@ -158,6 +348,114 @@ impl Component for App {
                     .send_message(PageViewMsg::Update);
                 false
             }
+            Msg::QueryRows(id, _)
+            | Msg::StatementFinished(id, _)
+            | Msg::QueryFinished(id, _)
+            | Msg::QueryCancelled(id, _)
+                if id != self.table_request =>
+            {
+                false
+            }
+            Msg::QueryRows(_, chunk) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::QueryRows)")
+                    .append_query_rows(chunk);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::StatementFinished(_, result) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::StatementFinished)")
+                    .finish_statement(result);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::QueryFinished(_, summary) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::QueryFinished)");
+                    store.set_transaction(summary.transaction.clone());
+                    store.follow_used_db(summary.db.clone());
+                    store.query_progress.get_or_insert_with(Default::default).summary = Some(summary);
+                }
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::QueryCancelled(_, summary) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::QueryCancelled)");
+                    store.set_transaction(summary.transaction.clone());
+                    store.follow_used_db(summary.db.clone());
+                    let progress = store.query_progress.get_or_insert_with(Default::default);
+                    progress.summary = Some(summary);
+                    progress.cancelled = true;
+                }
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::TableDescribed(description) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::TableDescribed)")
+                    .table_description = Some(description);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::References(references) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::References)")
+                    .row_references = Some(references);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::SchemaGraph(graph) => {
+                {
+                    let mut store = self
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (Msg::SchemaGraph)");
+
+                    // another database may have been picked while this one loaded
+                    if !store.show_diagram || store.selected_db.as_ref() != Some(&graph.db) {
+                        return false;
+                    }
+                    store.schema_graph = Some(graph);
+                }
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::GraphExported(export) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::GraphExported)")
+                    .graph_export = Some(export);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::Definition(definition) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::Definition)")
+                    .object_definition = Some(definition);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
+            Msg::Transaction(transaction) => {
+                self.store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (Msg::Transaction)")
+                    .set_transaction(transaction);
+                self.page_link.send_message(PageViewMsg::Update);
+                false
+            }
             _ => false,
         }
     }
@@ -170,13 +468,18 @@ impl Component for App {
         html! {
             <div class="app">
                 {self.warning_banner().unwrap_or_default()}
-                <Navbar/>
+                <Navbar
+                    active_profile=self.store.borrow().active_profile.clone()
+                    on_profiles=self.link.callback(|_| Msg::ShowProfiles)
+                />
                 <div class="columns m-2 fill hide-overflow">
 
                     <div class="db-collapse column p-1 fill scrollable">
                         <DBCollapse
                             store=self.store.clone()
                             on_selected=self.link.callback(Msg::TableSelected)
+                            on_diagram=self.link.callback(Msg::DiagramSelected)
+                            on_object=self.link.callback(Msg::ObjectSelected)
                         />
                     </div>
 
@@ -193,20 +496,60 @@ impl Component for App {
 }
 
 impl App {
+    // Tags the request with an id so its response can be told apart from others in flight
+    fn send(&mut self, request: Request) {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+
+        if matches!(request, Request::LoadTable(_) | Request::RunQuery(_)) {
+            self.table_request = Some(id);
+        }
+        // shown as running, with a cancel button, until its first rows arrive
+        if matches!(request, Request::RunQuery(_)) {
+            {
+                let mut store = self
+                    .store
+                    .try_borrow_mut()
+                    .expect("Can't Borrow Store (App::send)");
+                store.query_progress = Some(QueryProgress::default());
+                store.query_results.clear();
+                store.selected_result = 0;
+            }
+            self.page_link.send_message(PageViewMsg::Update);
+        }
+
+        Self::s_send(&mut self.socket, ApiRequest { id, request });
+    }
+
     pub fn warning_banner(&self) -> Option<Html> {
         match &self.state {
             State::Loading => {
-                Some(self.warning_modal("Websocket Connecting", String::from("Loading..."),
+                Some(self.warning_modal("Websocket Connecting", html! {"Loading..."},
             html! {}))
             }
             State::Errored { error } => {
-                Some(self.warning_modal("Error Occured", format!("Details: {}", error),
+                Some(self.warning_modal(&format!("{} Error", error.category), view_api_error(error),
             html! {
-                        <button onclick=self.link.callback(|_| Msg::ResetSocket) class="button is-success">{"Okay"}</button>
+                        <button onclick=self.link.callback(|_| Msg::DismissError) class="button is-success">{"Okay"}</button>
                     }))
             }
             State::Closed { message } => {
-                Some(self.warning_modal("Websocket Closed", format!("Details: {}", message),
+                Some(self.warning_modal("Websocket Closed", html! {
+                    <>
+                        <p class="mb-3">{format!("Details: {}", message)}</p>
+                        <div class="field">
+                            <label class="label is-small">{"Api Address"}</label>
+                            <div class="control">
+                                <input
+                                    class="input is-small"
+                                    type="text"
+                                    value=self.api_address.clone()
+                                    oninput=self.link.callback(|input: InputData| Msg::UpdateApiAddress(input.value))
+                                />
+                            </div>
+                        </div>
+                    </>
+                },
                 html! {
                         <button onclick=self.link.callback(|_| Msg::ResetSocket) class="button is-success">{"Try Reconnect"}</button>
                     }))
@@ -215,7 +558,7 @@ impl App {
         }
     }
 
-    pub fn warning_modal(&self, title: &str, body: String, button: Html) -> Html {
+    pub fn warning_modal(&self, title: &str, body: Html, button: Html) -> Html {
         html! {
             <div class="modal is-active">
                 <div class="modal-background"></div>
@@ -1,7 +1,8 @@
 use crate::app::store::AppStore;
 
 use super::collapse::Collapse;
-use rustql_types::ApiAction;
+use rustql_types::{Database, ObjectKind, SchemaObject};
+use strum::IntoEnumIterator;
 use std::{cell::RefCell, rc::Rc, usize};
 use yew::{prelude::*, virtual_dom::VNode, Properties};
 
@@ -17,10 +18,38 @@ pub enum DBCollapseMsg {
     ClearSearch,
 }
 
+// Position of the clicked table in the store's database list
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TableSelection {
+    pub db_id: usize,
+    // set for backends that group tables in schemas
+    pub schema_id: Option<usize>,
+    pub table_id: usize,
+}
+
+// Position of the clicked view, routine, trigger or event
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ObjectSelection {
+    pub db_id: usize,
+    pub schema_id: Option<usize>,
+    pub object_id: usize,
+    // shows the definition, for views rather than browsing their rows
+    pub definition: bool,
+}
+
+// Position of the database, or schema, whose diagram was opened
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SchemaSelection {
+    pub db_id: usize,
+    pub schema_id: Option<usize>,
+}
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct DBCollapseProps {
     pub store: Rc<RefCell<AppStore>>,
-    pub on_selected: Callback<(usize, usize)>,
+    pub on_selected: Callback<TableSelection>,
+    pub on_diagram: Callback<SchemaSelection>,
+    pub on_object: Callback<ObjectSelection>,
 }
 
 impl Component for DBCollapse {
@@ -112,9 +141,7 @@ impl DBCollapse {
             .map(|(db_id, item)| {
                 html! {
                     <Collapse title=item.name.clone()>
-                        { for item.tables.iter().enumerate().map(|(i, table)| {
-                            self.view_table_selector(table, i, db_id)
-                        })}
+                        {self.view_database(item, db_id, |_| true, false)}
                     </Collapse>
                 }
             })
@@ -122,6 +149,9 @@ impl DBCollapse {
     }
 
     fn view_search_list(&self) -> Html {
+        let search = self.search_field.to_lowercase();
+        let matches = |table: &String| table.to_lowercase().contains(&search);
+
         let dbs = self
             .props
             .store
@@ -130,23 +160,17 @@ impl DBCollapse {
             .iter()
             .enumerate()
             .map(|(db_id, db)| {
-                let tables: Vec<(usize, &String)> = db
-                    .tables
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, table)| {
-                        table
-                            .to_lowercase()
-                            .contains(&self.search_field.to_lowercase())
-                    })
-                    .collect();
+                let found = db.tables.iter().any(matches)
+                    || db.objects.iter().any(|object| matches(&object.name))
+                    || db.schemas.iter().any(|schema| {
+                        schema.tables.iter().any(matches)
+                            || schema.objects.iter().any(|object| matches(&object.name))
+                    });
 
-                if tables.len() > 0 {
+                if found {
                     html! {
                         <Collapse open=true title=db.name.clone()>
-                            { for tables.iter().map(|(table_id, table)| {
-                                self.view_table_selector(table, *table_id, db_id)
-                            })}
+                            {self.view_database(db, db_id, matches, true)}
                         </Collapse>
                     }
                 } else {
@@ -158,7 +182,117 @@ impl DBCollapse {
         dbs
     }
 
-    fn view_table_selector(&self, table_name: &String, table_id: usize, db_id: usize) -> VNode {
+    // Tables of a database, nested in a collapse per schema when the backend has schemas.
+    // Views, routines, triggers and events follow the tables in a collapse per kind
+    fn view_database(
+        &self,
+        db: &Database,
+        db_id: usize,
+        filter: impl Fn(&String) -> bool,
+        searching: bool,
+    ) -> Html {
+        let view_tables = |tables: &Vec<String>,
+                           objects: &Vec<SchemaObject>,
+                           schema_id: Option<usize>|
+         -> Html {
+            let diagram = match searching {
+                true => Html::default(),
+                false => self.view_diagram_selector(SchemaSelection { db_id, schema_id }),
+            };
+            let tables = tables
+                .iter()
+                .enumerate()
+                .filter(|(_, table)| filter(table))
+                .map(|(table_id, table)| {
+                    self.view_table_selector(
+                        table,
+                        TableSelection {
+                            db_id,
+                            schema_id,
+                            table_id,
+                        },
+                    )
+                })
+                .collect::<Html>();
+            let groups = ObjectKind::iter()
+                .map(|kind| {
+                    let entries = objects
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, object)| object.kind == kind && filter(&object.name))
+                        .map(|(object_id, object)| {
+                            self.view_object_selector(
+                                object,
+                                ObjectSelection {
+                                    db_id,
+                                    schema_id,
+                                    object_id,
+                                    definition: false,
+                                },
+                            )
+                        })
+                        .collect::<Vec<Html>>();
+
+                    match entries.is_empty() {
+                        true => Html::default(),
+                        false => html! {
+                            <Collapse open=searching title=format!("{} ({})", kind.plural(), entries.len())>
+                                {entries}
+                            </Collapse>
+                        },
+                    }
+                })
+                .collect::<Html>();
+
+            html! {
+                <>
+                    {diagram}
+                    {tables}
+                    {groups}
+                </>
+            }
+        };
+
+        if db.schemas.is_empty() {
+            return view_tables(&db.tables, &db.objects, None);
+        }
+
+        db.schemas
+            .iter()
+            .enumerate()
+            .filter(|(_, schema)| {
+                !searching
+                    || schema.tables.iter().any(|table| filter(table))
+                    || schema.objects.iter().any(|object| filter(&object.name))
+            })
+            .map(|(schema_id, schema)| {
+                html! {
+                    <Collapse open=searching title=schema.name.clone()>
+                        {view_tables(&schema.tables, &schema.objects, Some(schema_id))}
+                    </Collapse>
+                }
+            })
+            .collect()
+    }
+
+    fn view_diagram_selector(&self, selection: SchemaSelection) -> Html {
+        html! {
+            <a
+                class="panel-block has-text-info"
+                onclick=self.props.on_diagram.reform(move |event: MouseEvent| {
+                    event.prevent_default();
+                    selection
+                })
+            >
+                <span class="panel-icon has-text-info">
+                    <i class="fas fa-project-diagram" aria-hidden="true"/>
+                </span>
+                {"Diagram"}
+            </a>
+        }
+    }
+
+    fn view_table_selector(&self, table_name: &String, selection: TableSelection) -> VNode {
         html! {
             <a
                 class="panel-block"
@@ -166,11 +300,63 @@ impl DBCollapse {
                 onmouseup=self.link.callback(DBCollapseMsg::PreventDefault)
                 onclick=self.props.on_selected.reform(move|event: MouseEvent| {
                     event.prevent_default();
-                    (table_id, db_id)
+                    selection
                 })
             >
+                <span class="panel-icon">
+                    <i class="fas fa-table" aria-hidden="true"/>
+                </span>
                 {table_name}
             </a>
         }
     }
+
+    // Views open like tables, with their definition behind the icon on the right
+    fn view_object_selector(&self, object: &SchemaObject, selection: ObjectSelection) -> Html {
+        let definition = match object.kind.has_rows() {
+            true => html! {
+                <span
+                    class="icon hand ml-auto has-text-grey"
+                    title="Definition"
+                    onclick=self.props.on_object.reform(move |event: MouseEvent| {
+                        event.prevent_default();
+                        event.stop_propagation();
+                        ObjectSelection { definition: true, ..selection }
+                    })
+                >
+                    <i class="fas fa-code"/>
+                </span>
+            },
+            false => Html::default(),
+        };
+
+        html! {
+            <a
+                class="panel-block"
+                title=object.kind.to_string()
+                onmousedown=self.link.callback(DBCollapseMsg::PreventDefault)
+                onmouseup=self.link.callback(DBCollapseMsg::PreventDefault)
+                onclick=self.props.on_object.reform(move |event: MouseEvent| {
+                    event.prevent_default();
+                    selection
+                })
+            >
+                <span class="panel-icon">
+                    <i class=classes!("fas", object_icon(object.kind)) aria-hidden="true"/>
+                </span>
+                {&object.name}
+                {definition}
+            </a>
+        }
+    }
+}
+
+fn object_icon(kind: ObjectKind) -> &'static str {
+    match kind {
+        ObjectKind::View => "fa-eye",
+        ObjectKind::Procedure => "fa-cogs",
+        ObjectKind::Function => "fa-calculator",
+        ObjectKind::Trigger => "fa-bolt",
+        ObjectKind::Event => "fa-clock",
+    }
 }
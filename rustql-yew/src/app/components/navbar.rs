@@ -9,6 +9,10 @@ pub struct Navbar {
 pub struct DropDownProps {
     #[prop_or_default]
     pub open: bool,
+    #[prop_or_default]
+    pub active_profile: Option<String>,
+    #[prop_or_default]
+    pub on_profiles: Callback<()>,
 }
 
 pub enum Msg {
@@ -50,6 +54,22 @@ impl Component for Navbar {
                             {String::from("Home")}
                         </a>
                     </div>
+                    <div class="navbar-end">
+                        <a
+                            role="button"
+                            class="navbar-item"
+                            onclick=self.props.on_profiles.reform(|_| ())
+                        >
+                            <span class="icon-text">
+                                <span class="icon">
+                                    <i class="fas fa-plug"/>
+                                </span>
+                                <span>
+                                    {self.props.active_profile.clone().unwrap_or_else(|| String::from("Not Connected"))}
+                                </span>
+                            </span>
+                        </a>
+                    </div>
                 </div>
             </div>
         }
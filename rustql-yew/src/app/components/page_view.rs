@@ -1,4 +1,4 @@
-use crate::app::{pages::{view_table::{ViewTable}, welcome_page::WelcomePage}, store::AppStore, structs::page_view_link::CustomLink};
+use crate::app::{pages::{erd_page::ErdPage, profiles_page::ProfilesPage, view_table::{ViewTable}, welcome_page::WelcomePage}, store::AppStore, structs::page_view_link::CustomLink};
 use std::{cell::RefCell, rc::Rc};
 use yew::{html, Component, ComponentLink, Html, Properties, ShouldRender};
 
@@ -17,6 +17,7 @@ pub struct PageViewProps {
 
 pub enum PageViewMsg {
     Update,
+    CloseDefinition,
 }
 
 impl Component for PageView {
@@ -31,7 +32,11 @@ impl Component for PageView {
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            PageViewMsg::Update => true
+            PageViewMsg::Update => true,
+            PageViewMsg::CloseDefinition => {
+                self.props.store.borrow_mut().object_definition = None;
+                true
+            }
         }
     }
 
@@ -46,10 +51,35 @@ impl Component for PageView {
     }
 
     fn view(&self) -> Html {
+        html! {
+            <>
+                {self.view_page()}
+                {self.view_definition()}
+            </>
+        }
+    }
+}
+
+impl PageView {
+    fn view_page(&self) -> Html {
         let db_selected = self.props.store.borrow().selected_db.is_some();
         let table_selected = self.props.store.borrow().selected_table.is_some();
+        let connected = self.props.store.borrow().active_profile.is_some();
+        let show_diagram = self.props.store.borrow().show_diagram;
 
-        if table_selected && db_selected {
+        if !connected || self.props.store.borrow().show_profiles {
+            html! {
+                <ProfilesPage
+                    store=self.props.store.clone()
+                />
+            }
+        } else if show_diagram && db_selected {
+            html! {
+                <ErdPage
+                    store=self.props.store.clone()
+                />
+            }
+        } else if table_selected && db_selected {
             html! {
                 <ViewTable
                     store=self.props.store.clone()
@@ -61,4 +91,42 @@ impl Component for PageView {
             }
         }
     }
+
+    // The CREATE statement of the object picked in the sidebar, over whichever page is open
+    fn view_definition(&self) -> Html {
+        let store = self.props.store.borrow();
+        let definition = match store.object_definition.as_ref() {
+            Some(definition) => definition,
+            None => return Html::default(),
+        };
+        let parent = match &definition.schema {
+            Some(schema) => format!("{}.{}", definition.db, schema),
+            None => definition.db.clone(),
+        };
+
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card definition-card">
+                    <div class="modal-card-head">
+                        <div class="modal-card-title">
+                            {format!("{} {}", definition.kind, definition.name)}
+                            <p class="is-size-7 has-text-grey">{parent}</p>
+                        </div>
+                    </div>
+                    <div class="modal-card-body">
+                        <pre class="is-family-monospace">{&definition.definition}</pre>
+                    </div>
+                    <div class="modal-card-foot">
+                        <button
+                            class="button"
+                            onclick=self.link.callback(|_| PageViewMsg::CloseDefinition)
+                        >
+                            {"Close"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
 }
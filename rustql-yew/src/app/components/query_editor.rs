@@ -1,4 +1,4 @@
-use rustql_types::{ApiAction, ApiRequest};
+use rustql_types::{find_placeholders, split_statements, QueryParams, Request, RunQueryRequest};
 use yew::{Callback, Component, ComponentLink, Html, InputData, KeyboardEvent, NodeRef, Properties, classes, html::{onscroll::Event}, services::ConsoleService, web_sys::{HtmlElement, HtmlTextAreaElement}};
 use std::{cell::RefCell, rc::Rc};
 use yew::{html};
@@ -17,6 +17,22 @@ pub struct QueryEditor {
     height: i32,
     text: String,
     code: String,
+    // open while asking for placeholder values before running
+    prompt: Option<ParamPrompt>,
+}
+
+// The values asked for by the placeholders of the query about to run
+#[derive(Clone)]
+struct ParamPrompt {
+    // named placeholders in the order they first appear
+    names: Vec<String>,
+    params: QueryParams,
+}
+
+#[derive(Clone)]
+pub enum ParamKey {
+    Named(String),
+    Positional(usize),
 }
 
 #[derive(Clone, PartialEq, Properties)]
@@ -39,6 +55,10 @@ pub enum QueryEditorMsg {
     QueryKeyEvent(KeyboardEvent),
     Update(i32),
     Refresh,
+    UpdateParam(ParamKey, String),
+    ToggleNullParam(ParamKey),
+    RunWithParams,
+    ClosePrompt,
 }
 
 impl Component for QueryEditor {
@@ -63,6 +83,7 @@ impl Component for QueryEditor {
             props,
             text: String::new(),
             code: String::new(),
+            prompt: None,
         }
     }
 
@@ -169,30 +190,27 @@ impl Component for QueryEditor {
                         83 => self.append_shortcut(event, "SELECT * ", None, None),
                         70 => {
                             let text = &format!(
-                                "FROM `{}`.`{}` ",
-                                self.props.store.try_borrow().unwrap().selected_db.as_ref().unwrap(),
-                                self.props.store.try_borrow().unwrap().selected_table.as_ref().unwrap(),
+                                "FROM {} ",
+                                self.props.store.try_borrow().unwrap().qualified_table(),
                             );
                             self.append_shortcut(event, text, None, None)
                         },
                         73 => {
                             let text = &format!(
-                                "INSERT INTO `{}`.`{}` (``)\nVALUES('')",
-                                self.props.store.try_borrow().unwrap().selected_db.as_ref().unwrap(),
-                                self.props.store.try_borrow().unwrap().selected_table.as_ref().unwrap(),
+                                "INSERT INTO {} ()\nVALUES('')",
+                                self.props.store.try_borrow().unwrap().qualified_table(),
                             );
-                            let pos: Option<u32> = Some(text.chars().count() as u32 - 13);
+                            let pos: Option<u32> = Some(text.chars().count() as u32 - 12);
                             self.append_shortcut(event, text, pos, pos)
                         },
                         76 => self.append_shortcut(event, "LIMIT 24", Some(6), Some(8)),
                         74 => {
                             let text = &format!(
-                                "LEFT JOIN `{}`.`{}` as `name`",
-                                self.props.store.try_borrow().unwrap().selected_db.as_ref().unwrap(),
-                                self.props.store.try_borrow().unwrap().selected_table.as_ref().unwrap(),
+                                "LEFT JOIN {} as name",
+                                self.props.store.try_borrow().unwrap().qualified_table(),
                             );
-                            let start: Option<u32> = Some(text.chars().count() as u32 - 5);
-                            let end: Option<u32> = Some(text.chars().count() as u32 - 1);
+                            let start: Option<u32> = Some(text.chars().count() as u32 - 4);
+                            let end: Option<u32> = Some(text.chars().count() as u32);
                             self.append_shortcut(event, text, start, end)
                         },
                         85 => self.append_shortcut(event, "UPDATE ", None, None),
@@ -204,11 +222,42 @@ impl Component for QueryEditor {
                 } else {
                     match event.key_code() {
                         9 => self.append_shortcut(event, "\t", None, None),
+                        27 => self.props.store.try_borrow().unwrap().cancel_query(),
                         _ => {}
                     }
                 }
                 false
             }
+            QueryEditorMsg::UpdateParam(key, value) => {
+                if let Some(param) = self.prompt.as_mut().and_then(|prompt| prompt.param_mut(&key)) {
+                    *param = Some(value);
+                }
+                false
+            }
+            QueryEditorMsg::ToggleNullParam(key) => {
+                if let Some(param) = self.prompt.as_mut().and_then(|prompt| prompt.param_mut(&key)) {
+                    *param = match param {
+                        Some(_) => None,
+                        None => Some(String::new()),
+                    };
+                }
+                true
+            }
+            QueryEditorMsg::RunWithParams => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.props
+                        .store
+                        .try_borrow_mut()
+                        .expect("Can't Borrow Store (remember_query_params)")
+                        .remember_query_params(&prompt.params);
+                    self.run_query(prompt.params);
+                }
+                true
+            }
+            QueryEditorMsg::ClosePrompt => {
+                self.prompt = None;
+                true
+            }
             QueryEditorMsg::Refresh => true,
             QueryEditorMsg::Update(height) => {
                 self.height = height;
@@ -256,6 +305,7 @@ impl Component for QueryEditor {
                         ref=self.code_ref.clone()
                     />
                 </div>
+                {self.view_prompt()}
             </>
         }
     }
@@ -265,33 +315,168 @@ impl Component for QueryEditor {
     fn destroy(&mut self) {}
 }
 
+impl ParamPrompt {
+    fn param_mut(&mut self, key: &ParamKey) -> Option<&mut Option<String>> {
+        match key {
+            ParamKey::Named(name) => self.params.named.get_mut(name),
+            ParamKey::Positional(index) => self.params.positional.get_mut(*index),
+        }
+    }
+}
+
 impl QueryEditor {
-    pub fn send_query(&self) {
-        let data: (String, String, String) = (
-            self.props
+    // Runs the query, asking for the values of its placeholders first when it has any
+    pub fn send_query(&mut self) {
+        let (driver, remembered) = {
+            let store = self.props.store.try_borrow().expect("Could not borrow store");
+            (store.active_driver(), store.query_params.clone())
+        };
+
+        let mut names: Vec<String> = vec![];
+        let mut positional = 0;
+        for statement in split_statements(driver, &self.text) {
+            for placeholder in find_placeholders(driver, &statement) {
+                match placeholder.name {
+                    Some(name) if !names.contains(&name) => names.push(name),
+                    Some(_) => {}
+                    // numbered ones take their own value, a plain `?` the one after the last taken
+                    None => positional = placeholder.number.unwrap_or(positional + 1).max(positional),
+                }
+            }
+        }
+
+        if names.is_empty() && positional == 0 {
+            return self.run_query(QueryParams::default());
+        }
+
+        let named = names
+            .iter()
+            .map(|name| {
+                let value = remembered.named.get(name).cloned().unwrap_or_else(|| Some(String::new()));
+                (name.clone(), value)
+            })
+            .collect();
+        let positional = (0..positional)
+            .map(|index| remembered.positional.get(index).cloned().unwrap_or_else(|| Some(String::new())))
+            .collect();
+
+        self.prompt = Some(ParamPrompt {
+            names,
+            params: QueryParams { named, positional },
+        });
+        self.link.send_message(QueryEditorMsg::Refresh);
+    }
+
+    fn run_query(&self, params: QueryParams) {
+        let target = self
+            .props
+            .store
+            .try_borrow()
+            .expect("Could not borrow store")
+            .query_target()
+            .unwrap_or_default();
+        let request = RunQueryRequest {
+            db: target.db,
+            schema: target.schema,
+            table: self.props
                 .store
                 .try_borrow()
                 .expect("Could not borrow store")
-                .selected_db
-                .as_ref()
-                .expect("QueryKeyEvent expects db, failed")
-                .clone(),
-            self.props
+                .selected_table
+                .clone()
+                .unwrap_or_default(),
+            query: self.text.clone(),
+            continue_on_error: self
+                .props
                 .store
                 .try_borrow()
                 .expect("Could not borrow store")
-                .selected_table
-                .as_ref()
-                .expect("QueryKeyEvent expects table, failed")
-                .clone(),
-            self.text.clone(),
-        );
+                .continue_on_error,
+            params,
+        };
 
         self.props
             .store
             .try_borrow()
             .expect("Could not borrow store")
-            .socket_send(ApiRequest::create_data(ApiAction::RunQuery, data));
+            .socket_send(Request::RunQuery(request));
+    }
+
+    fn view_prompt(&self) -> Html {
+        let prompt = match &self.prompt {
+            Some(prompt) => prompt,
+            None => return Html::default(),
+        };
+
+        let named = prompt.names.iter().map(|name| {
+            let value = prompt.params.named.get(name).cloned().flatten();
+            self.view_param(format!(":{}", name), value, ParamKey::Named(name.clone()))
+        });
+        let positional = prompt.params.positional.iter().enumerate().map(|(index, value)| {
+            self.view_param(format!("?{}", index + 1), value.clone(), ParamKey::Positional(index))
+        });
+
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <div class="modal-card-head">
+                        <div class="modal-card-title">{"Query Parameters"}</div>
+                    </div>
+                    <div class="modal-card-body">
+                        {for named}
+                        {for positional}
+                    </div>
+                    <div class="modal-card-foot">
+                        <button
+                            class="button is-success"
+                            onclick=self.link.callback(|_| QueryEditorMsg::RunWithParams)
+                        >
+                            {"Run"}
+                        </button>
+                        <button
+                            class="button"
+                            onclick=self.link.callback(|_| QueryEditorMsg::ClosePrompt)
+                        >
+                            {"Cancel"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    // A value sent as text and converted by the server, or NULL
+    fn view_param(&self, label: String, value: Option<String>, key: ParamKey) -> Html {
+        let is_null = value.is_none();
+        let input_key = key.clone();
+
+        html! {
+            <div class="field">
+                <label class="label is-small is-family-monospace">{label}</label>
+                <div class="field has-addons">
+                    <div class="control is-expanded">
+                        <input
+                            class="input is-small"
+                            type="text"
+                            value=value.unwrap_or_default()
+                            placeholder=is_null.then(|| "NULL").unwrap_or_default()
+                            disabled=is_null
+                            oninput=self.link.callback(move |input: InputData| QueryEditorMsg::UpdateParam(input_key.clone(), input.value))
+                        />
+                    </div>
+                    <div class="control">
+                        <button
+                            class=classes!("button", "is-small", is_null.then(|| "is-info"))
+                            title="Send NULL instead of a value"
+                            onclick=self.link.callback(move |_| QueryEditorMsg::ToggleNullParam(key.clone()))
+                        >
+                            {"NULL"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
     }
 
     pub fn append_shortcut(&mut self, event: KeyboardEvent, text: &str, start: Option<u32>, end: Option<u32>) {
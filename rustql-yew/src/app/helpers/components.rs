@@ -1,18 +1,67 @@
 use std::{borrow::Borrow, usize};
 
-use crate::app::App;
+use crate::app::{components::db_collapse::{ObjectSelection, TableSelection}, App};
+use rustql_types::{ApiError, ErrorCategory, SchemaObject};
+use yew::{html, Html};
 
 impl App {
-    pub fn get_table(&self, db_id: usize, table_id: usize) -> String {
+    pub fn get_table(&self, selection: TableSelection) -> String {
+        let store = self.store.try_borrow().expect("Cannot Access Store");
+        let db = store
+            .databases
+            .get(selection.db_id)
+            .expect("Selected database does not exist");
+
+        let tables = match selection.schema_id {
+            Some(schema_id) => {
+                &db.schemas
+                    .get(schema_id)
+                    .expect("Selected schema does not exist")
+                    .tables
+            }
+            None => &db.tables,
+        };
+
+        tables
+            .get(selection.table_id)
+            .expect("Selected table does not exist")
+            .to_string()
+    }
+
+    pub fn get_object(&self, selection: ObjectSelection) -> SchemaObject {
+        let store = self.store.try_borrow().expect("Cannot Access Store");
+        let db = store
+            .databases
+            .get(selection.db_id)
+            .expect("Selected database does not exist");
+
+        let objects = match selection.schema_id {
+            Some(schema_id) => {
+                &db.schemas
+                    .get(schema_id)
+                    .expect("Selected schema does not exist")
+                    .objects
+            }
+            None => &db.objects,
+        };
+
+        objects
+            .get(selection.object_id)
+            .expect("Selected object does not exist")
+            .clone()
+    }
+
+    pub fn get_schema(&self, db_id: usize, schema_id: usize) -> String {
         self.store
             .try_borrow()
             .expect("Cannot Access Store")
             .databases
             .get(db_id)
-            .expect("Selected database does not exist")
-            .tables
-            .get(table_id)
-            .expect("Selected table does not exist")
+            .expect("Can't Find Db")
+            .schemas
+            .get(schema_id)
+            .expect("Can't Find Schema")
+            .name
             .to_string()
     }
 
@@ -27,3 +76,38 @@ impl App {
             .to_string()
     }
 }
+
+// Message and details of an error from the api, with what to change for limit errors
+pub fn view_api_error(error: &ApiError) -> Html {
+    let detail = |label: &str, value: Option<String>| match value {
+        Some(value) => html! {
+            <div class="control">
+                <div class="tags has-addons">
+                    <span class="tag is-dark">{label}</span>
+                    <span class="tag is-danger is-light">{value}</span>
+                </div>
+            </div>
+        },
+        None => Html::default(),
+    };
+
+    // what to change when a profile's query limit stopped the statement
+    let hint = match error.category {
+        ErrorCategory::Timeout => Some("The query ran longer than this connection profile allows and was stopped on the server. Raise or clear Max Execution Time in the profile to let it finish."),
+        ErrorCategory::RowLimit => Some("The query returned more rows than this connection profile allows, the rows within the limit are shown. Add a LIMIT or raise Max Rows in the profile."),
+        ErrorCategory::SizeLimit => Some("The result grew larger than this connection profile allows, the rows within the limit are shown. Select fewer or smaller columns, or raise Max Response in the profile."),
+        _ => None,
+    };
+
+    html! {
+        <>
+            <p class="mb-3">{&error.message}</p>
+            {hint.map(|hint| html! { <p class="mb-3 is-size-7 has-text-grey">{hint}</p> }).unwrap_or_default()}
+            <div class="field is-grouped is-grouped-multiline">
+                {detail("Action", error.action.clone())}
+                {detail("Code", error.code.map(|code| code.to_string()))}
+                {detail("SQLSTATE", error.sql_state.clone())}
+            </div>
+        </>
+    }
+}
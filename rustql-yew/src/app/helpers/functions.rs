@@ -2,7 +2,7 @@ use std::borrow::BorrowMut;
 
 use lazy_static::lazy_static;
 use regex::{Regex};
-use yew::services::ConsoleService;
+use yew::{services::ConsoleService, utils::window, web_sys::js_sys::Function};
 extern crate lazy_static;
 
 lazy_static! {
@@ -16,4 +16,27 @@ pub fn format_string_sql(text: &mut String) {
     *text = SQL_REGEX.replace_all(&*text.as_str(), r#"$1<span style="color: blue"><b>$2</b></span>$3"#).into();
     *text = SQL_REGEX.replace_all(&*text.as_str(), r#"$1<span style="color: blue"><b>$2</b></span>$3"#).into();
     *text = BR_REGEX.replace_all(&*text.as_str(), "<br/>").into();
-}
\ No newline at end of file
+}
+
+// 12000 -> "12,000"
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+// Has the browser ask before the page is closed or reloaded, closing the socket loses the
+// session's open transaction
+pub fn warn_on_close(warn: bool) {
+    let handler = warn.then(|| {
+        Function::new_with_args("event", "event.preventDefault(); event.returnValue = '';")
+    });
+    window().set_onbeforeunload(handler.as_ref());
+}
@@ -1,29 +1,44 @@
-use anyhow::Error;
-use rustql_types::{ApiAction, ApiRequest};
-use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use rustql_types::{ApiError, ApiRequest, ApiResponse, ErrorCategory, Response, PROTOCOL_VERSION};
 use yew::{
-    format::Json,
+    format::Text,
     services::{
+        storage::{Area, StorageService},
         websocket::{WebSocketStatus, WebSocketTask},
         WebSocketService,
     },
     Callback, Component, ComponentLink,
 };
 
+static API_ADDRESS_KEY: &str = "rustql.api_address";
+
+// Set RUSTQL_API_URL at build time to point the client at another api by default
+fn default_api_address() -> String {
+    option_env!("RUSTQL_API_URL")
+        .unwrap_or("ws://127.0.0.1:8888")
+        .to_string()
+}
+
+pub fn api_address() -> String {
+    StorageService::new(Area::Local)
+        .ok()
+        .and_then(|storage| storage.restore::<Text>(API_ADDRESS_KEY).ok())
+        .filter(|address| !address.trim().is_empty())
+        .unwrap_or_else(default_api_address)
+}
+
+pub fn set_api_address(address: &str) {
+    if let Ok(mut storage) = StorageService::new(Area::Local) {
+        storage.store(API_ADDRESS_KEY, Ok(address.trim().to_string()));
+    }
+}
+
 pub enum SocketMessage {
     Closed,
     Ignore,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ApiResponse {
-    action: String,
-    data: Option<String>,
-}
-
 pub trait Socket<T: Component> {
-    fn on_message(msg: ApiAction, data: String) -> T::Message;
+    fn on_message(id: Option<u64>, response: Response) -> T::Message;
 
     fn on_notification(msg: SocketMessage) -> T::Message;
 
@@ -31,7 +46,7 @@ pub trait Socket<T: Component> {
         let on_message = Self::s_out(link.clone());
         let on_close = Self::s_close(link.clone());
 
-        let task = WebSocketService::connect_text("ws://127.0.0.1:8888", on_message, on_close);
+        let task = WebSocketService::connect_text(&api_address(), on_message, on_close);
         match task {
             Ok(socket) => Some(socket),
             Err(_) => None,
@@ -50,36 +65,16 @@ pub trait Socket<T: Component> {
         }
     }
 
-    fn s_send_data<M: Serialize>(
-        socket: &mut Option<WebSocketTask>,
-        action: ApiAction,
-        data: M,
-    ) -> bool {
-        let request = ApiRequest {
-            action: action.to_string(),
-            data: Some(serde_json::to_string(&data).expect("Failed to serialize request data")),
-        };
-
-        let request_string = serde_json::to_string(&request).expect("Failed to build request");
-
-        match socket {
-            Some(ref mut socket) => {
-                socket.send(Ok(request_string));
-                true
+    fn s_out(link: ComponentLink<T>) -> Callback<Text> {
+        link.callback(|text: Text| match text {
+            Ok(text) => {
+                let (id, response) = parse_response(&text);
+                Self::on_message(id, response)
             }
-            None => false,
-        }
-    }
-
-    fn s_out(link: ComponentLink<T>) -> Callback<Json<Result<ApiResponse, Error>>> {
-        link.callback(|Json(msg): Json<Result<ApiResponse, Error>>| match msg {
-            Ok(response) => match ApiAction::from_str(&response.action) {
-                Ok(action) => Self::on_message(action, response.data.unwrap_or_default()),
-                Err(_) => {
-                    Self::on_message(ApiAction::Error, format!("{} not found", response.action))
-                }
-            },
-            Err(err) => Self::on_message(ApiAction::Error, err.to_string()),
+            Err(err) => Self::on_message(
+                None,
+                Response::Error(ApiError::new(ErrorCategory::Internal, err.to_string())),
+            ),
         })
     }
 
@@ -92,3 +87,26 @@ pub trait Socket<T: Component> {
         })
     }
 }
+
+// Responses from an api on another protocol version may not deserialize,
+// so fall back to reading the raw json to report the mismatch
+fn parse_response(text: &str) -> (Option<u64>, Response) {
+    match serde_json::from_str::<ApiResponse>(text) {
+        Ok(response) => (response.id, response.response),
+        Err(err) => {
+            let value: serde_json::Value = serde_json::from_str(text).unwrap_or_default();
+            let message = match value["response"]["data"]["protocol_version"].as_u64() {
+                Some(version) if version != PROTOCOL_VERSION as u64 => format!(
+                    "Incompatible api: it speaks protocol version {} but this client speaks version {}",
+                    version, PROTOCOL_VERSION
+                ),
+                _ => format!("Unsupported response from api: {}", err),
+            };
+
+            (
+                value["id"].as_u64(),
+                Response::Error(ApiError::new(ErrorCategory::Protocol, message)),
+            )
+        }
+    }
+}
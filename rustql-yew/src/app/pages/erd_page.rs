@@ -0,0 +1,465 @@
+use std::{cell::RefCell, rc::Rc};
+use rustql_types::{GraphExport, GraphFocus, GraphFormat, GraphLayout, GraphTable, Point, SchemaGraph, TableRef, HEADER_HEIGHT, TABLE_WIDTH};
+use strum::IntoEnumIterator;
+use yew::{html, web_sys::js_sys::encode_uri_component, ChangeData, Component, ComponentLink, Html, InputData, MouseEvent, Properties, WheelEvent};
+
+use crate::app::store::AppStore;
+
+// Bounds of the zoom, and the step of the zoom buttons and of each wheel notch
+static MIN_ZOOM: f64 = 0.2;
+static MAX_ZOOM: f64 = 3.0;
+static ZOOM_STEP: f64 = 1.2;
+
+pub struct ErdPage {
+    link: ComponentLink<Self>,
+    props: ErdPageProps,
+    zoom: f64,
+    // where the drawing's origin sits on the canvas
+    pan: Point,
+    drag: Option<Drag>,
+    // the table whose neighbourhood is shown, every table when empty
+    focus_table: String,
+    hops: usize,
+}
+
+// What the mouse moves while its button is held, with the pointer's last position
+enum Drag {
+    Canvas(i32, i32),
+    Table(String, i32, i32),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ErdPageProps {
+    #[prop_or_default]
+    pub store: Rc<RefCell<AppStore>>,
+}
+
+pub enum ErdPageMsg {
+    StartPan(MouseEvent),
+    StartDrag(String, MouseEvent),
+    Move(MouseEvent),
+    EndDrag,
+    Wheel(WheelEvent),
+    Zoom(f64),
+    ResetView,
+    ResetLayout,
+    FocusTable(String),
+    Hops(String),
+    OpenTable(String),
+    Export(GraphFormat),
+    CloseExport,
+    Reload,
+}
+
+impl Component for ErdPage {
+    type Message = ErdPageMsg;
+    type Properties = ErdPageProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            props,
+            zoom: 1.0,
+            pan: Point { x: 20.0, y: 20.0 },
+            drag: None,
+            focus_table: String::new(),
+            hops: 1,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> yew::ShouldRender {
+        match msg {
+            ErdPageMsg::StartPan(event) => {
+                event.prevent_default();
+                self.drag = Some(Drag::Canvas(event.client_x(), event.client_y()));
+                false
+            }
+            ErdPageMsg::StartDrag(table, event) => {
+                event.prevent_default();
+                event.stop_propagation();
+
+                // tables still on the grid get their position pinned once dragged
+                let position = self.layout().get(&table).copied().unwrap_or_default();
+                self.props.store.borrow_mut().move_graph_table(table.clone(), position);
+                self.drag = Some(Drag::Table(table, event.client_x(), event.client_y()));
+                false
+            }
+            ErdPageMsg::Move(event) => {
+                let (x, y) = (event.client_x(), event.client_y());
+                match self.drag.as_mut() {
+                    Some(Drag::Canvas(last_x, last_y)) => {
+                        self.pan.x += (x - *last_x) as f64;
+                        self.pan.y += (y - *last_y) as f64;
+                        *last_x = x;
+                        *last_y = y;
+                        true
+                    }
+                    Some(Drag::Table(table, last_x, last_y)) => {
+                        let mut store = self.props.store.borrow_mut();
+                        let mut position = store.graph_layout.get(table.as_str()).copied().unwrap_or_default();
+                        position.x += (x - *last_x) as f64 / self.zoom;
+                        position.y += (y - *last_y) as f64 / self.zoom;
+                        store.move_graph_table(table.clone(), position);
+                        *last_x = x;
+                        *last_y = y;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            ErdPageMsg::EndDrag => {
+                if let Some(Drag::Table(..)) = self.drag.take() {
+                    self.props.store.borrow().save_graph_layout();
+                }
+                false
+            }
+            ErdPageMsg::Wheel(event) => {
+                event.prevent_default();
+                let factor = match event.delta_y() < 0.0 {
+                    true => ZOOM_STEP,
+                    false => 1.0 / ZOOM_STEP,
+                };
+                // the point under the pointer stays put
+                self.zoom_at(factor, event.offset_x() as f64, event.offset_y() as f64);
+                true
+            }
+            ErdPageMsg::Zoom(factor) => {
+                self.zoom_at(factor, 0.0, 0.0);
+                true
+            }
+            ErdPageMsg::ResetView => {
+                self.zoom = 1.0;
+                self.pan = Point { x: 20.0, y: 20.0 };
+                true
+            }
+            ErdPageMsg::ResetLayout => {
+                self.props.store.borrow_mut().reset_graph_layout();
+                true
+            }
+            ErdPageMsg::FocusTable(table) => {
+                self.focus_table = table;
+                true
+            }
+            ErdPageMsg::Hops(hops) => {
+                self.hops = hops.parse().unwrap_or(self.hops);
+                true
+            }
+            ErdPageMsg::OpenTable(table) => {
+                let mut store = self.props.store.borrow_mut();
+                let (db, schema) = (store.get_db().unwrap_or_default(), store.selected_schema.clone());
+                store.open_table(TableRef { db, schema, table }, vec![]);
+                false
+            }
+            ErdPageMsg::Export(format) => {
+                let store = self.props.store.borrow();
+                store.export_graph(format, self.focus(), self.layout());
+                false
+            }
+            ErdPageMsg::CloseExport => {
+                self.props.store.borrow_mut().graph_export = None;
+                true
+            }
+            ErdPageMsg::Reload => {
+                let mut store = self.props.store.borrow_mut();
+                let (db, schema) = (store.get_db().unwrap_or_default(), store.selected_schema.clone());
+                store.open_diagram(db, schema);
+                true
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let store = self.props.store.borrow();
+        let graph = match store.schema_graph.as_ref() {
+            Some(graph) => graph.focused(self.focus().as_ref()),
+            None => {
+                return html! {
+                    <div class="rows rows-fill">
+                        {self.view_toolbar(None)}
+                        <p class="has-text-grey">{"Loading diagram..."}</p>
+                    </div>
+                }
+            }
+        };
+        let layout = graph.layout(&store.graph_layout);
+
+        let edges = graph
+            .edges()
+            .filter_map(|key| {
+                let path = graph.edge_path(&layout, key)?;
+                Some(html! {
+                    <path class="erd-edge" d=path marker-end="url(#erd-arrow)">
+                        <title>{format!("{} ({}) -> {} ({})",
+                            key.table.table,
+                            key.columns.join(", "),
+                            key.referenced_table.table,
+                            key.referenced_columns.join(", "))}</title>
+                    </path>
+                })
+            })
+            .collect::<Html>();
+        let tables = graph
+            .tables
+            .iter()
+            .filter_map(|table| Some(self.view_table(table, *layout.get(&table.name)?)))
+            .collect::<Html>();
+
+        html! {
+            <div class="rows rows-fill">
+                {self.view_toolbar(store.schema_graph.as_ref())}
+                <svg
+                    class="erd-canvas fill"
+                    onmousedown=self.link.callback(ErdPageMsg::StartPan)
+                    onmousemove=self.link.callback(ErdPageMsg::Move)
+                    onmouseup=self.link.callback(|_| ErdPageMsg::EndDrag)
+                    onmouseleave=self.link.callback(|_| ErdPageMsg::EndDrag)
+                    onwheel=self.link.callback(ErdPageMsg::Wheel)
+                >
+                    <defs>
+                        <marker
+                            id="erd-arrow" viewBox="0 0 10 10" refX="10" refY="5"
+                            markerWidth="8" markerHeight="8" orient="auto-start-reverse"
+                        >
+                            <path d="M 0 0 L 10 5 L 0 10 z" class="erd-arrow"/>
+                        </marker>
+                    </defs>
+                    <g transform=format!("translate({} {}) scale({})", self.pan.x, self.pan.y, self.zoom)>
+                        {edges}
+                        {tables}
+                    </g>
+                </svg>
+                {self.view_export(store.graph_export.as_ref())}
+            </div>
+        }
+    }
+}
+
+impl ErdPage {
+    fn focus(&self) -> Option<GraphFocus> {
+        match self.focus_table.is_empty() {
+            true => None,
+            false => Some(GraphFocus {
+                table: self.focus_table.clone(),
+                hops: self.hops,
+            }),
+        }
+    }
+
+    // Every box's position, the dragged ones and the rest on the grid
+    fn layout(&self) -> GraphLayout {
+        let store = self.props.store.borrow();
+        store
+            .schema_graph
+            .as_ref()
+            .map(|graph| graph.focused(self.focus().as_ref()).layout(&store.graph_layout))
+            .unwrap_or_default()
+    }
+
+    // Scales the drawing by `factor` around the canvas point (x, y)
+    fn zoom_at(&mut self, factor: f64, x: f64, y: f64) {
+        let zoom = (self.zoom * factor).max(MIN_ZOOM).min(MAX_ZOOM);
+        let scale = zoom / self.zoom;
+        self.pan.x = x - (x - self.pan.x) * scale;
+        self.pan.y = y - (y - self.pan.y) * scale;
+        self.zoom = zoom;
+    }
+
+    fn view_toolbar(&self, graph: Option<&SchemaGraph>) -> Html {
+        let store = self.props.store.borrow();
+        let title = match &store.selected_schema {
+            Some(schema) => format!("{}.{}", store.get_db().unwrap_or_default(), schema),
+            None => store.get_db().unwrap_or_default(),
+        };
+        let focus_options = graph
+            .map(|graph| {
+                graph
+                    .tables
+                    .iter()
+                    .map(|table| html! {
+                        <option value=table.name.clone() selected={table.name == self.focus_table}>
+                            {&table.name}
+                        </option>
+                    })
+                    .collect::<Html>()
+            })
+            .unwrap_or_default();
+        let exports = GraphFormat::iter()
+            .map(|format| html! {
+                <div class="control">
+                    <button
+                        class="button is-small is-info is-light"
+                        disabled=graph.is_none()
+                        onclick=self.link.callback(move |_| ErdPageMsg::Export(format))
+                    >
+                        {format.to_string()}
+                    </button>
+                </div>
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="level mb-2">
+                <div class="level-left">
+                    <span class="icon-text level-item">
+                        <span class="icon">
+                            <i class="fas fa-project-diagram"/>
+                        </span>
+                        <b>{title}</b>
+                    </span>
+                    <div class="field has-addons level-item">
+                        <div class="control">
+                            <div class="select is-small">
+                                <select onchange=self.link.callback(|change| match change {
+                                    ChangeData::Select(select) => ErdPageMsg::FocusTable(select.value()),
+                                    _ => ErdPageMsg::FocusTable(String::new()),
+                                })>
+                                    <option value="" selected=self.focus_table.is_empty()>{"All tables"}</option>
+                                    {focus_options}
+                                </select>
+                            </div>
+                        </div>
+                        <div class="control">
+                            <input
+                                class="input is-small erd-hops"
+                                type="number"
+                                min="0"
+                                title="Foreign keys away from the table"
+                                disabled=self.focus_table.is_empty()
+                                value=self.hops.to_string()
+                                oninput=self.link.callback(|input: InputData| ErdPageMsg::Hops(input.value))
+                            />
+                        </div>
+                    </div>
+                </div>
+                <div class="level-right">
+                    <div class="field has-addons level-item">
+                        <div class="control">
+                            <button class="button is-small" title="Zoom out" onclick=self.link.callback(|_| ErdPageMsg::Zoom(1.0 / ZOOM_STEP))>
+                                <span class="icon"><i class="fas fa-search-minus"/></span>
+                            </button>
+                        </div>
+                        <div class="control">
+                            <button class="button is-small" title="Reset view" onclick=self.link.callback(|_| ErdPageMsg::ResetView)>
+                                {format!("{:.0}%", self.zoom * 100.0)}
+                            </button>
+                        </div>
+                        <div class="control">
+                            <button class="button is-small" title="Zoom in" onclick=self.link.callback(|_| ErdPageMsg::Zoom(ZOOM_STEP))>
+                                <span class="icon"><i class="fas fa-search-plus"/></span>
+                            </button>
+                        </div>
+                    </div>
+                    <div class="field has-addons level-item">
+                        <div class="control">
+                            <button class="button is-small" title="Put the tables back on the grid" onclick=self.link.callback(|_| ErdPageMsg::ResetLayout)>
+                                <span class="icon"><i class="fas fa-th"/></span>
+                            </button>
+                        </div>
+                        <div class="control">
+                            <button class="button is-small" title="Reload" onclick=self.link.callback(|_| ErdPageMsg::Reload)>
+                                <span class="icon"><i class="fas fa-sync-alt"/></span>
+                            </button>
+                        </div>
+                    </div>
+                    <div class="field has-addons level-item">
+                        {exports}
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    // A table's box, dragged by its header. A double click on the header browses the table
+    fn view_table(&self, table: &GraphTable, position: Point) -> Html {
+        let name = table.name.clone();
+        let open = table.name.clone();
+        let focused = table.name == self.focus_table;
+
+        let columns = table
+            .columns
+            .iter()
+            .map(|column| {
+                let y = table.column_y(&column.name) + 4.0;
+                html! {
+                    <>
+                        <text x="8" y=y.to_string() class={if column.primary_key { "erd-key" } else { "" }}>
+                            {&column.name}
+                        </text>
+                        <text x=(TABLE_WIDTH - 8.0).to_string() y=y.to_string() text-anchor="end" class="erd-type">
+                            {&column.sql_type}
+                        </text>
+                    </>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <g class="erd-table" transform=format!("translate({} {})", position.x, position.y)>
+                <rect
+                    class={if focused { "erd-box is-focused" } else { "erd-box" }}
+                    width=TABLE_WIDTH.to_string()
+                    height=table.height().to_string()
+                    rx="4"
+                />
+                <g
+                    class="erd-header"
+                    onmousedown=self.link.callback(move |event| ErdPageMsg::StartDrag(name.clone(), event))
+                    ondblclick=self.link.callback(move |_| ErdPageMsg::OpenTable(open.clone()))
+                >
+                    <rect width=TABLE_WIDTH.to_string() height=HEADER_HEIGHT.to_string() rx="4"/>
+                    <text x="8" y="19">{&table.name}</text>
+                </g>
+                {columns}
+            </g>
+        }
+    }
+
+    // The exported text with a link saving it to a file
+    fn view_export(&self, export: Option<&GraphExport>) -> Html {
+        let export = match export {
+            Some(export) => export,
+            None => return Html::default(),
+        };
+        let href = format!(
+            "data:{};charset=utf-8,{}",
+            export.format.mime_type(),
+            String::from(encode_uri_component(&export.text))
+        );
+        let file_name = format!(
+            "{}.{}",
+            self.props.store.borrow().get_db().unwrap_or_default(),
+            export.format.extension()
+        );
+
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <div class="modal-card-head">
+                        <div class="modal-card-title">{format!("{} Export", export.format)}</div>
+                    </div>
+                    <div class="modal-card-body">
+                        <pre class="is-family-monospace">{&export.text}</pre>
+                    </div>
+                    <div class="modal-card-foot">
+                        <a class="button is-success" href=href download=file_name>
+                            <span class="icon"><i class="fas fa-download"/></span>
+                            <span>{"Download"}</span>
+                        </a>
+                        <button
+                            class="button"
+                            onclick=self.link.callback(|_| ErdPageMsg::CloseExport)
+                        >
+                            {"Close"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod erd_page;
+pub mod profiles_page;
 pub mod view_table;
 pub mod welcome_page;
@@ -0,0 +1,505 @@
+use rustql_types::{ConnectionProfile, DriverKind, ProfileName, Request, TransactionStatus, UpdateProfileRequest};
+use std::{cell::RefCell, rc::Rc};
+use strum::IntoEnumIterator;
+use yew::{classes, html, utils::window, ChangeData, Component, ComponentLink, Html, InputData, Properties};
+
+use crate::app::store::AppStore;
+
+pub struct ProfilesPage {
+    link: ComponentLink<Self>,
+    props: ProfilesPageProps,
+    form: ConnectionProfile,
+    editing: Option<String>,
+    form_open: bool,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ProfilesPageProps {
+    #[prop_or_default]
+    pub store: Rc<RefCell<AppStore>>,
+}
+
+pub enum ProfileField {
+    Name,
+    Host,
+    Port,
+    User,
+    Password,
+    Database,
+    Path,
+    MinConnections,
+    MaxConnections,
+    IdleTimeout,
+    MaxExecutionSecs,
+    MaxRows,
+    MaxResponseBytes,
+}
+
+pub enum ProfilesPageMsg {
+    NewProfile,
+    EditProfile(usize),
+    CloseForm,
+    SelectDriver(DriverKind),
+    UpdateField(ProfileField, String),
+    ToggleHealthCheck,
+    SaveProfile,
+    TestProfile,
+    DeleteProfile(String),
+    Connect(String),
+}
+
+impl Component for ProfilesPage {
+    type Message = ProfilesPageMsg;
+    type Properties = ProfilesPageProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            props,
+            form: ConnectionProfile::default(),
+            editing: None,
+            form_open: false,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> yew::ShouldRender {
+        match msg {
+            ProfilesPageMsg::NewProfile => {
+                self.form = ConnectionProfile::default();
+                self.editing = None;
+                self.form_open = true;
+            }
+            ProfilesPageMsg::EditProfile(index) => {
+                if let Some(profile) = self.props.store.borrow().profiles.get(index) {
+                    self.form = profile.clone();
+                    self.editing = Some(profile.name.clone());
+                    self.form_open = true;
+                }
+            }
+            ProfilesPageMsg::CloseForm => {
+                self.form_open = false;
+                self.editing = None;
+            }
+            ProfilesPageMsg::SelectDriver(driver) => {
+                // keep a customised port, otherwise follow the backend's default
+                if Some(self.form.port) == self.form.driver.default_port() {
+                    if let Some(port) = driver.default_port() {
+                        self.form.port = port;
+                    }
+                }
+                self.form.driver = driver;
+            }
+            ProfilesPageMsg::UpdateField(field, value) => match field {
+                ProfileField::Name => self.form.name = value,
+                ProfileField::Host => self.form.host = value,
+                ProfileField::Port => {
+                    if let Ok(port) = value.parse() {
+                        self.form.port = port;
+                    }
+                }
+                ProfileField::User => self.form.user = value,
+                ProfileField::Password => self.form.password = value,
+                ProfileField::Database => {
+                    self.form.database = Some(value).filter(|db| !db.trim().is_empty())
+                }
+                ProfileField::Path => {
+                    self.form.path = Some(value).filter(|path| !path.trim().is_empty())
+                }
+                ProfileField::MinConnections => {
+                    if let Ok(min) = value.parse() {
+                        self.form.pool.min_connections = min;
+                    }
+                }
+                ProfileField::MaxConnections => {
+                    if let Ok(max) = value.parse() {
+                        self.form.pool.max_connections = max;
+                    }
+                }
+                ProfileField::IdleTimeout => {
+                    if let Ok(seconds) = value.parse() {
+                        self.form.pool.idle_timeout_secs = seconds;
+                    }
+                }
+                // left empty, a limit is off
+                ProfileField::MaxExecutionSecs => {
+                    self.form.limits.max_execution_secs = value.parse().ok()
+                }
+                ProfileField::MaxRows => self.form.limits.max_rows = value.parse().ok(),
+                ProfileField::MaxResponseBytes => {
+                    self.form.limits.max_response_bytes = value.parse().ok()
+                }
+            },
+            ProfilesPageMsg::ToggleHealthCheck => {
+                self.form.pool.health_check = !self.form.pool.health_check;
+            }
+            ProfilesPageMsg::SaveProfile => {
+                if let Some(original_name) = &self.editing {
+                    if !self.confirm_rollback(original_name, "Saving the active connection rolls back its open transaction, continue?") {
+                        return false;
+                    }
+                }
+                let request = match self.editing.clone() {
+                    Some(original_name) => Request::UpdateProfile(UpdateProfileRequest {
+                        original_name,
+                        profile: self.form.clone(),
+                    }),
+                    None => Request::CreateProfile(self.form.clone()),
+                };
+                self.props.store.borrow().socket_send(request);
+                self.form_open = false;
+                self.editing = None;
+            }
+            ProfilesPageMsg::TestProfile => {
+                self.props.store.borrow_mut().profile_test = None;
+                self.props
+                    .store
+                    .borrow()
+                    .socket_send(Request::TestProfile(self.form.clone()));
+            }
+            ProfilesPageMsg::DeleteProfile(name) => {
+                if !self.confirm_rollback(&name, "Deleting the active connection rolls back its open transaction, continue?") {
+                    return false;
+                }
+                self.props
+                    .store
+                    .borrow()
+                    .socket_send(Request::DeleteProfile(ProfileName { name }));
+            }
+            ProfilesPageMsg::Connect(name) => {
+                let confirmed = !self.props.store.borrow().transaction.open
+                    || window()
+                        .confirm_with_message("Connecting rolls back the open transaction, continue?")
+                        .unwrap_or(false);
+                if !confirmed {
+                    return false;
+                }
+                self.props
+                    .store
+                    .borrow()
+                    .socket_send(Request::Connect(ProfileName { name }));
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="rows rows-fill scrollable">
+                <div class="row">
+                    <div class="columns">
+                        <div class="column">
+                            <h1 class="title is-4">{"Connections"}</h1>
+                        </div>
+                        <div class="column is-narrow">
+                            <button
+                                class="button is-primary"
+                                onclick=self.link.callback(|_| ProfilesPageMsg::NewProfile)
+                            >
+                                <i class="fas fa-plus"/>
+                            </button>
+                        </div>
+                    </div>
+                </div>
+                <div class="row">
+                    {self.view_profiles()}
+                </div>
+                {self.view_form()}
+            </div>
+        }
+    }
+}
+
+impl ProfilesPage {
+    // Saving or deleting the active profile closes the api's connection for it,
+    // which rolls back an open transaction like connecting elsewhere does
+    fn confirm_rollback(&self, name: &str, message: &str) -> bool {
+        let mut store = self.props.store.borrow_mut();
+        if store.active_profile.as_deref() != Some(name) || !store.transaction.open {
+            return true;
+        }
+
+        let confirmed = window().confirm_with_message(message).unwrap_or(false);
+        if confirmed {
+            let autocommit = store.transaction.autocommit;
+            store.set_transaction(TransactionStatus { autocommit, open: false });
+        }
+        confirmed
+    }
+
+    fn view_profiles(&self) -> Html {
+        let store = self.props.store.borrow();
+
+        let rows = store
+            .profiles
+            .iter()
+            .enumerate()
+            .map(|(index, profile)| {
+                let active = store.active_profile.as_ref() == Some(&profile.name);
+                let connect_name = profile.name.clone();
+                let delete_name = profile.name.clone();
+
+                html! {
+                    <tr class=classes!(active.then(|| "is-selected"))>
+                        <td>{&profile.name}</td>
+                        <td>
+                            <span class="tag is-light mr-2">{profile.driver}</span>
+                            {match profile.driver.is_file_based() {
+                                true => profile.path.clone().unwrap_or_default(),
+                                false => format!("{}@{}:{}", profile.user, profile.host, profile.port),
+                            }}
+                        </td>
+                        <td>{profile.database.clone().unwrap_or_default()}</td>
+                        <td class="has-text-right">
+                            <div class="buttons is-right">
+                                <button
+                                    class="button is-small is-success"
+                                    onclick=self.link.callback(move |_| ProfilesPageMsg::Connect(connect_name.clone()))
+                                >
+                                    <i class="fas fa-plug"/>
+                                </button>
+                                <button
+                                    class="button is-small"
+                                    onclick=self.link.callback(move |_| ProfilesPageMsg::EditProfile(index))
+                                >
+                                    <i class="fas fa-edit"/>
+                                </button>
+                                <button
+                                    class="button is-small is-danger"
+                                    onclick=self.link.callback(move |_| ProfilesPageMsg::DeleteProfile(delete_name.clone()))
+                                >
+                                    <i class="fas fa-trash"/>
+                                </button>
+                            </div>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <table class="table is-fullwidth is-hoverable">
+                <thead>
+                    <tr>
+                        <th>{"Name"}</th>
+                        <th>{"Server"}</th>
+                        <th>{"Database"}</th>
+                        <th/>
+                    </tr>
+                </thead>
+                <tbody>
+                    {rows}
+                </tbody>
+            </table>
+        }
+    }
+
+    fn view_form(&self) -> Html {
+        if !self.form_open {
+            return Html::default();
+        }
+
+        let title = match &self.editing {
+            Some(name) => format!("Edit {}", name),
+            None => String::from("New Connection"),
+        };
+
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <div class="modal-card-head">
+                        <div class="modal-card-title">{title}</div>
+                    </div>
+                    <div class="modal-card-body">
+                        {self.view_input("Name", "text", self.form.name.clone(), |value| ProfilesPageMsg::UpdateField(ProfileField::Name, value))}
+                        {self.view_driver_select()}
+                        {self.view_connection_fields()}
+                        {self.view_pool_settings()}
+                        {self.view_query_limits()}
+                        {self.view_test_result()}
+                    </div>
+                    <div class="modal-card-foot">
+                        <button
+                            class="button is-success"
+                            onclick=self.link.callback(|_| ProfilesPageMsg::SaveProfile)
+                        >
+                            {"Save"}
+                        </button>
+                        <button
+                            class="button is-info"
+                            onclick=self.link.callback(|_| ProfilesPageMsg::TestProfile)
+                        >
+                            {"Test"}
+                        </button>
+                        <button
+                            class="button"
+                            onclick=self.link.callback(|_| ProfilesPageMsg::CloseForm)
+                        >
+                            {"Cancel"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_connection_fields(&self) -> Html {
+        if self.form.driver.is_file_based() {
+            return self.view_input("Database File", "text", self.form.path.clone().unwrap_or_default(), |value| ProfilesPageMsg::UpdateField(ProfileField::Path, value));
+        }
+
+        html! {
+            <>
+                {self.view_input("Host", "text", self.form.host.clone(), |value| ProfilesPageMsg::UpdateField(ProfileField::Host, value))}
+                {self.view_input("Port", "number", self.form.port.to_string(), |value| ProfilesPageMsg::UpdateField(ProfileField::Port, value))}
+                {self.view_input("User", "text", self.form.user.clone(), |value| ProfilesPageMsg::UpdateField(ProfileField::User, value))}
+                {self.view_password()}
+                {self.view_input("Database", "text", self.form.database.clone().unwrap_or_default(), |value| ProfilesPageMsg::UpdateField(ProfileField::Database, value))}
+            </>
+        }
+    }
+
+    fn view_input(
+        &self,
+        label: &str,
+        input_type: &str,
+        value: String,
+        on_input: fn(String) -> ProfilesPageMsg,
+    ) -> Html {
+        html! {
+            <div class="field">
+                <label class="label is-small">{label}</label>
+                <div class="control">
+                    <input
+                        class="input is-small"
+                        type=input_type.to_string()
+                        value=value
+                        oninput=self.link.callback(move |input: InputData| on_input(input.value))
+                    />
+                </div>
+            </div>
+        }
+    }
+
+    fn view_password(&self) -> Html {
+        // saved passwords are never sent back, leaving the field blank keeps the stored one
+        let placeholder = if self.editing.is_some() { "Unchanged" } else { "" };
+
+        html! {
+            <div class="field">
+                <label class="label is-small">{"Password"}</label>
+                <div class="control">
+                    <input
+                        class="input is-small"
+                        type="password"
+                        placeholder=placeholder
+                        value=self.form.password.clone()
+                        oninput=self.link.callback(|input: InputData| ProfilesPageMsg::UpdateField(ProfileField::Password, input.value))
+                    />
+                </div>
+            </div>
+        }
+    }
+
+    fn view_driver_select(&self) -> Html {
+        let options = DriverKind::iter()
+            .map(|driver| {
+                html! {
+                    <option value=driver.to_string() selected=driver == self.form.driver>
+                        {driver}
+                    </option>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="field">
+                <label class="label is-small">{"Driver"}</label>
+                <div class="control">
+                    <div class="select is-small">
+                        <select
+                            onchange=self.link.batch_callback(|change: ChangeData| match change {
+                                ChangeData::Select(select) => select
+                                    .value()
+                                    .parse()
+                                    .ok()
+                                    .map(ProfilesPageMsg::SelectDriver),
+                                _ => None,
+                            })
+                        >
+                            {options}
+                        </select>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_pool_settings(&self) -> Html {
+        let pool = &self.form.pool;
+
+        html! {
+            <>
+                <div class="columns">
+                    <div class="column">
+                        {self.view_input("Min Connections", "number", pool.min_connections.to_string(), |value| ProfilesPageMsg::UpdateField(ProfileField::MinConnections, value))}
+                    </div>
+                    <div class="column">
+                        {self.view_input("Max Connections", "number", pool.max_connections.to_string(), |value| ProfilesPageMsg::UpdateField(ProfileField::MaxConnections, value))}
+                    </div>
+                    <div class="column">
+                        {self.view_input("Idle Timeout (s)", "number", pool.idle_timeout_secs.to_string(), |value| ProfilesPageMsg::UpdateField(ProfileField::IdleTimeout, value))}
+                    </div>
+                </div>
+                <div class="field">
+                    <label class="checkbox is-size-7">
+                        <input
+                            type="checkbox"
+                            checked=pool.health_check
+                            onclick=self.link.callback(|_| ProfilesPageMsg::ToggleHealthCheck)
+                        />
+                        {" Check connection health before use"}
+                    </label>
+                </div>
+            </>
+        }
+    }
+
+    fn view_query_limits(&self) -> Html {
+        let limits = &self.form.limits;
+        let value = |limit: Option<String>| limit.unwrap_or_default();
+
+        html! {
+            <>
+                <p class="is-size-7 has-text-grey mb-2">{"Query limits, leave empty for no limit"}</p>
+                <div class="columns">
+                    <div class="column">
+                        {self.view_input("Max Execution Time (s)", "number", value(limits.max_execution_secs.map(|secs| secs.to_string())), |value| ProfilesPageMsg::UpdateField(ProfileField::MaxExecutionSecs, value))}
+                    </div>
+                    <div class="column">
+                        {self.view_input("Max Rows", "number", value(limits.max_rows.map(|rows| rows.to_string())), |value| ProfilesPageMsg::UpdateField(ProfileField::MaxRows, value))}
+                    </div>
+                    <div class="column">
+                        {self.view_input("Max Response (bytes)", "number", value(limits.max_response_bytes.map(|bytes| bytes.to_string())), |value| ProfilesPageMsg::UpdateField(ProfileField::MaxResponseBytes, value))}
+                    </div>
+                </div>
+            </>
+        }
+    }
+
+    fn view_test_result(&self) -> Html {
+        match &self.props.store.borrow().profile_test {
+            Some(result) if result.name == self.form.name => html! {
+                <div class=classes!("notification", "is-light", if result.success { "is-success" } else { "is-danger" })>
+                    {&result.message}
+                </div>
+            },
+            _ => Html::default(),
+        }
+    }
+}
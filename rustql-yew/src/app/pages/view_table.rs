@@ -1,7 +1,9 @@
 use std::{cell::RefCell, rc::Rc};
-use yew::{Callback, Component, ComponentLink, Html, MouseEvent, NodeRef, Properties, classes, html};
+use rustql_types::{AlterIndexRequest, DriverKind, ErrorCategory, FilterOperator, ForeignKey, IndexChange, IndexColumn, IndexDefinition, IndexDescription, Request, StatementResult, StatementWarning, TableData, TableFilter, TableSort, Value, alter_index_ddl};
+use strum::IntoEnumIterator;
+use yew::{Callback, ChangeData, Component, ComponentLink, Html, InputData, MouseEvent, NodeRef, Properties, classes, html};
 
-use crate::app::{components::query_editor::{QueryEditor, QueryEditorMsg}, store::AppStore, structs::page_view_link::CustomLink};
+use crate::app::{components::query_editor::{QueryEditor, QueryEditorMsg}, helpers::{components::view_api_error, functions::format_count}, store::AppStore, structs::page_view_link::CustomLink};
 
 #[derive(Clone)]
 pub struct ViewTable {
@@ -9,10 +11,14 @@ pub struct ViewTable {
     editor_link: CustomLink<QueryEditor>,
     props: WelcomePageProps,
     query_box_open: bool,
+    filter_box_open: bool,
+    filter_form: TableFilter,
     dragging: bool,
     query_box_height: i32,
     start_position: (i32, i32),
     splitter: NodeRef,
+    // open while creating or dropping an index, its DDL is previewed before it is sent
+    index_change: Option<IndexChange>,
 
     // Event listeners
 
@@ -34,6 +40,37 @@ pub enum ViewTableMsg {
     SetDragging(MouseEvent, bool),
     Drag(MouseEvent),
     AppendToQuery(String),
+    LoadPage(usize),
+    SetPageSize(usize),
+    SortBy(String),
+    ClearSort,
+    ToggleFilterBoxOpen,
+    FilterColumn(String),
+    FilterOperator(FilterOperator),
+    FilterValue(String),
+    AddFilter,
+    RemoveFilter(usize),
+    CancelQuery,
+    SelectResult(usize),
+    ToggleContinueOnError,
+    ToggleAutocommit,
+    SelectQueryTarget(usize),
+    ToggleStructure,
+    EndTransaction(bool),
+    NewIndex,
+    DropIndex(String, bool),
+    IndexName(String),
+    IndexType(String),
+    ToggleIndexUnique,
+    ToggleIndexColumn(String),
+    ToggleIndexColumnDesc(usize),
+    IndexPrefix(usize, String),
+    RunIndexChange,
+    CloseIndexDialog,
+    FollowForeignKey(usize, usize),
+    ShowReferences(usize),
+    OpenReferences(usize),
+    CloseReferences,
 }
 
 impl Component for ViewTable {
@@ -52,10 +89,18 @@ impl Component for ViewTable {
             editor_link: CustomLink::new(),
             props,
             query_box_open: false,
+            filter_box_open: false,
+            filter_form: TableFilter {
+                column: String::new(),
+                operator: FilterOperator::default(),
+                value: String::new(),
+                binary: false,
+            },
             dragging: false,
             start_position: (0,0),
             query_box_height: 100,
             splitter: NodeRef::default(),
+            index_change: None,
             // event listeners
             drag,
             dragging_false,
@@ -74,6 +119,237 @@ impl Component for ViewTable {
                 self.editor_link.send_message(QueryEditorMsg::AppendText(text.to_string(), None, None));
                 false
             },
+            ViewTableMsg::CancelQuery => {
+                self.props.store.borrow().cancel_query();
+                false
+            },
+            ViewTableMsg::SelectResult(index) => {
+                self.props.store.borrow_mut().selected_result = index;
+                true
+            },
+            ViewTableMsg::ToggleContinueOnError => {
+                let mut store = self.props.store.borrow_mut();
+                store.continue_on_error = !store.continue_on_error;
+                true
+            },
+            ViewTableMsg::ToggleAutocommit => {
+                let store = self.props.store.borrow();
+                store.socket_send(Request::SetAutocommit(!store.transaction.autocommit));
+                false
+            },
+            ViewTableMsg::ToggleStructure => {
+                let mut store = self.props.store.borrow_mut();
+                store.show_structure = !store.show_structure;
+                if store.show_structure && store.table_description.is_none() {
+                    store.describe_table();
+                }
+                true
+            },
+            ViewTableMsg::NewIndex => {
+                self.index_change = Some(IndexChange::Create(IndexDefinition::default()));
+                true
+            },
+            ViewTableMsg::DropIndex(name, primary) => {
+                self.index_change = Some(IndexChange::Drop { name, primary });
+                true
+            },
+            ViewTableMsg::IndexName(name) => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    index.name = name;
+                }
+                true
+            },
+            ViewTableMsg::IndexType(index_type) => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    index.index_type = Some(index_type).filter(|index_type| !index_type.is_empty());
+                }
+                true
+            },
+            ViewTableMsg::ToggleIndexUnique => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    index.unique = !index.unique;
+                }
+                true
+            },
+            // columns join the key in the order they are picked
+            ViewTableMsg::ToggleIndexColumn(name) => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    match index.columns.iter().position(|column| column.name == name) {
+                        Some(position) => { index.columns.remove(position); },
+                        None => index.columns.push(IndexColumn { name, ..IndexColumn::default() }),
+                    }
+                }
+                true
+            },
+            ViewTableMsg::ToggleIndexColumnDesc(position) => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    if let Some(column) = index.columns.get_mut(position) {
+                        column.descending = !column.descending;
+                    }
+                }
+                true
+            },
+            ViewTableMsg::IndexPrefix(position, length) => {
+                if let Some(IndexChange::Create(index)) = self.index_change.as_mut() {
+                    if let Some(column) = index.columns.get_mut(position) {
+                        column.sub_part = length.trim().parse().ok().filter(|length| *length > 0);
+                    }
+                }
+                true
+            },
+            ViewTableMsg::RunIndexChange => {
+                if let Some(request) = self.index_request() {
+                    self.props.store.borrow().socket_send(Request::AlterIndex(request));
+                }
+                self.index_change = None;
+                true
+            },
+            ViewTableMsg::CloseIndexDialog => {
+                self.index_change = None;
+                true
+            },
+            // opens the parent table at the row the key of the `row`th row points to
+            ViewTableMsg::FollowForeignKey(key, row) => {
+                let mut store = self.props.store.borrow_mut();
+                let target = store
+                    .table_description
+                    .as_ref()
+                    .and_then(|description| description.foreign_keys.get(key))
+                    .and_then(|key| {
+                        let filters = row_filters(store.table_data.as_ref()?, row, &key.columns, &key.referenced_columns)?;
+                        Some((key.referenced_table.clone(), filters))
+                    });
+
+                if let Some((table, filters)) = target {
+                    store.open_table(table, filters);
+                }
+                true
+            },
+            ViewTableMsg::ShowReferences(row) => {
+                let mut store = self.props.store.borrow_mut();
+                store.row_references = None;
+                store.load_references(row);
+                true
+            },
+            // the referencing rows all hold the same key, the first one's values filter the table
+            ViewTableMsg::OpenReferences(index) => {
+                let mut store = self.props.store.borrow_mut();
+                let target = store
+                    .row_references
+                    .as_ref()
+                    .and_then(|references| references.get(index))
+                    .and_then(|references| {
+                        let key = &references.foreign_key;
+                        let filters = row_filters(&references.data, 0, &key.columns, &key.columns)?;
+                        Some((key.table.clone(), filters))
+                    });
+
+                if let Some((table, filters)) = target {
+                    store.open_table(table, filters);
+                }
+                true
+            },
+            ViewTableMsg::CloseReferences => {
+                self.props.store.borrow_mut().row_references = None;
+                true
+            },
+            ViewTableMsg::SelectQueryTarget(index) => {
+                let mut store = self.props.store.borrow_mut();
+                store.editor_target = store.query_targets().get(index).cloned();
+                true
+            },
+            ViewTableMsg::EndTransaction(commit) => {
+                let request = if commit { Request::Commit } else { Request::Rollback };
+                self.props.store.borrow().socket_send(request);
+                false
+            },
+            ViewTableMsg::LoadPage(offset) => {
+                self.props.store.borrow().load_page(offset);
+                false
+            },
+            ViewTableMsg::SetPageSize(page_size) => {
+                let mut store = self.props.store.borrow_mut();
+                store.page_size = page_size;
+
+                // stay on the page holding the first row shown
+                let first_row = store
+                    .table_data
+                    .as_ref()
+                    .and_then(|data| data.page)
+                    .map_or(0, |page| page.offset);
+                store.load_page(first_row / page_size * page_size);
+                false
+            },
+            ViewTableMsg::SortBy(column) => {
+                let mut store = self.props.store.borrow_mut();
+
+                // ascending, then descending, then back to the table's own order
+                store.table_sort = match store.table_sort.take() {
+                    Some(sort) if sort.column == column && !sort.descending => Some(TableSort {
+                        column,
+                        descending: true,
+                    }),
+                    Some(sort) if sort.column == column => None,
+                    _ => Some(TableSort {
+                        column,
+                        descending: false,
+                    }),
+                };
+                store.load_page(0);
+                false
+            },
+            ViewTableMsg::ClearSort => {
+                let mut store = self.props.store.borrow_mut();
+                store.table_sort = None;
+                store.load_page(0);
+                false
+            },
+            ViewTableMsg::ToggleFilterBoxOpen => {
+                self.filter_box_open = !self.filter_box_open;
+                true
+            },
+            ViewTableMsg::FilterColumn(column) => {
+                self.filter_form.column = column;
+                true
+            },
+            ViewTableMsg::FilterOperator(operator) => {
+                self.filter_form.operator = operator;
+                true
+            },
+            ViewTableMsg::FilterValue(value) => {
+                self.filter_form.value = value;
+                false
+            },
+            ViewTableMsg::AddFilter => {
+                let columns = self.filter_columns();
+                let mut filter = self.filter_form.clone();
+
+                // the select shows the first column until another one is picked
+                if !columns.contains(&filter.column) {
+                    match columns.into_iter().next() {
+                        Some(column) => filter.column = column,
+                        None => return false,
+                    }
+                }
+                if !filter.operator.takes_value() {
+                    filter.value.clear();
+                }
+
+                let mut store = self.props.store.borrow_mut();
+                store.table_filters.push(filter);
+                store.load_page(0);
+
+                self.filter_form.value.clear();
+                true
+            },
+            ViewTableMsg::RemoveFilter(index) => {
+                let mut store = self.props.store.borrow_mut();
+                if index < store.table_filters.len() {
+                    store.table_filters.remove(index);
+                    store.load_page(0);
+                }
+                false
+            },
             ViewTableMsg::ToggleQueryBoxOpen => { 
                 self.query_box_open = !self.query_box_open;
                 self.editor_link.send_message(QueryEditorMsg::Update(self.query_box_height));
@@ -126,11 +402,30 @@ impl Component for ViewTable {
 }
 
 impl ViewTable {
+    fn view_schema(&self) -> Html {
+        match self.props.store.borrow().selected_schema.clone() {
+            Some(schema) => html! {
+                <>
+                    <span class="icon-text">
+                        <span class="icon">
+                            <i class="fas fa-folder"/>
+                        </span>
+                        <span>{schema}</span>
+                    </span>
+                    <br/>
+                </>
+            },
+            None => Html::default(),
+        }
+    }
+
     fn view_rows(&self) -> Html {
 
         let db = self.props.store.borrow().get_db();
         let table = self.props.store.borrow().get_table();
 
+        let show_structure = self.props.store.borrow().show_structure;
+
         let append_db = self.link.callback(move |_| ViewTableMsg::AppendToQuery(db.clone().unwrap()));
         let append_table = self.link.callback(move |_| ViewTableMsg::AppendToQuery(table.clone().unwrap()));
 
@@ -153,6 +448,7 @@ impl ViewTable {
                                     </span>
                                 </span>
                                 <br/>
+                                {self.view_schema()}
                                 <span class="icon-text">
                                     <span class="icon">
                                         <i class="fas fa-table"/>
@@ -171,23 +467,427 @@ impl ViewTable {
                             </div>
                         </div>
                     </div>
+                    {self.view_filter_box()}
+                    {self.view_active_filters()}
+                    {self.view_result_tabs()}
                     <div class="row view-table mt-2 fill hide-overflow">
-                        {self.view_table()}
+                        {if show_structure { self.view_structure() } else { self.view_table() }}
                     </div>
+                    {(!show_structure).then(|| self.view_references()).unwrap_or_default()}
+                    {(!show_structure).then(|| self.view_pagination()).unwrap_or_default()}
+                    {self.view_query_progress()}
                     {self.view_query_box()}
+                    {self.view_index_dialog()}
                 </>
             }
         } else { Html::default() }
     }
 
+    // The selected table's columns, a click on a name adds it to the query
+    fn view_structure(&self) -> Html {
+        let store = self.props.store.borrow();
+        let description = match store.table_description.as_ref() {
+            Some(description) => description,
+            None => return html! {
+                <progress class="progress is-small is-info" max="100"/>
+            },
+        };
+
+        let rows = description
+            .columns
+            .iter()
+            .map(|column| {
+                let name = column.name.clone();
+                let key_icon = match column.key.as_str() {
+                    "PRI" => Some("has-text-warning"),
+                    "UNI" => Some("has-text-info"),
+                    "MUL" => Some("has-text-grey-light"),
+                    _ => None,
+                };
+
+                html! {
+                    <tr>
+                        <td>
+                            <span
+                                class="is-clickable"
+                                onclick=self.link.callback(move |_| ViewTableMsg::AppendToQuery(name.clone()))
+                                onmousedown=self.link.callback(ViewTableMsg::PreventDefault)
+                                onmouseup=self.link.callback(ViewTableMsg::PreventDefault)
+                            >
+                                <b>{&column.name}</b>
+                            </span>
+                            {key_icon.map(|color| html! {
+                                <span class=classes!("icon", "is-small", "ml-1", color)>
+                                    <i class="fas fa-key"/>
+                                </span>
+                            }).unwrap_or_default()}
+                        </td>
+                        <td class="is-family-monospace">{&column.sql_type}</td>
+                        <td>{if column.nullable { "YES" } else { "NO" }}</td>
+                        <td>
+                            {match &column.default {
+                                Some(default) => html! { <span class="is-family-monospace">{default}</span> },
+                                None => html! { <i class="has-text-grey-light">{"NULL"}</i> },
+                            }}
+                        </td>
+                        <td>{&column.key}</td>
+                        <td>{&column.extra}</td>
+                        <td>{column.collation.clone().unwrap_or_default()}</td>
+                        <td>{&column.comment}</td>
+                    </tr>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="scrollable-all fill">
+                <table class="table is-bordered is-striped is-narrow is-hoverable is-fullwidth">
+                    <thead>
+                        <tr>
+                            <th>{"Column"}</th>
+                            <th>{"Type"}</th>
+                            <th>{"Nullable"}</th>
+                            <th>{"Default"}</th>
+                            <th>{"Key"}</th>
+                            <th>{"Extra"}</th>
+                            <th>{"Collation"}</th>
+                            <th>{"Comment"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {rows}
+                    </tbody>
+                </table>
+                {self.view_indexes(&description.indexes)}
+            </div>
+        }
+    }
+
+    fn view_indexes(&self, indexes: &[IndexDescription]) -> Html {
+        let rows = indexes
+            .iter()
+            .map(|index| {
+                let name = index.name.clone();
+                let primary = index.primary;
+                let columns = index
+                    .columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                html! {
+                    <tr class=classes!((!index.visible).then(|| "has-text-grey-light"))>
+                        <td>
+                            <b>{&index.name}</b>
+                            {if index.primary {
+                                html! {
+                                    <span class="icon is-small ml-1 has-text-warning">
+                                        <i class="fas fa-key"/>
+                                    </span>
+                                }
+                            } else { Html::default() }}
+                        </td>
+                        <td class="is-family-monospace">{columns}</td>
+                        <td>{if index.unique { "YES" } else { "NO" }}</td>
+                        <td>{&index.index_type}</td>
+                        <td>{index.cardinality.map(|count| format_count(count as usize)).unwrap_or_default()}</td>
+                        <td>{if index.visible { "YES" } else { "NO" }}</td>
+                        <td class="has-text-centered">
+                            <button
+                                class="button is-small is-danger is-light"
+                                title="Drop index"
+                                onclick=self.link.callback(move |_| ViewTableMsg::DropIndex(name.clone(), primary))
+                            >
+                                <i class="fas fa-trash"/>
+                            </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <>
+                <div class="level mt-4 mb-2">
+                    <div class="level-left">
+                        <b>{"Indexes"}</b>
+                    </div>
+                    <div class="level-right">
+                        <button class="button is-small is-info" onclick=self.link.callback(|_| ViewTableMsg::NewIndex)>
+                            <span class="icon is-small">
+                                <i class="fas fa-plus"/>
+                            </span>
+                            <span>{"New index"}</span>
+                        </button>
+                    </div>
+                </div>
+                <table class="table is-bordered is-striped is-narrow is-hoverable is-fullwidth">
+                    <thead>
+                        <tr>
+                            <th>{"Index"}</th>
+                            <th>{"Columns"}</th>
+                            <th>{"Unique"}</th>
+                            <th>{"Type"}</th>
+                            <th>{"Cardinality"}</th>
+                            <th>{"Visible"}</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {rows}
+                    </tbody>
+                </table>
+            </>
+        }
+    }
+
+    // The change being edited for the selected table
+    fn index_request(&self) -> Option<AlterIndexRequest> {
+        let store = self.props.store.borrow();
+        Some(AlterIndexRequest {
+            db: store.get_db()?,
+            schema: store.selected_schema.clone(),
+            table: store.get_table()?,
+            change: self.index_change.clone()?,
+        })
+    }
+
+    fn view_index_dialog(&self) -> Html {
+        let request = match self.index_request() {
+            Some(request) => request,
+            None => return Html::default(),
+        };
+        let driver = self.props.store.borrow().active_driver();
+        let ddl = alter_index_ddl(driver, &request);
+
+        let (title, form, button, ready) = match &request.change {
+            IndexChange::Create(index) => (
+                "New Index",
+                self.view_index_form(driver, index),
+                "is-success",
+                !index.columns.is_empty(),
+            ),
+            IndexChange::Drop { .. } => ("Drop Index", Html::default(), "is-danger", true),
+        };
+
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <div class="modal-card-head">
+                        <div class="modal-card-title">{title}</div>
+                    </div>
+                    <div class="modal-card-body">
+                        {form}
+                        <label class="label is-small">{"Statement"}</label>
+                        <pre class="is-family-monospace">{ddl}</pre>
+                    </div>
+                    <div class="modal-card-foot">
+                        <button
+                            class=classes!("button", button)
+                            disabled=!ready
+                            onclick=self.link.callback(|_| ViewTableMsg::RunIndexChange)
+                        >
+                            {"Run"}
+                        </button>
+                        <button
+                            class="button"
+                            onclick=self.link.callback(|_| ViewTableMsg::CloseIndexDialog)
+                        >
+                            {"Cancel"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_index_form(&self, driver: DriverKind, index: &IndexDefinition) -> Html {
+        let store = self.props.store.borrow();
+        let table_columns = store
+            .table_description
+            .as_ref()
+            .map(|description| description.columns.as_slice())
+            .unwrap_or_default();
+
+        let index_types: &[&str] = match driver {
+            DriverKind::MySql => &["BTREE", "HASH", "FULLTEXT", "SPATIAL"],
+            DriverKind::Postgres => &["btree", "hash", "gist", "gin", "brin"],
+            DriverKind::Sqlite => &[],
+        };
+        let selected_type = index.index_type.clone().unwrap_or_default();
+        let type_options = index_types
+            .iter()
+            .map(|index_type| html! {
+                <option value=index_type.to_string() selected=*index_type == selected_type>
+                    {index_type}
+                </option>
+            })
+            .collect::<Html>();
+
+        let columns = table_columns
+            .iter()
+            .map(|table_column| {
+                let name = table_column.name.clone();
+                let position = index.columns.iter().position(|column| column.name == name);
+                let key_part = position.map(|position| (position, &index.columns[position]));
+
+                html! {
+                    <div class="field has-addons">
+                        <div class="control is-expanded">
+                            <label class="checkbox">
+                                <input
+                                    type="checkbox"
+                                    checked=position.is_some()
+                                    onclick=self.link.callback(move |_| ViewTableMsg::ToggleIndexColumn(name.clone()))
+                                />
+                                <span class="ml-2">{&table_column.name}</span>
+                                {position.map(|position| html! {
+                                    <span class="tag is-info is-light ml-2">{position + 1}</span>
+                                }).unwrap_or_default()}
+                            </label>
+                        </div>
+                        {key_part.map(|(position, column)| html! {
+                            <>
+                                {if driver == DriverKind::MySql {
+                                    html! {
+                                        <div class="control">
+                                            <input
+                                                class="input is-small"
+                                                type="number"
+                                                min="1"
+                                                placeholder="Prefix"
+                                                value=column.sub_part.map(|length| length.to_string()).unwrap_or_default()
+                                                oninput=self.link.callback(move |input: InputData| ViewTableMsg::IndexPrefix(position, input.value))
+                                            />
+                                        </div>
+                                    }
+                                } else { Html::default() }}
+                                <div class="control">
+                                    <button
+                                        class=classes!("button", "is-small", column.descending.then(|| "is-info"))
+                                        title="Descending"
+                                        onclick=self.link.callback(move |_| ViewTableMsg::ToggleIndexColumnDesc(position))
+                                    >
+                                        {"DESC"}
+                                    </button>
+                                </div>
+                            </>
+                        }).unwrap_or_default()}
+                    </div>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <>
+                <div class="field">
+                    <label class="label is-small">{"Name"}</label>
+                    <div class="control">
+                        <input
+                            class="input is-small"
+                            type="text"
+                            value=index.name.clone()
+                            placeholder=if driver == DriverKind::Sqlite { "Generated from the table and columns" } else { "Generated by the server" }
+                            oninput=self.link.callback(|input: InputData| ViewTableMsg::IndexName(input.value))
+                        />
+                    </div>
+                </div>
+                <div class="field is-grouped">
+                    <div class="control">
+                        <label class="checkbox">
+                            <input
+                                type="checkbox"
+                                checked=index.unique
+                                onclick=self.link.callback(|_| ViewTableMsg::ToggleIndexUnique)
+                            />
+                            <span class="ml-2">{"Unique"}</span>
+                        </label>
+                    </div>
+                    {if index_types.is_empty() { Html::default() } else {
+                        html! {
+                            <div class="control">
+                                <div class="select is-small">
+                                    <select
+                                        onchange=self.link.batch_callback(|change: ChangeData| match change {
+                                            ChangeData::Select(select) => Some(ViewTableMsg::IndexType(select.value())),
+                                            _ => None,
+                                        })
+                                    >
+                                        <option value="" selected=selected_type.is_empty()>{"Default type"}</option>
+                                        {type_options}
+                                    </select>
+                                </div>
+                            </div>
+                        }
+                    }}
+                </div>
+                <label class="label is-small">{"Columns"}</label>
+                {columns}
+            </>
+        }
+    }
+
     fn view_table(&self) -> Html {
-        match &self.props.store.borrow().table_data {
+        let store = self.props.store.borrow();
+        match store.shown_data() {
+            // statements without a result set, e.g. an UPDATE
+            Some(data) if data.table_fields.is_empty() => match store.shown_result() {
+                Some(result) if result.error.is_none() => view_statement_summary(result),
+                _ => Html::default(),
+            },
             Some(data) => {
+                // foreign keys only link the rows of a table page
+                let description = store
+                    .table_description
+                    .as_ref()
+                    .filter(|_| data.page.is_some());
+                let foreign_keys = description
+                    .map(|description| description.foreign_keys.as_slice())
+                    .unwrap_or_default();
+                let referenced = description
+                    .map(|description| !description.referenced_by.is_empty())
+                    .unwrap_or_default();
+                // the key each column is part of
+                let column_keys = data
+                    .table_fields
+                    .iter()
+                    .map(|field| foreign_keys.iter().position(|key| key.columns.contains(&field.name)))
+                    .collect::<Vec<Option<usize>>>();
+
                 let titles: Html = data
                     .table_fields
                     .iter()
                     .map(|field| {
-                        html! { <th class="is-size-6">{&field.name }</th> }
+                        // query results come back whole, only table pages are sorted by the api
+                        let sortable = data.page.is_some();
+                        let sort_icon = match store.table_sort.as_ref() {
+                            Some(sort) if sort.column == field.name && sort.descending => "fa-sort-down",
+                            Some(sort) if sort.column == field.name => "fa-sort-up",
+                            _ => "fa-sort has-text-grey-lighter",
+                        };
+                        let column = field.name.clone();
+
+                        html! {
+                            <th
+                                class=classes!("is-size-6", sortable.then(|| "is-clickable"))
+                                title=field.sql_type.clone()
+                                onclick=self.link.batch_callback(move |_| sortable.then(|| ViewTableMsg::SortBy(column.clone())))
+                            >
+                                {&field.name}
+                                {sortable.then(|| html! {
+                                    <span class="icon is-small ml-1">
+                                        <i class=classes!("fas", sort_icon)/>
+                                    </span>
+                                }).unwrap_or_default()}
+                                {field.meta.primary_key.then(|| html! {
+                                    <span class="icon is-small has-text-warning ml-1">
+                                        <i class="fas fa-key"/>
+                                    </span>
+                                }).unwrap_or_default()}
+                            </th>
+                        }
                     })
                     .collect();
 
@@ -196,15 +896,29 @@ impl ViewTable {
                         let rows = data
                             .table_fields
                             .iter()
-                            .map(|field| {
-                                let value: &String = field.values.get(i).unwrap();
-
-                                html! { <td class="is-size-7">{value}</td> }
+                            .zip(&column_keys)
+                            .map(|(field, key)| {
+                                let value = field.values.get(i).unwrap_or(&Value::Null);
+                                match key {
+                                    Some(key) if !value.is_null() => self.view_key_cell(value, &foreign_keys[*key], *key, i),
+                                    _ => view_cell(value),
+                                }
                             })
                             .collect::<Html>();
 
                         html! {
                             <tr>
+                                {referenced.then(|| html! {
+                                    <td class="is-size-7">
+                                        <span
+                                            class="icon is-small is-clickable has-text-info"
+                                            title="Referenced by"
+                                            onclick=self.link.callback(move |_| ViewTableMsg::ShowReferences(i))
+                                        >
+                                            <i class="fas fa-sitemap"/>
+                                        </span>
+                                    </td>
+                                }).unwrap_or_default()}
                                 {rows}
                             </tr>
                         }
@@ -216,6 +930,7 @@ impl ViewTable {
                         <table class="table is-bordered is-striped is-narrow is-hoverable is-fullwidth">
                             <thead>
                                 <tr>
+                                    {referenced.then(|| html! { <th></th> }).unwrap_or_default()}
                                     {titles}
                                 </tr>
                             </thead>
@@ -230,9 +945,437 @@ impl ViewTable {
         }
     }
 
+    // A link to the parent row of the `row`th row's `key`
+    fn view_key_cell(&self, value: &Value, key: &ForeignKey, index: usize, row: usize) -> Html {
+        html! {
+            <td class=classes!("is-size-7", value.is_numeric().then(|| "has-text-right"))>
+                <a
+                    title=format!("Open {} at {}", key.referenced_table.table, key.referenced_columns.join(", "))
+                    onclick=self.link.callback(move |_| ViewTableMsg::FollowForeignKey(index, row))
+                >
+                    {value}
+                </a>
+            </td>
+        }
+    }
+
+    // The first rows of each table referencing the chosen row
+    fn view_references(&self) -> Html {
+        let store = self.props.store.borrow();
+        let references = match store.row_references.as_ref() {
+            Some(references) => references,
+            None => return Html::default(),
+        };
+
+        let groups = references
+            .iter()
+            .enumerate()
+            .map(|(index, references)| {
+                let key = &references.foreign_key;
+                let total = references.data.page.map(|page| page.total).unwrap_or(references.data.count);
+                let titles = references
+                    .data
+                    .table_fields
+                    .iter()
+                    .map(|field| html! { <th class="is-size-7">{&field.name}</th> })
+                    .collect::<Html>();
+                let rows = (0..references.data.count)
+                    .map(|i| {
+                        let cells = references
+                            .data
+                            .table_fields
+                            .iter()
+                            .map(|field| view_cell(field.values.get(i).unwrap_or(&Value::Null)))
+                            .collect::<Html>();
+                        html! { <tr>{cells}</tr> }
+                    })
+                    .collect::<Html>();
+
+                html! {
+                    <div class="mb-3">
+                        <a
+                            class="is-size-7"
+                            onclick=self.link.batch_callback(move |_| (total > 0).then(|| ViewTableMsg::OpenReferences(index)))
+                        >
+                            <b>{&key.table.table}</b>
+                            {format!(" ({}) ", key.columns.join(", "))}
+                            <span class="tag is-info is-light">{format!("{} rows", format_count(total))}</span>
+                        </a>
+                        {if total > 0 {
+                            html! {
+                                <table class="table is-bordered is-narrow is-fullwidth mt-1">
+                                    <thead><tr>{titles}</tr></thead>
+                                    <tbody>{rows}</tbody>
+                                </table>
+                            }
+                        } else { Html::default() }}
+                    </div>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="row mt-2 box p-3 references-panel scrollable-all">
+                <div class="level mb-2">
+                    <div class="level-left">
+                        <b>{"Referenced by"}</b>
+                    </div>
+                    <div class="level-right">
+                        <button class="delete" onclick=self.link.callback(|_| ViewTableMsg::CloseReferences)/>
+                    </div>
+                </div>
+                {if references.is_empty() {
+                    html! { <span class="is-size-7 has-text-grey">{"No rows reference this one"}</span> }
+                } else { groups }}
+            </div>
+        }
+    }
+
+    // Columns of the table being browsed, filters only apply to table pages
+    fn filter_columns(&self) -> Vec<String> {
+        match self.props.store.borrow().shown_data() {
+            Some(data) if data.page.is_some() => data
+                .table_fields
+                .iter()
+                .map(|field| field.name.clone())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn view_filter_box(&self) -> Html {
+        let columns = self.filter_columns();
+        if !self.filter_box_open || columns.is_empty() {
+            return Html::default();
+        }
+
+        let column_options = columns
+            .iter()
+            .map(|column| {
+                html! {
+                    <option value=column.clone() selected=*column == self.filter_form.column>
+                        {column}
+                    </option>
+                }
+            })
+            .collect::<Html>();
+
+        let operator_options = FilterOperator::iter()
+            .map(|operator| {
+                html! {
+                    <option value=operator.to_string() selected=operator == self.filter_form.operator>
+                        {operator}
+                    </option>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="row mt-2">
+                <div class="field has-addons">
+                    <div class="control">
+                        <div class="select is-small">
+                            <select
+                                onchange=self.link.batch_callback(|change: ChangeData| match change {
+                                    ChangeData::Select(select) => Some(ViewTableMsg::FilterColumn(select.value())),
+                                    _ => None,
+                                })
+                            >
+                                {column_options}
+                            </select>
+                        </div>
+                    </div>
+                    <div class="control">
+                        <div class="select is-small">
+                            <select
+                                onchange=self.link.batch_callback(|change: ChangeData| match change {
+                                    ChangeData::Select(select) => select
+                                        .value()
+                                        .parse()
+                                        .ok()
+                                        .map(ViewTableMsg::FilterOperator),
+                                    _ => None,
+                                })
+                            >
+                                {operator_options}
+                            </select>
+                        </div>
+                    </div>
+                    <div class="control is-expanded">
+                        <input
+                            class="input is-small"
+                            type="text"
+                            placeholder="Value"
+                            disabled=!self.filter_form.operator.takes_value()
+                            value=self.filter_form.value.clone()
+                            oninput=self.link.callback(|input: InputData| ViewTableMsg::FilterValue(input.value))
+                        />
+                    </div>
+                    <div class="control">
+                        <button class="button is-small is-info" onclick=self.link.callback(|_| ViewTableMsg::AddFilter)>
+                            {"Add filter"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_active_filters(&self) -> Html {
+        let store = self.props.store.borrow();
+        if store.table_sort.is_none() && store.table_filters.is_empty() {
+            return Html::default();
+        }
+
+        let sort = match store.table_sort.as_ref() {
+            Some(sort) => html! {
+                <div class="control">
+                    <div class="tags has-addons">
+                        <span class="tag is-info is-light">
+                            {format!("ORDER BY {} {}", sort.column, if sort.descending { "DESC" } else { "ASC" })}
+                        </span>
+                        <a class="tag is-delete" onclick=self.link.callback(|_| ViewTableMsg::ClearSort)/>
+                    </div>
+                </div>
+            },
+            None => Html::default(),
+        };
+
+        let filters = store
+            .table_filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| {
+                html! {
+                    <div class="control">
+                        <div class="tags has-addons">
+                            <span class="tag is-warning is-light">{filter}</span>
+                            <a class="tag is-delete" onclick=self.link.callback(move |_| ViewTableMsg::RemoveFilter(index))/>
+                        </div>
+                    </div>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="row mt-2">
+                <div class="field is-grouped is-grouped-multiline">
+                    {sort}
+                    {filters}
+                </div>
+            </div>
+        }
+    }
+
+    // One tab per statement of the last query, the selected one's error above its rows
+    fn view_result_tabs(&self) -> Html {
+        let store = self.props.store.borrow();
+        if store.query_results.is_empty() {
+            return Html::default();
+        }
+
+        let tabs = store
+            .query_results
+            .iter()
+            .enumerate()
+            .map(|(index, result)| {
+                let (icon, timing) = match result.result.as_ref() {
+                    None => ("fa-spinner fa-pulse", String::new()),
+                    Some(result) if result.error.is_some() => ("fa-exclamation-circle has-text-danger", format!(" · {} ms", result.elapsed_ms)),
+                    Some(result) => ("fa-check has-text-success", format!(" · {} ms", result.elapsed_ms)),
+                };
+                let query = result.result.as_ref().map(|result| result.query.clone()).unwrap_or_default();
+
+                html! {
+                    <li class=classes!((index == store.selected_result).then(|| "is-active"))>
+                        <a title=query onclick=self.link.callback(move |_| ViewTableMsg::SelectResult(index))>
+                            <span class="icon is-small">
+                                <i class=classes!("fas", icon)/>
+                            </span>
+                            <span>{format!("Result {}{}", index + 1, timing)}</span>
+                        </a>
+                    </li>
+                }
+            })
+            .collect::<Html>();
+
+        // a cancelled statement is told apart below the rows
+        let error = match store.shown_result().and_then(|result| result.error.as_ref()) {
+            Some(error) if error.category != ErrorCategory::Cancelled => html! {
+                <div class="notification is-danger is-light py-2 px-3 mb-0">
+                    {view_api_error(error)}
+                </div>
+            },
+            _ => Html::default(),
+        };
+        let warnings = store
+            .shown_result()
+            .map(|result| view_warnings(&result.warnings))
+            .unwrap_or_default();
+
+        html! {
+            <div class="row mt-2">
+                <div class="tabs is-small mb-2">
+                    <ul>{tabs}</ul>
+                </div>
+                {error}
+                {warnings}
+            </div>
+        }
+    }
+
+    fn view_query_progress(&self) -> Html {
+        let store = self.props.store.borrow();
+        let progress = match store.query_progress.as_ref() {
+            Some(progress) => progress,
+            None => return Html::default(),
+        };
+
+        let summary = match progress.summary.as_ref() {
+            Some(summary) => summary,
+            None => return html! {
+                <div class="row mt-2 is-size-7">
+                    <div class="field is-grouped is-align-items-center">
+                        <span class="icon-text mr-3">
+                            <span class="icon"><i class="fas fa-spinner fa-pulse"/></span>
+                            <span>{format!("{} rows received", format_count(progress.rows))}</span>
+                        </span>
+                        <button
+                            class="button is-small is-danger is-light"
+                            title="Cancel query (Esc)"
+                            onclick=self.link.callback(|_| ViewTableMsg::CancelQuery)
+                        >
+                            <span class="icon is-small"><i class="fas fa-stop"/></span>
+                            <span>{"Cancel"}</span>
+                        </button>
+                    </div>
+                </div>
+            },
+        };
+
+        let status = match store.shown_result() {
+            _ if progress.cancelled => html! {
+                <span class="icon-text has-text-grey">
+                    <span class="icon"><i class="fas fa-ban"/></span>
+                    <span>{format!("Query cancelled after {} rows", format_count(progress.rows))}</span>
+                </span>
+            },
+            Some(result) if result.truncated => html! {
+                <span class="icon-text has-text-warning-dark">
+                    <span class="icon"><i class="fas fa-exclamation-triangle"/></span>
+                    <span>
+                        {format!("Result truncated, only the first {} rows were kept", format_count(result.rows))}
+                    </span>
+                </span>
+            },
+            Some(result) => match result.affected_rows {
+                Some(affected) => html! { {format!("{} rows affected in {} ms", format_count(affected as usize), result.elapsed_ms)} },
+                None => html! { {format!("{} rows in {} ms", format_count(result.rows), result.elapsed_ms)} },
+            },
+            None => Html::default(),
+        };
+
+        // totals for scripts, a single statement's are in its status
+        let totals = match (summary.statements, summary.failed) {
+            (0..=1, _) => Html::default(),
+            (statements, 0) => html! {
+                <span class="has-text-grey ml-3">{format!("{} statements in {} ms", statements, summary.elapsed_ms)}</span>
+            },
+            (statements, failed) => html! {
+                <span class="has-text-grey ml-3">{format!("{} statements in {} ms, {} failed", statements, summary.elapsed_ms, failed)}</span>
+            },
+        };
+
+        html! {
+            <div class="row mt-2 is-size-7">
+                {status}
+                {totals}
+            </div>
+        }
+    }
+
+    fn view_pagination(&self) -> Html {
+        let page = match self.props.store.borrow().shown_data().and_then(|data| data.page) {
+            Some(page) => page,
+            None => return Html::default(),
+        };
+
+        let button = |icon: &str, title: &str, enabled: bool, offset: usize| {
+            html! {
+                <button
+                    class="button is-small"
+                    title=title.to_string()
+                    disabled=!enabled
+                    onclick=self.link.callback(move |_| ViewTableMsg::LoadPage(offset))
+                >
+                    <span class="icon is-small">
+                        <i class=classes!("fas", icon.to_string())/>
+                    </span>
+                </button>
+            }
+        };
+
+        let sizes = PAGE_SIZES
+            .iter()
+            .map(|size| {
+                html! {
+                    <option value=size.to_string() selected=*size == page.page_size>
+                        {size}
+                    </option>
+                }
+            })
+            .collect::<Html>();
+
+        let first_row = if page.total > 0 { page.offset + 1 } else { 0 };
+        let last_row = (page.offset + page.page_size).min(page.total);
+
+        html! {
+            <div class="row mt-2">
+                <div class="level is-mobile">
+                    <div class="level-left">
+                        <div class="level-item">
+                            <div class="buttons has-addons">
+                                {button("fa-angle-double-left", "First page", page.has_previous(), 0)}
+                                {button("fa-angle-left", "Previous page", page.has_previous(), page.previous_offset())}
+                                <button class="button is-small is-static">
+                                    {format!("Page {} of {}", page.number(), page.page_count())}
+                                </button>
+                                {button("fa-angle-right", "Next page", page.has_next(), page.next_offset())}
+                                {button("fa-angle-double-right", "Last page", page.has_next(), page.last_offset())}
+                            </div>
+                        </div>
+                        <div class="level-item is-size-7">
+                            {format!("Rows {}-{} of {}", format_count(first_row), format_count(last_row), format_count(page.total))}
+                        </div>
+                    </div>
+                    <div class="level-right">
+                        <div class="level-item">
+                            <div class="select is-small">
+                                <select
+                                    title="Rows per page"
+                                    onchange=self.link.batch_callback(|change: ChangeData| match change {
+                                        ChangeData::Select(select) => select
+                                            .value()
+                                            .parse()
+                                            .ok()
+                                            .map(ViewTableMsg::SetPageSize),
+                                        _ => None,
+                                    })
+                                >
+                                    {sizes}
+                                </select>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
     fn view_toolbar(&self) -> Html {
         html! {
             <div class="columns is-mobile float-right">
+                {self.query_box_open.then(|| self.view_target_picker()).unwrap_or_default()}
                 <div class="column is-narrow">
                     <button onclick=&self.toggle_query_box_open
                         class=classes!("button", self.query_box_open.then(||"is-info"))
@@ -241,12 +1384,30 @@ impl ViewTable {
                     </button>
                 </div>
                 <div class="column is-narrow">
-                    <button class="button">
+                    <button
+                        onclick=self.link.callback(|_| ViewTableMsg::ToggleContinueOnError)
+                        class=classes!("button", self.props.store.borrow().continue_on_error.then(||"is-info"))
+                        title="Keep running a script after a statement fails"
+                    >
+                        <i class="is-medium fas fa-forward"/>
+                    </button>
+                </div>
+                {self.view_transaction_controls()}
+                <div class="column is-narrow">
+                    <button
+                        onclick=self.link.callback(|_| ViewTableMsg::ToggleFilterBoxOpen)
+                        class=classes!("button", self.filter_box_open.then(||"is-info"))
+                        title="Filter rows"
+                    >
                         <i class="is-medium fas fa-search"/>
                     </button>
                 </div>
                 <div class="column is-narrow">
-                    <button class="button">
+                    <button
+                        onclick=self.link.callback(|_| ViewTableMsg::ToggleStructure)
+                        class=classes!("button", self.props.store.borrow().show_structure.then(||"is-info"))
+                        title="Table structure"
+                    >
                         <i class="is-medium fas fa-table"/>
                     </button>
                 </div>
@@ -254,6 +1415,89 @@ impl ViewTable {
         }
     }
 
+    // Where the editor's queries run, unqualified names resolve against it
+    fn view_target_picker(&self) -> Html {
+        let store = self.props.store.borrow();
+        let target = store.query_target();
+        let options = store
+            .query_targets()
+            .into_iter()
+            .enumerate()
+            .map(|(index, option)| html! {
+                <option value=index.to_string() selected=Some(&option) == target.as_ref()>
+                    {option.label()}
+                </option>
+            })
+            .collect::<Html>();
+
+        html! {
+            <div class="column is-narrow">
+                <div class="select">
+                    <select
+                        title="Database the editor's queries run in"
+                        onchange=self.link.batch_callback(|change: ChangeData| match change {
+                            ChangeData::Select(select) => select
+                                .value()
+                                .parse()
+                                .ok()
+                                .map(ViewTableMsg::SelectQueryTarget),
+                            _ => None,
+                        })
+                    >
+                        {options}
+                    </select>
+                </div>
+            </div>
+        }
+    }
+
+    // Autocommit toggle, plus commit and rollback while statements wait on them
+    fn view_transaction_controls(&self) -> Html {
+        let transaction = self.props.store.borrow().transaction.clone();
+        let pending = html! {
+            <>
+                <div class="column is-narrow">
+                    <span class="tag is-warning is-medium" title="Uncommitted changes are rolled back if the connection closes">
+                        {"Transaction open"}
+                    </span>
+                </div>
+                <div class="column is-narrow">
+                    <div class="buttons has-addons">
+                        <button
+                            onclick=self.link.callback(|_| ViewTableMsg::EndTransaction(true))
+                            class="button is-success"
+                            title="Commit"
+                        >
+                            <i class="is-medium fas fa-check"/>
+                        </button>
+                        <button
+                            onclick=self.link.callback(|_| ViewTableMsg::EndTransaction(false))
+                            class="button is-danger"
+                            title="Rollback"
+                        >
+                            <i class="is-medium fas fa-undo"/>
+                        </button>
+                    </div>
+                </div>
+            </>
+        };
+
+        html! {
+            <>
+                {transaction.open.then(|| pending).unwrap_or_default()}
+                <div class="column is-narrow">
+                    <button
+                        onclick=self.link.callback(|_| ViewTableMsg::ToggleAutocommit)
+                        class=classes!("button", transaction.autocommit.then(||"is-info"))
+                        title=if transaction.autocommit { "Autocommit on, each statement is committed as it runs" } else { "Autocommit off, statements wait for a commit" }
+                    >
+                        <i class="is-medium fas fa-check-double"/>
+                    </button>
+                </div>
+            </>
+        }
+    }
+
     fn view_query_box(&self) -> Html {
         let hide = !self.query_box_open;
 
@@ -276,3 +1520,107 @@ impl ViewTable {
         }
     }
 }
+
+static PAGE_SIZES: &[usize] = &[24, 50, 100, 250, 500, 1000];
+
+// Long binary values are cut, the grid is not the place to read them
+static MAX_BYTES_SHOWN: usize = 32;
+
+// What a statement without a result set did
+fn view_statement_summary(result: &StatementResult) -> Html {
+    let detail = |label: &str, value: String| html! {
+        <div class="control">
+            <div class="tags has-addons">
+                <span class="tag is-dark">{label}</span>
+                <span class="tag is-success is-light">{value}</span>
+            </div>
+        </div>
+    };
+
+    html! {
+        <div class="notification is-light py-2 px-3">
+            <p class="mb-2 is-size-7 is-family-monospace">{&result.query}</p>
+            <div class="field is-grouped is-grouped-multiline">
+                {result.affected_rows.map(|rows| detail("Affected Rows", format_count(rows as usize))).unwrap_or_default()}
+                {result.last_insert_id.map(|id| detail("Insert Id", id.to_string())).unwrap_or_default()}
+                {detail("Warnings", result.warnings.len().to_string())}
+                {detail("Time", format!("{} ms", result.elapsed_ms))}
+            </div>
+            {result.info.as_ref().map(|info| html! { <p class="is-size-7">{info}</p> }).unwrap_or_default()}
+        </div>
+    }
+}
+
+fn view_warnings(warnings: &[StatementWarning]) -> Html {
+    if warnings.is_empty() {
+        return Html::default();
+    }
+
+    let rows = warnings
+        .iter()
+        .map(|warning| {
+            html! {
+                <tr>
+                    <td>{&warning.level}</td>
+                    <td>{warning.code}</td>
+                    <td>{&warning.message}</td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="notification is-warning is-light py-2 px-3 mb-0">
+            <table class="table is-narrow is-size-7 is-fullwidth has-background-warning-light">
+                <thead>
+                    <tr>
+                        <th>{"Level"}</th>
+                        <th>{"Code"}</th>
+                        <th>{"Message"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {rows}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+// Equal filters on `filtered` for the values `columns` hold in the `row`th row, None when one is NULL
+fn row_filters(data: &TableData, row: usize, columns: &[String], filtered: &[String]) -> Option<Vec<TableFilter>> {
+    columns
+        .iter()
+        .zip(filtered)
+        .map(|(column, filtered)| {
+            let field = data.table_fields.iter().find(|field| field.name == *column)?;
+            match field.values.get(row)? {
+                Value::Null => None,
+                value => Some(TableFilter::equal(filtered.clone(), value)),
+            }
+        })
+        .collect()
+}
+
+fn view_cell(value: &Value) -> Html {
+    match value {
+        Value::Null => html! {
+            <td class="is-size-7 has-text-grey-light is-italic">{"NULL"}</td>
+        },
+        Value::Bytes(bytes) if bytes.len() > MAX_BYTES_SHOWN => {
+            let shown = Value::Bytes(bytes[..MAX_BYTES_SHOWN].to_vec());
+            html! {
+                <td class="is-size-7 is-family-monospace" title=format!("{} bytes", bytes.len())>
+                    {format!("{}…", shown)}
+                </td>
+            }
+        }
+        Value::Bytes(_) => html! {
+            <td class="is-size-7 is-family-monospace">{value}</td>
+        },
+        value if value.is_numeric() => html! {
+            <td class="is-size-7 has-text-right">{value}</td>
+        },
+        value => html! { <td class="is-size-7">{value}</td> },
+    }
+}
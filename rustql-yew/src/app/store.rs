@@ -1,27 +1,246 @@
-use crate::app::Msg;
-use rustql_types::ApiRequest;
-use rustql_types::{Database, TableData};
-use yew::{Callback, Component, ComponentLink};
+use crate::app::{helpers::functions::warn_on_close, Msg};
+use rustql_types::{
+    ConnectionProfile, Database, DefinitionRequest, DescribeTableRequest, DriverKind, ExportGraphRequest, GraphExport,
+    GraphFocus, GraphFormat, GraphLayout, LoadReferencesRequest, LoadTableRequest, ObjectDefinition, Point,
+    ProfileTestResult, QueryChunk, QueryParams, QuerySummary, Request, RowReferences, SchemaGraph, SchemaGraphRequest, SchemaObject, StatementResult,
+    TableData, TableDescription, TableFilter, TableRef, TableSort, TransactionStatus, Value,
+    DEFAULT_PAGE_SIZE,
+};
+use yew::{
+    format::Json,
+    services::storage::{Area, StorageService},
+    Callback, Component, ComponentLink,
+};
+
+static QUERY_PARAMS_KEY: &str = "rustql.query_params";
+// Followed by the profile, database and schema the diagram's box positions belong to
+static GRAPH_LAYOUT_KEY: &str = "rustql.erd";
 
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct AppStore {
-    pub socket_link: Callback<ApiRequest>,
+    pub socket_link: Callback<Request>,
+    pub profiles: Vec<ConnectionProfile>,
+    pub active_profile: Option<String>,
+    pub profile_test: Option<ProfileTestResult>,
+    pub show_profiles: bool,
     pub databases: Vec<Database>,
     pub selected_db: Option<String>,
+    pub selected_schema: Option<String>,
     pub selected_table: Option<String>,
+    // picked in the editor toolbar, None follows the sidebar's selection
+    pub editor_target: Option<QueryTarget>,
     pub table_data: Option<TableData>,
-    pub default_limit: i32,
+    // the selected table's columns, shown instead of its rows while the structure tab is open
+    pub show_structure: bool,
+    pub table_description: Option<TableDescription>,
+    // rows of other tables pointing at a row of the table page, shown in a panel below it
+    pub row_references: Option<Vec<RowReferences>>,
+    // the selected database's diagram is shown instead of a table
+    pub show_diagram: bool,
+    pub schema_graph: Option<SchemaGraph>,
+    // box positions the user dragged the diagram's tables to, kept per database
+    pub graph_layout: GraphLayout,
+    // the last export, shown in a dialog until it is closed
+    pub graph_export: Option<GraphExport>,
+    // the CREATE statement of a view, routine, trigger or event, shown over the page
+    pub object_definition: Option<ObjectDefinition>,
+    pub page_size: usize,
+    // applied by the api when browsing the selected table
+    pub table_sort: Option<TableSort>,
+    pub table_filters: Vec<TableFilter>,
+    // set while the rows shown come from a query rather than a table page
+    pub query_progress: Option<QueryProgress>,
+    // one per statement of the last query, shown as result tabs instead of the table page
+    pub query_results: Vec<QueryResult>,
+    pub selected_result: usize,
+    pub continue_on_error: bool,
+    // the placeholder values last run with, offered again by the next prompt
+    pub query_params: QueryParams,
+    // of the session's connection the editor's queries run on
+    pub transaction: TransactionStatus,
+}
+
+// The database, and schema for backends with them, the editor's queries run in
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct QueryTarget {
+    pub db: String,
+    pub schema: Option<String>,
+}
+
+impl QueryTarget {
+    pub fn label(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", self.db, schema),
+            None => self.db.clone(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct QueryProgress {
+    // received so far, over all statements
+    pub rows: usize,
+    // set once every statement ran
+    pub summary: Option<QuerySummary>,
+    // stopped by a Cancel before it finished
+    pub cancelled: bool,
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct QueryResult {
+    pub data: Option<TableData>,
+    // None while the statement runs
+    pub result: Option<StatementResult>,
 }
 
 impl AppStore {
     pub fn new() -> Self {
         Self {
             socket_link: Callback::default(),
+            profiles: Vec::new(),
+            active_profile: None,
+            profile_test: None,
+            show_profiles: false,
             databases: Vec::new(),
             selected_db: None,
+            selected_schema: None,
             selected_table: None,
+            editor_target: None,
             table_data: None,
-            default_limit: 24
+            show_structure: false,
+            table_description: None,
+            row_references: None,
+            show_diagram: false,
+            schema_graph: None,
+            graph_layout: GraphLayout::new(),
+            graph_export: None,
+            object_definition: None,
+            page_size: DEFAULT_PAGE_SIZE,
+            table_sort: None,
+            table_filters: Vec::new(),
+            query_progress: None,
+            query_results: Vec::new(),
+            selected_result: 0,
+            continue_on_error: false,
+            query_params: stored_query_params(),
+            transaction: TransactionStatus::default(),
+        }
+    }
+
+    pub fn set_transaction(&mut self, transaction: TransactionStatus) {
+        warn_on_close(transaction.open);
+        self.transaction = transaction;
+    }
+
+    // Keeps the values for the next prompt, named ones from earlier queries are kept too
+    pub fn remember_query_params(&mut self, params: &QueryParams) {
+        self.query_params.named.extend(params.named.clone());
+        self.query_params.positional = params.positional.clone();
+
+        if let Ok(mut storage) = StorageService::new(Area::Local) {
+            storage.store(QUERY_PARAMS_KEY, Json(&self.query_params));
+        }
+    }
+
+    // Adds a batch of rows to its statement's result, the first batch replaces it.
+    // The newest statement is shown as the script runs
+    pub fn append_query_rows(&mut self, chunk: QueryChunk) {
+        let result = self.statement_result(chunk.statement);
+        match result.data.as_mut() {
+            Some(data) if chunk.offset > 0 => {
+                for (field, rows) in data.table_fields.iter_mut().zip(chunk.data.table_fields) {
+                    field.values.extend(rows.values);
+                }
+                data.count += chunk.data.count;
+            }
+            _ => result.data = Some(chunk.data),
+        }
+
+        self.query_progress = Some(QueryProgress {
+            rows: self
+                .query_results
+                .iter()
+                .filter_map(|result| result.data.as_ref())
+                .map(|data| data.count)
+                .sum(),
+            ..Default::default()
+        });
+    }
+
+    pub fn finish_statement(&mut self, result: StatementResult) {
+        let statement = result.statement;
+        self.statement_result(statement).result = Some(result);
+    }
+
+    fn statement_result(&mut self, statement: usize) -> &mut QueryResult {
+        if statement >= self.query_results.len() {
+            self.query_results.resize(statement + 1, QueryResult::default());
+            self.selected_result = statement;
+        }
+        &mut self.query_results[statement]
+    }
+
+    // Rows on screen, the selected result of the last query or else the table page
+    pub fn shown_data(&self) -> Option<&TableData> {
+        match self.query_results.get(self.selected_result) {
+            Some(result) => result.data.as_ref(),
+            None => self.table_data.as_ref(),
+        }
+    }
+
+    pub fn shown_result(&self) -> Option<&StatementResult> {
+        self.query_results
+            .get(self.selected_result)
+            .and_then(|result| result.result.as_ref())
+    }
+
+    pub fn query_running(&self) -> bool {
+        self.query_progress
+            .as_ref()
+            .map_or(false, |progress| progress.summary.is_none())
+    }
+
+    // Stops the running query, the api answers it with QueryCancelled
+    pub fn cancel_query(&self) {
+        if self.query_running() {
+            self.socket_send(Request::Cancel);
+        }
+    }
+
+    pub fn query_target(&self) -> Option<QueryTarget> {
+        self.editor_target.clone().or_else(|| {
+            self.selected_db.clone().map(|db| QueryTarget {
+                db,
+                schema: self.selected_schema.clone(),
+            })
+        })
+    }
+
+    // Every database, or schema of one, the editor can run in
+    pub fn query_targets(&self) -> Vec<QueryTarget> {
+        self.databases
+            .iter()
+            .flat_map(|database| match database.schemas.is_empty() {
+                true => vec![QueryTarget {
+                    db: database.name.clone(),
+                    schema: None,
+                }],
+                false => database
+                    .schemas
+                    .iter()
+                    .map(|schema| QueryTarget {
+                        db: database.name.clone(),
+                        schema: Some(schema.name.clone()),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    // A `USE` in the last script moved the editor to another database
+    pub fn follow_used_db(&mut self, db: String) {
+        if !db.is_empty() && self.query_target().map(|target| target.db) != Some(db.clone()) {
+            self.editor_target = Some(QueryTarget { db, schema: None });
         }
     }
 
@@ -33,14 +252,165 @@ impl AppStore {
         self.selected_table.clone()
     }
 
+    pub fn active_driver(&self) -> DriverKind {
+        self.profiles
+            .iter()
+            .find(|profile| Some(&profile.name) == self.active_profile.as_ref())
+            .map(|profile| profile.driver)
+            .unwrap_or_default()
+    }
+
+    // Selected table quoted for the active backend, qualified by its schema when it has one
+    pub fn qualified_table(&self) -> String {
+        let driver = self.active_driver();
+        let parent = self.selected_schema.as_ref().or(self.selected_db.as_ref());
+
+        format!(
+            "{}.{}",
+            driver.quote_identifier(parent.unwrap()),
+            driver.quote_identifier(self.selected_table.as_ref().unwrap())
+        )
+    }
+
     pub fn default_query(&self) -> String {
-        format!("SELECT * FROM {}.{} \nLIMIT {}", 
-            self.selected_db.as_ref().unwrap(),
-            self.selected_table.as_ref().unwrap(),
-            self.default_limit
+        format!("SELECT * FROM {} \nLIMIT {}", 
+            self.qualified_table(),
+            self.page_size
+        )
+    }
+
+    // Asks the api for a page of the selected table, rows from `offset` on
+    pub fn load_page(&self, offset: usize) {
+        if let (Some(db), Some(table)) = (self.get_db(), self.get_table()) {
+            self.socket_send(Request::LoadTable(LoadTableRequest {
+                db,
+                schema: self.selected_schema.clone(),
+                table,
+                offset,
+                page_size: self.page_size,
+                sort: self.table_sort.clone(),
+                filters: self.table_filters.clone(),
+            }));
+        }
+    }
+
+    // Browses a table from its first page, its description is needed for the foreign key links
+    pub fn open_table(&mut self, table: TableRef, filters: Vec<TableFilter>) {
+        self.selected_db = Some(table.db);
+        self.selected_schema = table.schema;
+        self.selected_table = Some(table.table);
+        self.show_diagram = false;
+        self.table_sort = None;
+        self.table_filters = filters;
+        self.table_description = None;
+        self.row_references = None;
+        self.load_page(0);
+        self.describe_table();
+    }
+
+    // Shows the diagram of a database, or one of its schemas, with the positions saved for it
+    pub fn open_diagram(&mut self, db: String, schema: Option<String>) {
+        self.selected_db = Some(db.clone());
+        self.selected_schema = schema.clone();
+        self.selected_table = None;
+        self.show_diagram = true;
+        self.schema_graph = None;
+        self.graph_export = None;
+        self.graph_layout = StorageService::new(Area::Local)
+            .ok()
+            .and_then(|storage| {
+                let Json(layout) = storage.restore::<Json<anyhow::Result<GraphLayout>>>(&self.graph_layout_key());
+                layout.ok()
+            })
+            .unwrap_or_default();
+        self.socket_send(Request::LoadSchemaGraph(SchemaGraphRequest { db, schema }));
+    }
+
+    pub fn move_graph_table(&mut self, table: String, position: Point) {
+        self.graph_layout.insert(table, position);
+    }
+
+    pub fn save_graph_layout(&self) {
+        if let Ok(mut storage) = StorageService::new(Area::Local) {
+            storage.store(&self.graph_layout_key(), Json(&self.graph_layout));
+        }
+    }
+
+    // Forgets the dragged positions, the tables go back on the grid
+    pub fn reset_graph_layout(&mut self) {
+        self.graph_layout.clear();
+        if let Ok(mut storage) = StorageService::new(Area::Local) {
+            storage.remove(&self.graph_layout_key());
+        }
+    }
+
+    fn graph_layout_key(&self) -> String {
+        format!(
+            "{}.{}.{}.{}",
+            GRAPH_LAYOUT_KEY,
+            self.active_profile.clone().unwrap_or_default(),
+            self.selected_db.clone().unwrap_or_default(),
+            self.selected_schema.clone().unwrap_or_default()
         )
     }
 
+    // The api renders the export from the schema as it is now, laid out as on screen
+    pub fn export_graph(&self, format: GraphFormat, focus: Option<GraphFocus>, layout: GraphLayout) {
+        if let Some(db) = self.get_db() {
+            self.socket_send(Request::ExportSchemaGraph(ExportGraphRequest {
+                db,
+                schema: self.selected_schema.clone(),
+                format,
+                focus,
+                layout,
+            }));
+        }
+    }
+
+    pub fn load_definition(&self, db: String, schema: Option<String>, object: SchemaObject) {
+        self.socket_send(Request::LoadDefinition(DefinitionRequest {
+            db,
+            schema,
+            name: object.name,
+            kind: object.kind,
+        }));
+    }
+
+    // Asks for the rows referencing the `row`th row of the table page
+    pub fn load_references(&self, row: usize) {
+        let data = match self.table_data.as_ref() {
+            Some(data) => data,
+            None => return,
+        };
+        let values = data
+            .table_fields
+            .iter()
+            .filter_map(|field| match field.values.get(row) {
+                Some(Value::Null) | None => None,
+                Some(value) => Some((field.name.clone(), value.clone())),
+            })
+            .collect();
+
+        if let (Some(db), Some(table)) = (self.get_db(), self.get_table()) {
+            self.socket_send(Request::LoadReferences(LoadReferencesRequest {
+                db,
+                schema: self.selected_schema.clone(),
+                table,
+                values,
+            }));
+        }
+    }
+
+    pub fn describe_table(&self) {
+        if let (Some(db), Some(table)) = (self.get_db(), self.get_table()) {
+            self.socket_send(Request::DescribeTable(DescribeTableRequest {
+                db,
+                schema: self.selected_schema.clone(),
+                table,
+            }));
+        }
+    }
+
     pub fn set_socket_link<T: Component>(&mut self, link: ComponentLink<T>)
     where
         <T as yew::Component>::Message: From<Msg>,
@@ -48,7 +418,17 @@ impl AppStore {
         self.socket_link = link.callback(|request| Msg::SocketSend(request))
     }
 
-    pub fn socket_send(&self, msg: ApiRequest) {
+    pub fn socket_send(&self, msg: Request) {
         self.socket_link.emit(msg);
     }
 }
+
+fn stored_query_params() -> QueryParams {
+    StorageService::new(Area::Local)
+        .ok()
+        .and_then(|storage| {
+            let Json(params) = storage.restore::<Json<anyhow::Result<QueryParams>>>(QUERY_PARAMS_KEY);
+            params.ok()
+        })
+        .unwrap_or_default()
+}